@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+
+// full-screen black overlay that fades the screen out, optionally switches state, then fades back in
+//
+// this is a ui node rather than a mesh like the intro's "screen" overlay so that it covers the
+// window no matter where the camera is, and survives the intro's cleanup of sprites and meshes
+#[derive(Component)]
+pub struct Fade {
+    timer: Timer,
+    out: bool,
+    next: Option<AppState>,
+}
+
+// sent once a fade reaches full black, right before it starts fading back in
+// carries the state the fade switched to, if any
+#[derive(Event)]
+pub struct FadedOut(pub Option<AppState>);
+
+// fade to black over dur seconds, switch to next (if any), then fade back in over dur seconds
+// callers should check there isn't a Fade already running
+pub fn fade_transition(commands: &mut Commands, dur: f32, next: Option<AppState>) {
+    commands.spawn((
+        Fade {
+            timer: Timer::from_seconds(dur, TimerMode::Once),
+            out: true,
+            next,
+        },
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.).into(),
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_fade(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fades: Query<(Entity, &mut Fade, &mut BackgroundColor)>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut faded_out: EventWriter<FadedOut>,
+) {
+    for (e, mut fade, mut bg) in &mut fades {
+        fade.timer.tick(time.delta());
+        let s = fade.timer.fraction();
+        bg.0.set_a(if fade.out { s } else { 1. - s });
+        if !fade.timer.finished() {
+            continue;
+        }
+        if fade.out {
+            // the state change happens before the next Update, so the fade in covers its OnEnter
+            let next = fade.next.take();
+            if let Some(next) = &next {
+                next_state.set(next.clone());
+            }
+            faded_out.send(FadedOut(next));
+            fade.out = false;
+            fade.timer.reset();
+        } else {
+            commands.entity(e).despawn();
+        }
+    }
+}
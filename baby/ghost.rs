@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+
+use crate::intro::GameTime;
+use crate::level::{Control, End, GameSet, Layer, PlayerSize};
+use crate::AppState;
+
+// BABY_GHOST=<file> races the best run saved in <file>: a see-through baby follows the path the
+// player took on that run, and finishing faster than it replaces the file with the new run
+pub struct GhostPlugin;
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(path) = std::env::var("BABY_GHOST") else {
+            return;
+        };
+        let best = match std::fs::read_to_string(&path) {
+            Ok(text) => match Run::parse(&text) {
+                Ok(run) => Some(run),
+                Err(e) => {
+                    eprintln!("can't read ghost {path:?}: {e}, starting without one");
+                    None
+                }
+            },
+            // no best run yet
+            Err(_) => None,
+        };
+        app.insert_resource(Ghosts {
+            path,
+            best,
+            current: Run::default(),
+        })
+        .add_systems(OnEnter(AppState::Game), spawn_ghost)
+        .add_systems(
+            Update,
+            (record_run, play_ghost, finish_run)
+                .after(GameSet::Move)
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+// player positions every Run::SAMPLE seconds of game time, from the start to the win
+#[derive(Default, Clone)]
+pub struct Run {
+    pub time: f32,
+    pub path: Vec<Vec2>,
+}
+
+impl Run {
+    const SAMPLE: f32 = 1. / 30.;
+
+    // where the player was t seconds in, between the samples either side of it
+    // None once the run is over
+    pub fn at(&self, t: f32) -> Option<Vec2> {
+        let i = t / Self::SAMPLE;
+        let (a, b) = (self.path.get(i as usize)?, self.path.get(i as usize + 1));
+        Some(b.map_or(*a, |b| a.lerp(*b, i.fract())))
+    }
+
+    // the total time on the first line, then an `x y` line per sample
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\n", self.time);
+        for p in &self.path {
+            out += &format!("{} {}\n", p.x, p.y);
+        }
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Run, String> {
+        let mut lines = text.lines();
+        let time = (lines.next().ok_or("empty run")?.trim())
+            .parse()
+            .map_err(|e| format!("bad run time: {e}"))?;
+        let path = (lines.enumerate())
+            .map(|(i, l)| {
+                let (x, y) = (l.split_once(' ')).ok_or(format!("line {} isn't `x y`", i + 2))?;
+                let num = |s: &str| s.trim().parse::<f32>().map_err(|e| format!("{s:?}: {e}"));
+                Ok(Vec2::new(num(x)?, num(y)?))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Run { time, path })
+    }
+}
+
+#[derive(Resource)]
+pub struct Ghosts {
+    path: String,
+    best: Option<Run>,
+    current: Run,
+}
+
+#[derive(Component)]
+pub struct Ghost;
+
+fn spawn_ghost(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    ghosts: Res<Ghosts>,
+    size: Res<PlayerSize>,
+) {
+    let Some(start) = ghosts.best.as_ref().and_then(|run| run.at(0.)) else {
+        return;
+    };
+    commands.spawn((
+        Ghost,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1., 1., 1., 0.4),
+                custom_size: Some(Vec2::new(1.2, 1.4)),
+                ..default()
+            },
+            // just behind the player
+            transform: Transform {
+                translation: start.extend(Layer::Ghost.z()),
+                scale: size.0.extend(1.),
+                ..default()
+            },
+            texture: assets.load("baby.png"),
+            ..default()
+        },
+    ));
+}
+
+pub fn record_run(
+    mut ghosts: ResMut<Ghosts>,
+    time: Res<GameTime>,
+    end: Res<End>,
+    player: Query<&Transform, With<Control>>,
+) {
+    let Ok(t) = player.get_single() else {
+        return;
+    };
+    if end.0 {
+        return;
+    }
+    let run = &mut ghosts.current;
+    run.time += time.delta_seconds();
+    // a sample for every SAMPLE passed, the same spot if a frame covered more than one
+    while run.path.len() as f32 * Run::SAMPLE <= run.time {
+        run.path.push(t.translation.xy());
+    }
+}
+
+pub fn play_ghost(
+    ghosts: Res<Ghosts>,
+    mut ghost: Query<(&mut Transform, &mut Visibility), With<Ghost>>,
+) {
+    let Some(best) = &ghosts.best else {
+        return;
+    };
+    for (mut t, mut vis) in &mut ghost {
+        match best.at(ghosts.current.time) {
+            Some(pos) => t.translation = pos.extend(t.translation.z),
+            // it already won
+            None => *vis = Visibility::Hidden,
+        }
+    }
+}
+
+pub fn finish_run(mut ghosts: ResMut<Ghosts>, end: Res<End>) {
+    if !end.is_changed() || !end.0 {
+        return;
+    }
+    let time = ghosts.current.time;
+    if ghosts.best.as_ref().is_some_and(|best| best.time <= time) {
+        return;
+    }
+    info!("new best run {time:.2}s");
+    if let Err(e) = std::fs::write(&ghosts.path, ghosts.current.to_text()) {
+        eprintln!("can't save ghost {:?}: {e}", ghosts.path);
+    }
+    ghosts.best = Some(ghosts.current.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn ghost_follows_between_the_recorded_samples() {
+        // samples a thirtieth of a second apart
+        let best = Run::parse("0.1\n0 0\n10 0\n10 20\n").unwrap();
+        assert_eq!(Run::parse(&best.to_text()).unwrap().path, best.path);
+        let mut world = World::new();
+        let ghost = world
+            .spawn((Ghost, Transform::default(), Visibility::Visible))
+            .id();
+        let at = |world: &mut World, time: f32| {
+            world.insert_resource(Ghosts {
+                path: String::new(),
+                best: Some(best.clone()),
+                current: Run { time, path: vec![] },
+            });
+            world.run_system_once(play_ghost);
+            let t = world.get::<Transform>(ghost).unwrap().translation.xy();
+            (t, *world.get::<Visibility>(ghost).unwrap())
+        };
+        let (pos, vis) = at(&mut world, 0.25 * Run::SAMPLE);
+        assert!((pos - Vec2::new(2.5, 0.)).length() < 1e-3, "{pos}");
+        assert_eq!(vis, Visibility::Visible);
+        let (pos, _) = at(&mut world, 1.5 * Run::SAMPLE);
+        assert!((pos - Vec2::new(10., 10.)).length() < 1e-3, "{pos}");
+        // once the run is over the ghost has already won
+        let (pos, vis) = at(&mut world, 3.5 * Run::SAMPLE);
+        assert!((pos - Vec2::new(10., 10.)).length() < 1e-3, "{pos}");
+        assert_eq!(vis, Visibility::Hidden);
+    }
+}
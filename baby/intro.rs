@@ -1,31 +1,50 @@
 use bevy::{
     app::AppExit,
+    asset::LoadState,
     audio::PlaybackMode,
     prelude::*,
-    render::camera::ScalingMode,
+    render::{camera::ScalingMode, view::screenshot::ScreenshotManager},
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    time::TimeUpdateStrategy,
+    window::{PrimaryWindow, WindowFocused},
 };
 use std::collections::HashMap as Map;
+use std::path::PathBuf;
 
+use crate::fade::{fade_transition, Fade, FadedOut};
+use crate::map::Level;
 use crate::AppState;
 
 #[derive(Component, Default)]
 pub struct DebugUi {
-    text: Map<&'static str, String>,
+    // in the order they were first watched, so the overlay lines don't jump around
+    text: Vec<(&'static str, String)>,
+    // text changed since the overlay was last rebuilt
+    dirty: bool,
 }
 
 impl DebugUi {
     fn watch(&mut self, key: &'static str, val: impl std::fmt::Debug) {
-        self.text.insert(key, format!("{:?}", val));
+        let val = format!("{:?}", val);
+        match self.text.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) if *v == val => return,
+            Some((_, v)) => *v = val,
+            None => self.text.push((key, val)),
+        }
+        self.dirty = true;
     }
 }
 
-pub fn draw_debug(mut dbg: Query<(&mut Text, &DebugUi)>) {
+pub fn draw_debug(mut dbg: Query<(&mut Text, &mut DebugUi)>) {
     if cfg!(debug_assertions) {
-        let (mut txt, dbg) = dbg.single_mut();
+        let (mut txt, mut dbg) = dbg.single_mut();
+        if !dbg.dirty {
+            return;
+        }
         txt.sections = (dbg.text.iter())
             .map(|(k, v)| TextSection::new(format!("{k}: {v}\n"), default()))
             .collect();
+        dbg.dirty = false;
     }
 }
 
@@ -33,15 +52,25 @@ pub fn draw_debug(mut dbg: Query<(&mut Text, &DebugUi)>) {
 pub struct Subtitle;
 
 // ------------------------------- Intro Cutscene -------------------------------
+#[derive(Debug)]
 enum Q {
     // advance time
     Tick(f32),
     // set translation
     Tran(&'static str, f32, f32, f32),
+    // set translation, easing in from the previous translation
+    TranEase(&'static str, f32, f32, f32, Ease),
     // set rotation (in radians around z-axis)
     Rot(&'static str, f32),
+    // rock back and forth around the last Rot: (name, amplitude, period, duration)
+    // swings amplitude to one side then the other every half period, and is back on the last Rot
+    // after duration (rounded to half periods), doesn't advance time so cues can play over it
+    // a negative amplitude swings the other way first
+    Wiggle(&'static str, f32, f32, f32),
     // set flip x
     Flip(&'static str, bool),
+    // restart the entity's texture animation from its first frame
+    AnimReset(&'static str),
     // sound paused
     Paused(&'static str, bool),
     // sound volume
@@ -51,12 +80,111 @@ enum Q {
     // subtitle
     Subtitle(&'static str),
 }
+impl Q {
+    // the entity the cue acts on, if any
+    fn name(&self) -> Option<&'static str> {
+        match *self {
+            Q::Tran(name, ..)
+            | Q::TranEase(name, ..)
+            | Q::Rot(name, _)
+            | Q::Wiggle(name, ..)
+            | Q::Flip(name, _)
+            | Q::AnimReset(name)
+            | Q::Paused(name, _)
+            | Q::Vol(name, _)
+            | Q::Despawn(name) => Some(name),
+            Q::Tick(_) | Q::Subtitle(_) => None,
+        }
+    }
+}
+// setup_anim builds each entity's curves without looking at when it's despawned, so a cue that
+// uses an entity after its Despawn (or a wiggle still going when it happens) silently does nothing
+fn check_cues(cues: &[Q]) -> Vec<String> {
+    let mut t = 0.;
+    let mut despawned: Map<&str, f32> = Map::new();
+    let mut wiggle_end: Map<&str, f32> = Map::new();
+    let mut problems = vec![];
+    for (i, cue) in cues.iter().enumerate() {
+        if let Q::Tick(dt) = cue {
+            t += dt;
+            continue;
+        }
+        let Some(name) = cue.name() else {
+            continue;
+        };
+        if let Some(at) = despawned.get(name) {
+            problems.push(format!(
+                "cue {i} uses {name:?} at {t}s, after it was despawned at {at}s"
+            ));
+            continue;
+        }
+        match *cue {
+            Q::Despawn(_) => {
+                if let Some(end) = wiggle_end.get(name).filter(|end| **end > t) {
+                    problems.push(format!(
+                        "cue {i} despawns {name:?} at {t}s, while it wiggles until {end}s"
+                    ));
+                }
+                despawned.insert(name, t);
+            }
+            Q::Wiggle(_, _, _, duration) => {
+                wiggle_end.insert(name, t + duration);
+            }
+            _ => {}
+        }
+    }
+    problems
+}
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Ease {
+    Linear,
+    // start slow, speed up
+    In,
+    // start fast, slow down
+    Out,
+    // along a PathFollow curve with these two control points, for arcs
+    Path(Vec2, Vec2),
+}
+impl Ease {
+    // how many keyframes an eased segment is baked into
+    const SAMPLES: usize = 8;
+
+    fn ease(self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::In => t * t,
+            Ease::Out => 1. - (1. - t) * (1. - t),
+            Ease::Path(p1, p2) => CubicSegment::new_bezier(p1, p2).ease(t),
+        }
+    }
+
+    // t of the way from a to b
+    fn lerp(self, a: Vec3, b: Vec3, t: f32) -> Vec3 {
+        match self {
+            Ease::Path(p1, p2) => {
+                let xy = PathFollow::at(&CubicSegment::new_bezier(p1, p2), a.xy(), b.xy(), t);
+                xy.extend(a.z + (b.z - a.z) * t)
+            }
+            _ => a.lerp(b, self.ease(t)),
+        }
+    }
+}
 // Camera cues
 struct CQ {
     // each field follows (start, end)
     time: (f32, f32),
     scale: (f32, f32),
-    tran: (Vec3, Vec3),
+    tran: (CamAt, CamAt),
+    // curves for the translation and the scale, None is the camera's bezier. different ones let
+    // the framing settle before the zoom is done
+    ease: (Option<Ease>, Option<Ease>),
+}
+// where a camera cue points
+#[derive(Clone, Copy)]
+enum CamAt {
+    Pos(Vec3),
+    // wherever the named entity is right now, plus an offset. the offset's z is the camera's z
+    Follow(&'static str, Vec3),
 }
 enum AR {
     Sprite(
@@ -66,10 +194,23 @@ enum AR {
         f32,
         bool,
     ),
-    Sound(&'static str, &'static str, bool),
+    // (name, path, play once, volume when no Vol cue says otherwise)
+    Sound(&'static str, &'static str, bool, f32),
     Overlay(&'static str, f32),
     Image(&'static str, &'static str, (f32, f32, f32), f32),
 }
+impl AR {
+    // starts loading whatever file the resource needs, with the type setup_anim loads it as
+    fn load(&self, asset_server: &AssetServer) -> Option<UntypedHandle> {
+        match *self {
+            AR::Sprite(_, path, ..) | AR::Image(_, path, ..) => {
+                Some(asset_server.load::<Image>(path).untyped())
+            }
+            AR::Sound(_, path, ..) => Some(asset_server.load::<AudioSource>(path).untyped()),
+            AR::Overlay(..) => None,
+        }
+    }
+}
 const ANIM_RSC: &'static [AR] = &[
     AR::Overlay("screen", 100.),
     AR::Image("bg", "scenes/intro/bg.png", (0., -35., -10.), 1.),
@@ -91,16 +232,16 @@ const ANIM_RSC: &'static [AR] = &[
         0.5,
         false,
     ),
-    AR::Sound("city", "sounds/city-background.wav", false),
-    AR::Sound("sad_song", "sounds/biedne-dziecie.wav", true),
-    AR::Sound("sad_song_jazz", "sounds/biedne-dziecie-jazz.wav", true),
-    AR::Sound("car_idle", "sounds/car-idle.wav", false),
-    AR::Sound("car_brake", "sounds/car-brake-squeak.wav", true),
-    AR::Sound("car_win_open", "sounds/car-window-open.wav", true),
-    AR::Sound("car_win_close", "sounds/car-window-close.wav", true),
-    AR::Sound("woosh", "sounds/woosh.wav", true),
-    AR::Sound("thump", "sounds/thump.wav", true),
-    AR::Sound("car_peels_out", "sounds/car-peels-out.wav", true),
+    AR::Sound("city", "sounds/city-background.wav", false, 1.),
+    AR::Sound("sad_song", "sounds/biedne-dziecie.wav", true, 1.),
+    AR::Sound("sad_song_jazz", "sounds/biedne-dziecie-jazz.wav", true, 1.),
+    AR::Sound("car_idle", "sounds/car-idle.wav", false, 1.),
+    AR::Sound("car_brake", "sounds/car-brake-squeak.wav", true, 1.),
+    AR::Sound("car_win_open", "sounds/car-window-open.wav", true, 1.),
+    AR::Sound("car_win_close", "sounds/car-window-close.wav", true, 1.),
+    AR::Sound("woosh", "sounds/woosh.wav", true, 1.),
+    AR::Sound("thump", "sounds/thump.wav", true, 0.3),
+    AR::Sound("car_peels_out", "sounds/car-peels-out.wav", true, 1.),
 ];
 const ANIM_CUE_JAZZ: &'static [Q] = &[
     Q::Tran("baby", 60., -200., -10.),
@@ -134,7 +275,7 @@ const ANIM_CUE_JAZZ: &'static [Q] = &[
     Q::Paused("car_brake", false),
     // car stops
     Q::Tick(0.25),
-    Q::Tran("car", -50., -150., 0.),
+    Q::TranEase("car", -50., -150., 0., Ease::Out),
     // window rolls down
     Q::Tick(1.),
     Q::Paused("car_win_open", false),
@@ -146,7 +287,13 @@ const ANIM_CUE_JAZZ: &'static [Q] = &[
     Q::Paused("woosh", false),
     // baby hits ground
     Q::Tick(1.),
-    Q::Tran("baby_thrown", 30., -220., 1.),
+    Q::TranEase(
+        "baby_thrown",
+        30.,
+        -220.,
+        1.,
+        Ease::Path(Vec2::new(0.3, -0.8), Vec2::new(0.6, -0.6)),
+    ),
     Q::Paused("thump", false),
     // window rolls up
     Q::Tick(1.),
@@ -165,7 +312,7 @@ const ANIM_CUE_JAZZ: &'static [Q] = &[
     Q::Tick(0.2),
     Q::Rot("car", 0.7),
     Q::Tick(1.8),
-    Q::Tran("car", 700., -50., 0.),
+    Q::TranEase("car", 700., -50., 0., Ease::In),
     Q::Vol("car_idle", 0.),
     // somber music plays
     // hold camera for few seconds
@@ -174,80 +321,34 @@ const ANIM_CUE_JAZZ: &'static [Q] = &[
     Q::Tick(0.5),
     Q::Subtitle("poor lonely baby"),
     Q::Tick(6.5),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.8),
     Q::Subtitle("born in the summer"),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.3),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(3.0),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(3.5),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(4.8),
     Q::Subtitle("abandoned in the trash"),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.8),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.8),
     Q::Subtitle("his parents did not want him"),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.3),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(3.0),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(3.5),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(4.8),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.8),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(1.2),
     // sudden baby reveal, upbeat wacky music plays
     Q::Tran("baby", 60., -200., -10.),
     Q::Despawn("baby_thrown"),
     Q::Tick(1.0),
     Q::Paused("sad_song_jazz", true),
     Q::Tran("baby", 60., -200., 0.),
+    Q::AnimReset("baby"),
     Q::Tick(1.0),
 ];
 const ANIM_CUE_WAIL: &'static [Q] = &[
@@ -282,7 +383,7 @@ const ANIM_CUE_WAIL: &'static [Q] = &[
     Q::Paused("car_brake", false),
     // car stops
     Q::Tick(0.25),
-    Q::Tran("car", -50., -150., 0.),
+    Q::TranEase("car", -50., -150., 0., Ease::Out),
     // window rolls down
     Q::Tick(1.),
     Q::Paused("car_win_open", false),
@@ -293,7 +394,13 @@ const ANIM_CUE_WAIL: &'static [Q] = &[
     Q::Paused("woosh", false),
     // baby hits ground
     Q::Tick(1.),
-    Q::Tran("baby_thrown", 30., -220., 1.),
+    Q::TranEase(
+        "baby_thrown",
+        30.,
+        -220.,
+        1.,
+        Ease::Path(Vec2::new(0.3, -0.8), Vec2::new(0.6, -0.6)),
+    ),
     Q::Paused("thump", false),
     // window rolls up
     Q::Tick(1.),
@@ -311,7 +418,7 @@ const ANIM_CUE_WAIL: &'static [Q] = &[
     Q::Tick(0.2),
     Q::Rot("car", 0.7),
     Q::Tick(1.8),
-    Q::Tran("car", 700., -50., 0.),
+    Q::TranEase("car", 700., -50., 0., Ease::In),
     Q::Vol("car_idle", 0.),
     // somber music plays
     Q::Paused("sad_song", false),
@@ -319,111 +426,110 @@ const ANIM_CUE_WAIL: &'static [Q] = &[
     // camera slowly zooms in on baby
     // baby wriggles on ground
     Q::Tick(1.0), // for sad_song
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(1.5),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.3),
     Q::Subtitle("poor lonely baby"),
     Q::Tick(0.5),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.3),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(3.5),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(1.2),
     Q::Subtitle(""),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(0.6),
+    Q::Tick(1.2),
     Q::Subtitle("born in the summer"),
     Q::Tick(2.4),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.8),
     Q::Subtitle(""),
-    Q::Rot("baby_thrown", 1.6),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
     Q::Tick(0.6),
     Q::Subtitle("abandoned in the trash"),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.3),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
+    Q::Tick(3.2),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(3.5),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(1.2),
     Q::Subtitle(""),
     Q::Tick(0.6),
     Q::Subtitle("his parents did not want him"),
-    Q::Rot("baby_thrown", 1.5),
     Q::Tick(3.0),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(2.0),
-    Q::Rot("baby_thrown", 1.4),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.6),
-    Q::Tick(0.6),
-    Q::Rot("baby_thrown", 1.5),
-    Q::Tick(4.0),
+    Q::Wiggle("baby_thrown", 0.1, 1.2, 1.8),
+    Q::Tick(3.8),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(3.2),
+    Q::Wiggle("baby_thrown", -0.1, 1.2, 1.2),
+    Q::Tick(5.2),
     // sudden baby reveal, upbeat wacky music plays
     Q::Tran("baby", 60., -200., -10.),
     Q::Despawn("baby_thrown"),
     Q::Tick(1.0),
     Q::Paused("sad_song_jazz", true),
     Q::Tran("baby", 60., -200., 0.),
+    Q::AnimReset("baby"),
     Q::Tick(1.0),
 ];
 const CAM_CUE: &'static [CQ] = &[
     CQ {
         time: (20., 60.),
         scale: (1., 0.4),
-        tran: (Vec3::new(0., 0., 0.), Vec3::new(60., -185., 0.)),
+        tran: (
+            CamAt::Pos(Vec3::new(0., 0., 0.)),
+            CamAt::Follow("baby", Vec3::new(0., 15., 0.)),
+        ),
+        ease: (Some(Ease::Out), None),
     },
     CQ {
         time: (65., 65.5),
         scale: (0.4, 0.8),
-        tran: (Vec3::new(60., -185., 0.), Vec3::new(60., -120., 0.)),
+        tran: (
+            CamAt::Follow("baby", Vec3::new(0., 15., 0.)),
+            CamAt::Follow("baby", Vec3::new(0., 80., 0.)),
+        ),
+        ease: (None, None),
     },
 ];
 
 #[derive(Component)]
 pub struct Bezier(CubicSegment<Vec2>);
 
+// moves an entity from `from` to `to` over duration seconds of game time, then goes away.
+// segment runs from (0, 0) to (1, 1) and its x and y are how far along each axis the entity is,
+// so a control point past 0 or 1 on one axis overshoots it, like going up before falling
+#[derive(Component)]
+pub struct PathFollow {
+    pub segment: CubicSegment<Vec2>,
+    pub from: Vec2,
+    pub to: Vec2,
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
+impl PathFollow {
+    pub fn at(segment: &CubicSegment<Vec2>, from: Vec2, to: Vec2, t: f32) -> Vec2 {
+        from + (to - from) * segment.position(t.clamp(0., 1.))
+    }
+}
+
+pub fn follow_paths(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut paths: Query<(Entity, &mut Transform, &mut PathFollow)>,
+) {
+    for (e, mut t, mut path) in &mut paths {
+        path.elapsed += time.delta_seconds();
+        let s = if path.duration > 0. {
+            path.elapsed / path.duration
+        } else {
+            1.
+        };
+        let pos = PathFollow::at(&path.segment, path.from, path.to, s);
+        t.translation = pos.extend(t.translation.z);
+        if s >= 1. {
+            commands.entity(e).remove::<PathFollow>();
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct MainCamera;
 
@@ -432,8 +538,128 @@ pub struct TextureAnimate {
     pub frame_len: f32,
     pub cycle: Cycle,
     pub idx_beg: usize,
+    // may be less than idx_beg to play the frames in reverse
+    pub idx_end: usize,
+    // (beg, end) frames to play instead while facing left, for sheets drawn in both directions
+    // None means the sheet is symmetric, and facing left flips the sprite
+    pub idx_left: Option<(usize, usize)>,
+    // seconds of (scaled) time this sprite has been animating, frames are picked from it
+    pub elapsed: f32,
+}
+// which way an animated sprite is looking, set by whatever moves it
+#[derive(Component, Default)]
+pub struct Facing {
+    pub left: bool,
+}
+// named sheets an animated sprite can switch between, like idle and jump
+// whatever tracks the sprite's state sets `current`, swap_sheets does the rest
+#[derive(Component)]
+pub struct SpriteSheets {
+    pub sheets: Vec<(&'static str, Sheet)>,
+    pub current: &'static str,
+    // the sheet that's on the sprite now
+    shown: &'static str,
+}
+#[derive(Clone)]
+pub struct Sheet {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub idx_beg: usize,
     pub idx_end: usize,
 }
+impl SpriteSheets {
+    // the first sheet should be the one the sprite is spawned with
+    pub fn new(sheets: Vec<(&'static str, Sheet)>) -> SpriteSheets {
+        let first = sheets[0].0;
+        SpriteSheets {
+            sheets,
+            current: first,
+            shown: first,
+        }
+    }
+}
+// scales the game's own clocks (physics and sprite animation) without touching bevy's Time,
+// so 0 freezes the world while input and ui keep running
+#[derive(Resource)]
+pub struct TimeScale(pub f32);
+// the game's clock, bevy's Time run through TimeScale that stands still while paused
+// pausing is separate from the scale so unpausing goes back to whatever speed it was
+// BABY_STEP=<fps> moves it on by exactly 1/fps every frame however long frames really take, so
+// a run plays out the same every time
+#[derive(Resource, Default)]
+pub struct GameTime {
+    delta: f32,
+    // bevy's delta without the scale or pause, for the intro which keeps its own pace
+    real: f32,
+    elapsed: f32,
+    pub paused: bool,
+    // the window is in the background, kept apart from paused so coming back doesn't unpause
+    pub unfocused: bool,
+    pub step: Option<f32>,
+}
+impl GameTime {
+    pub fn from_env() -> GameTime {
+        let step = std::env::var("BABY_STEP")
+            .ok()
+            .and_then(|fps| fps.parse::<f32>().ok())
+            .filter(|fps| *fps > 0.)
+            .map(|fps| 1. / fps);
+        GameTime { step, ..default() }
+    }
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+    pub fn delta(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f32(self.delta)
+    }
+    pub fn real_delta_seconds(&self) -> f32 {
+        self.real
+    }
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed
+    }
+    // move the clock on by dt seconds before scaling. tick_game_time does this every frame, but
+    // it can be called by hand to drive systems without a real clock behind them
+    pub fn advance(&mut self, dt: f32, scale: f32) {
+        self.real = if self.unfocused { 0. } else { dt };
+        self.delta = if self.paused { 0. } else { self.real * scale };
+        self.elapsed += self.real;
+    }
+}
+pub fn tick_game_time(mut game: ResMut<GameTime>, time: Res<Time>, scale: Res<TimeScale>) {
+    let dt = game.step.unwrap_or(time.delta_seconds());
+    game.advance(dt, scale.0);
+}
+// stop the clock and any playing sounds while the window is in the background
+pub fn pause_on_focus(
+    mut focus: EventReader<WindowFocused>,
+    mut game: ResMut<GameTime>,
+    sinks: Query<(Entity, &AudioSink)>,
+    mut paused: Local<Vec<Entity>>,
+) {
+    let Some(focus) = focus.read().last() else {
+        return;
+    };
+    if focus.focused != game.unfocused {
+        return;
+    }
+    game.unfocused = !focus.focused;
+    if game.unfocused {
+        for (e, sink) in &sinks {
+            if !sink.is_paused() {
+                sink.pause();
+                paused.push(e);
+            }
+        }
+    } else {
+        // only what was paused here, sounds the intro paused itself stay paused
+        for e in paused.drain(..) {
+            if let Ok((_, sink)) = sinks.get(e) {
+                sink.play();
+            }
+        }
+    }
+}
 #[derive(Clone, Copy)]
 pub enum Cycle {
     PingPong,
@@ -443,14 +669,40 @@ pub enum Cycle {
 pub struct CueSequencer {
     playing: bool,
     audio: Map<Name, (Vec<(f32, f32)>, Vec<(f32, bool)>)>,
+    // each sound's own volume, what it plays at before (or without) a Vol cue
+    base_volume: Map<Name, f32>,
     despawn: Map<Name, f32>,
     flip: Map<Name, Vec<(f32, bool)>>,
+    anim_reset: Map<Name, Vec<f32>>,
     subtitles: Vec<(f32, &'static str)>,
-    time: f32,
-    end: f32,
+    // (start time, "<index> <cue>") of every cue but Tick, for showing which one is playing
+    cues: Vec<(f32, String)>,
+    pub(crate) time: f32,
+    // frame time not yet turned into whole steps
+    rem: f32,
+    // whole steps taken this frame, which the intro's animations play by too
+    steps: u32,
+    pub(crate) end: f32,
 }
 
 impl CueSequencer {
+    // the sequence advances in fixed steps, so where the cues land doesn't depend on frame timing
+    const STEP: f32 = 1. / 60.;
+    const SUBTITLE_FADE: f32 = 0.3;
+
+    // turn dt into as many whole steps as it makes up, keeping the rest for next time
+    fn advance(&mut self, dt: f32) {
+        self.rem += dt;
+        self.steps = 0;
+        // the epsilon keeps float error from dropping a step when frames are a whole number of
+        // steps
+        while self.rem >= CueSequencer::STEP - 1e-6 {
+            self.rem -= CueSequencer::STEP;
+            self.time += CueSequencer::STEP;
+            self.steps += 1;
+        }
+    }
+
     fn get_curve<T: Copy>(curve: &Vec<(f32, T)>, time: f32) -> Option<(T, T, f32)> {
         if curve.is_empty() {
             return None;
@@ -476,7 +728,8 @@ impl CueSequencer {
         let Some((vol, paused)) = self.audio.get(name) else {
             return None;
         };
-        let (vol_a, vol_b, s) = Self::get_curve(vol, time).unwrap_or((1., 1., 1.));
+        let base = self.base_volume.get(name).copied().unwrap_or(1.);
+        let (vol_a, vol_b, s) = Self::get_curve(vol, time).unwrap_or((base, base, 1.));
         let vol = vol_b * s + vol_a * (1. - s);
         let (paused, paused_b, s) = Self::get_curve(paused, time).unwrap_or((true, true, 1.));
         let paused = if s >= 1. { paused_b } else { paused };
@@ -503,6 +756,16 @@ impl CueSequencer {
         flip
     }
 
+    // the latest reset in (from, to], the animation has been running for to - reset since then
+    fn get_anim_reset(&self, name: &Name, from: f32, to: f32) -> Option<f32> {
+        let resets = self.anim_reset.get(name)?;
+        resets
+            .iter()
+            .rev()
+            .find(|t| **t > from && **t <= to)
+            .copied()
+    }
+
     fn get_subtitle(&mut self, time: f32) -> &'static str {
         let (sub_cur, sub_next, s) = Self::get_curve(&self.subtitles, time).unwrap_or(("", "", 1.));
         if s >= 1. {
@@ -511,6 +774,25 @@ impl CueSequencer {
             sub_cur
         }
     }
+
+    // subtitles fade in after they change and out before the next change, so one crossfades
+    // into the next over twice SUBTITLE_FADE
+    // the last cue to have started by time
+    fn get_cue(&self, time: f32) -> Option<&str> {
+        (self.cues.iter().rev())
+            .find(|(t, _)| *t <= time)
+            .map(|(_, cue)| cue.as_str())
+    }
+
+    fn get_subtitle_alpha(&self, time: f32) -> f32 {
+        let since = (self.subtitles.iter().rev())
+            .find(|(t, _)| *t <= time)
+            .map_or(f32::INFINITY, |(t, _)| time - t);
+        let until = (self.subtitles.iter())
+            .find(|(t, _)| *t > time)
+            .map_or(f32::INFINITY, |(t, _)| t - time);
+        (since.min(until) / Self::SUBTITLE_FADE).clamp(0., 1.)
+    }
 }
 
 pub fn sequence_cues(
@@ -518,25 +800,49 @@ pub fn sequence_cues(
     audio: Query<&AudioSink>,
     mut subtitle: Query<&mut Text, With<Subtitle>>,
     mut sprite: Query<&mut Sprite>,
+    mut anims: Query<&mut TextureAnimate>,
     mut commands: Commands,
     mut sequence: ResMut<CueSequencer>,
-    time: Res<Time>,
     mut dbg: Query<&mut DebugUi>,
-    mut next_state: ResMut<NextState<AppState>>,
+    mut players: Query<&mut AnimationPlayer>,
+    attract: Res<AttractMode>,
+    game: Res<GameTime>,
+    start: Res<StartLevel>,
+    fades: Query<(), With<Fade>>,
 ) {
-    if !sequence.playing {
+    // the intro runs on the game's clock unscaled, so it still holds still in the background
+    if !sequence.playing || game.unfocused {
+        sequence.steps = 0;
         return;
     }
     if sequence.time >= sequence.end {
-        next_state.set(AppState::Game);
+        sequence.playing = false;
+        // skipped right at the end, and that fade is already on its way to the game
+        if !fades.is_empty() {
+            return;
+        }
+        // attract mode fades out without a state to go to, and replay_intro starts it over
+        if attract.0 {
+            fade_transition(&mut commands, 1., None);
+        } else {
+            start.start_game(&mut commands);
+        }
         return;
     }
 
     let mut dbg = dbg.single_mut();
-    dbg.watch("time", time.elapsed_seconds());
+    dbg.watch("time", game.elapsed_seconds());
 
-    sequence.time += time.delta_seconds();
+    let prev = sequence.time;
+    sequence.advance(game.real_delta_seconds());
     let t = sequence.time;
+    dbg.watch("cue", sequence.get_cue(t).unwrap_or("-"));
+    // cues only depend on the current time, so stepping them once at the end is the same as
+    // stepping them every time. the animations are driven from the same clock
+    for mut player in &mut players {
+        player.pause();
+        player.seek_to(t);
+    }
     for (e, name) in &mut names {
         if let Some((vol, paused)) = sequence.get_audio(name, t) {
             if let Ok(sink) = audio.get(e) {
@@ -558,25 +864,36 @@ pub fn sequence_cues(
                 s.flip_x = flip;
             }
         }
+        if let Some(reset) = sequence.get_anim_reset(name, prev, t) {
+            if let Ok(mut anim) = anims.get_mut(e) {
+                anim.elapsed = t - reset;
+            }
+        }
     }
     let mut subtitle = subtitle.single_mut();
     let seq_subtitle = sequence.get_subtitle(t);
     if subtitle.sections[0].value != seq_subtitle {
         subtitle.sections[0].value = seq_subtitle.to_string();
     }
+    let alpha = sequence.get_subtitle_alpha(t);
+    if subtitle.sections[0].style.color.a() != alpha {
+        subtitle.sections[0].style.color.set_a(alpha);
+    }
 }
 
 pub fn sequence_camera(
     mut camera: Query<(&mut OrthographicProjection, &mut Transform, &Bezier), With<MainCamera>>,
-    time: Res<Time>,
+    sequence: Res<CueSequencer>,
+    names: Query<(&Name, &Transform), Without<MainCamera>>,
 ) {
+    // on the sequence's clock rather than the app's, so a replayed intro moves the camera again
+    let t = sequence.time;
     let mut cur_cq: Option<&CQ> = None;
     for cq in CAM_CUE {
         let CQ {
             time: (cq_s, sq_e), ..
         } = cq;
-        let time = time.elapsed_seconds();
-        if time >= *cq_s && time <= *sq_e {
+        if t >= *cq_s && t <= *sq_e {
             cur_cq = Some(cq);
         }
     }
@@ -585,59 +902,270 @@ pub fn sequence_camera(
         time: (p1_t, p2_t),
         scale: (p1_s, p2_s),
         tran: (p1_tr, p2_tr),
+        ease: (tran_ease, scale_ease),
     }) = cur_cq
     else {
         return;
     };
 
-    let t = time.elapsed_seconds();
     let i = inverse_lerp(*p1_t..=*p2_t, t).unwrap();
 
     let Ok((mut proj, mut tran, bez)) = camera.get_single_mut() else {
         return;
     };
 
-    let ease = bez.0.ease(i);
-    proj.scale = lerp(*p1_s..=*p2_s, ease);
-    tran.translation = p1_tr.lerp(*p2_tr, ease);
+    let at = |target: &CamAt| match *target {
+        CamAt::Pos(pos) => Some(pos),
+        CamAt::Follow(name, off) => (names.iter())
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(_, t)| (t.translation.xy() + off.xy()).extend(off.z)),
+    };
+    // a followed entity that's gone leaves the camera where it is
+    let (Some(p1_tr), Some(p2_tr)) = (at(p1_tr), at(p2_tr)) else {
+        return;
+    };
+
+    let ease = |curve: &Option<Ease>| curve.map_or_else(|| bez.0.ease(i), |e| e.ease(i));
+    proj.scale = lerp(*p1_s..=*p2_s, ease(scale_ease));
+    tran.translation = p1_tr.lerp(p2_tr, ease(tran_ease));
 }
 
-pub fn animate_texture(mut tex: Query<(&mut TextureAtlas, &TextureAnimate)>, time: Res<Time>) {
-    for (mut atlas, anim) in &mut tex {
-        let (beg, end) = (anim.idx_beg, anim.idx_end);
-        let len = end + 1 - beg;
-        let n = time.elapsed_seconds() / anim.frame_len;
+pub fn swap_sheets(
+    mut sprites: Query<
+        (
+            &mut SpriteSheets,
+            &mut Handle<Image>,
+            &mut TextureAtlas,
+            &mut TextureAnimate,
+        ),
+        Changed<SpriteSheets>,
+    >,
+) {
+    for (mut sheets, mut texture, mut atlas, mut anim) in &mut sprites {
+        if sheets.current == sheets.shown {
+            continue;
+        }
+        let Some((_, sheet)) = (sheets.sheets.iter()).find(|(name, _)| *name == sheets.current)
+        else {
+            warn!("no sprite sheet named {}", sheets.current);
+            sheets.current = sheets.shown;
+            continue;
+        };
+        let sheet = sheet.clone();
+        *texture = sheet.texture;
+        atlas.layout = sheet.layout;
+        // the old index can be past the end of a sheet with fewer frames, so start over
+        atlas.index = sheet.idx_beg;
+        anim.idx_beg = sheet.idx_beg;
+        anim.idx_end = sheet.idx_end;
+        anim.idx_left = None;
+        anim.elapsed = 0.;
+        sheets.shown = sheets.current;
+    }
+}
+
+pub fn animate_texture(
+    mut tex: Query<(
+        &mut TextureAtlas,
+        &mut TextureAnimate,
+        Option<&Facing>,
+        Option<&mut Sprite>,
+    )>,
+    time: Res<GameTime>,
+    sequence: Option<Res<CueSequencer>>,
+) {
+    // the intro's sprites keep to the sequencer's fixed steps, a step at a time like its clock,
+    // so a capture comes out the same whatever the frame times were
+    let (dt, steps) = match sequence {
+        Some(sequence) if sequence.playing => (CueSequencer::STEP, sequence.steps),
+        _ => (time.delta_seconds(), 1),
+    };
+    for (mut atlas, mut anim, facing, sprite) in &mut tex {
+        for _ in 0..steps {
+            anim.elapsed += dt;
+        }
+        let left = facing.is_some_and(|f| f.left);
+        let (beg, end) = match anim.idx_left {
+            Some(range) if left => range,
+            _ => (anim.idx_beg, anim.idx_end),
+        };
+        // only sprites that have a facing get flipped, the intro flips its sprites with cues
+        if let (Some(_), Some(mut sprite)) = (facing, sprite) {
+            let flip = left && anim.idx_left.is_none();
+            if sprite.flip_x != flip {
+                sprite.flip_x = flip;
+            }
+        }
+        // an end before the beginning plays the range backwards
+        let len = beg.abs_diff(end) + 1;
+        let frame = |i: usize| if end < beg { beg - i } else { beg + i };
+        let n = anim.elapsed / anim.frame_len;
         let n = n as usize;
-        match anim.cycle {
+        let i = match anim.cycle {
+            // a single frame has nothing to bounce between
+            Cycle::PingPong if len == 1 => 0,
             Cycle::PingPong => {
                 let n = n % (len * 2 - 2);
                 if n < len {
-                    atlas.index = beg + n;
+                    n
                 } else {
-                    atlas.index = beg + (len - (n - len) - 2);
+                    len - (n - len) - 2
                 }
             }
-            Cycle::Loop => {
-                let n = n % len;
-                atlas.index = beg + n;
-            }
-        }
+            Cycle::Loop => n % len,
+        };
+        atlas.index = frame(i);
     }
 }
 
 pub fn check_kbd(
     kbd: Res<ButtonInput<KeyCode>>,
-    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+    fades: Query<(), With<Fade>>,
     mut quit: EventWriter<AppExit>,
+    mut attract: ResMut<AttractMode>,
+    start: Res<StartLevel>,
 ) {
-    if kbd.pressed(KeyCode::Space) {
-        next_state.set(AppState::Game);
+    // in attract mode any key starts the game, for good
+    let any = attract.0 && kbd.get_just_pressed().next().is_some();
+    if (any || kbd.pressed(KeyCode::Space)) && fades.is_empty() {
+        attract.0 = false;
+        start.start_game(&mut commands);
     }
     if kbd.pressed(KeyCode::Escape) {
         quit.send(AppExit);
     }
 }
 
+// BABY_ATTRACT=1 plays the intro over and over instead of going on to the game, for leaving it
+// running on a screen. any key leaves attract mode and starts the game
+#[derive(Resource)]
+pub struct AttractMode(pub bool);
+
+impl AttractMode {
+    pub fn from_env() -> AttractMode {
+        AttractMode(std::env::var_os("BABY_ATTRACT").is_some())
+    }
+}
+
+// BABY_SKIP_INTRO=1 starts straight in the game, without loading the intro's assets or adding
+// any of its systems, for working on the game without sitting through the intro every run
+#[derive(Resource)]
+pub struct SkipIntro(pub bool);
+
+impl SkipIntro {
+    pub fn from_env() -> SkipIntro {
+        SkipIntro(std::env::var_os("BABY_SKIP_INTRO").is_some())
+    }
+}
+
+// BABY_START=<file> is the map the intro leads into, instead of the one the game was started with
+// (BABY_MAP or the built in one). it's loaded as the intro ends, so the game's setup finds it
+#[derive(Resource, Default)]
+pub struct StartLevel(pub Option<PathBuf>);
+
+impl StartLevel {
+    pub fn from_env() -> StartLevel {
+        StartLevel(std::env::var_os("BABY_START").map(PathBuf::from))
+    }
+
+    // the start level, None if there isn't one or it can't be loaded
+    pub fn load(&self) -> Option<Level> {
+        let path = self.0.as_ref()?;
+        (Level::load(path))
+            .map_err(|e| eprintln!("can't load BABY_START {path:?}: {e}"))
+            .ok()
+    }
+
+    // fade out of the intro into the game, on the start level if there is one
+    pub fn start_game(&self, commands: &mut Commands) {
+        if let Some(level) = self.load() {
+            commands.insert_resource(level);
+        }
+        fade_transition(commands, 1., Some(AppState::Game));
+    }
+}
+
+// a state can't be re-entered by setting it again, so the intro restarts itself by running its
+// own exit and enter schedules once the end of the sequence has faded out
+pub fn replay_intro(
+    mut faded_out: EventReader<FadedOut>,
+    attract: Res<AttractMode>,
+    mut commands: Commands,
+) {
+    if faded_out.read().any(|ev| ev.0.is_none()) && attract.0 {
+        commands.add(|world: &mut World| {
+            world.run_schedule(OnExit(AppState::Intro));
+            world.run_schedule(OnEnter(AppState::Intro));
+        });
+    }
+}
+
+// BABY_CAPTURE=<dir> writes every frame of the intro to <dir>/00000.png, <dir>/00001.png, ...
+// time is stepped by a fixed amount each frame while capturing so the output doesn't depend on
+// how fast frames can actually be rendered and saved
+pub struct CapturePlugin;
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(dir) = std::env::var("BABY_CAPTURE") else {
+            return;
+        };
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            eprintln!("can't capture intro to {dir:?}: {e}");
+            return;
+        }
+        app.insert_resource(Capture {
+            dir,
+            frame: 0,
+            shot: default(),
+        })
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            std::time::Duration::from_secs_f32(1. / Capture::FPS),
+        ))
+        .add_systems(PostUpdate, capture_frame.run_if(in_state(AppState::Intro)))
+        // the last few frames come back from the renderer after the intro's over
+        .add_systems(PostUpdate, save_captures);
+    }
+}
+
+#[derive(Resource)]
+pub struct Capture {
+    dir: String,
+    frame: u32,
+    // frames the renderer has handed back, by number, waiting to be written out
+    shot: std::sync::Arc<std::sync::Mutex<Vec<(u32, Image)>>>,
+}
+impl Capture {
+    const FPS: f32 = 30.;
+}
+
+pub fn capture_frame(
+    mut capture: ResMut<Capture>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    win: Query<Entity, With<PrimaryWindow>>,
+) {
+    let (frame, shot) = (capture.frame, capture.shot.clone());
+    let requested = screenshots.take_screenshot(win.single(), move |img| {
+        shot.lock().unwrap().push((frame, img));
+    });
+    if requested.is_ok() {
+        capture.frame += 1;
+    }
+}
+
+// write out whatever frames the renderer has finished with as <dir>/<frame>.png
+pub fn save_captures(capture: Res<Capture>) {
+    let shot = std::mem::take(&mut *capture.shot.lock().unwrap());
+    for (frame, img) in shot {
+        let path = format!("{}/{frame:05}.png", capture.dir);
+        let saved = (img.try_into_dynamic().map_err(|e| e.to_string()))
+            .and_then(|img| img.to_rgb8().save(&path).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            eprintln!("can't save {path:?}: {e}");
+        }
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 #[cfg(target_arch = "wasm32")]
@@ -674,12 +1202,23 @@ pub fn setup_anim(
     } else {
         ANIM_CUE_WAIL
     };
+    if cfg!(debug_assertions) {
+        for (seq, cues) in [("jazz", ANIM_CUE_JAZZ), ("wail", ANIM_CUE_WAIL)] {
+            for problem in check_cues(cues) {
+                warn!("{seq} intro: {problem}");
+            }
+        }
+    }
 
     let mut pos: Map<&'static str, Vec3> = Map::new();
     let mut end = 0.;
-    for cue in anim_cue.iter() {
+    sequence.cues.clear();
+    for (i, cue) in anim_cue.iter().enumerate() {
+        if !matches!(cue, Q::Tick(_)) {
+            sequence.cues.push((end, format!("{i} {cue:?}")));
+        }
         match cue {
-            Q::Tran(name, x, y, z) => {
+            Q::Tran(name, x, y, z) | Q::TranEase(name, x, y, z, _) => {
                 if !pos.contains_key(name) {
                     pos.insert(name, Vec3::new(*x, *y, *z));
                 }
@@ -728,6 +1267,8 @@ pub fn setup_anim(
                         cycle,
                         idx_beg,
                         idx_end,
+                        idx_left: None,
+                        elapsed: 0.,
                     },
                 ));
                 entities.insert(name, cmd.id());
@@ -761,7 +1302,7 @@ pub fn setup_anim(
                 ));
                 entities.insert(Name::new(name), cmd.id());
             }
-            &AR::Sound(name, snd, once) => {
+            &AR::Sound(name, snd, once, volume) => {
                 let cmd = commands.spawn((
                     Name::new(name),
                     AudioBundle {
@@ -773,11 +1314,13 @@ pub fn setup_anim(
                             } else {
                                 PlaybackMode::Loop
                             },
+                            volume: bevy::audio::Volume::new(volume),
                             ..default()
                         },
                     },
                 ));
                 entities.insert(Name::new(name), cmd.id());
+                sequence.base_volume.insert(Name::new(name), volume);
             }
         }
     }
@@ -789,6 +1332,7 @@ pub fn setup_anim(
         let mut pos_steps = vec![];
         let mut pos_frames = vec![];
         let mut rot_next = None;
+        let mut rot_last = 0.;
         let mut rot_steps = vec![];
         let mut rot_frames = vec![];
 
@@ -798,6 +1342,8 @@ pub fn setup_anim(
         let mut play_cues = vec![];
         let mut flip_next = None;
         let mut flip_cues = vec![];
+        let mut reset_next = false;
+        let mut reset_cues = vec![];
 
         let mut sub_next = None;
         let mut sub_cues = vec![];
@@ -806,7 +1352,10 @@ pub fn setup_anim(
         for cue in anim_cue.iter() {
             match cue {
                 Q::Tran(kname, x, y, z) if *kname == name.as_str() => {
-                    pos_next = Some(Vec3::new(*x, *y, *z));
+                    pos_next = Some((Vec3::new(*x, *y, *z), Ease::Linear));
+                }
+                Q::TranEase(kname, x, y, z, ease) if *kname == name.as_str() => {
+                    pos_next = Some((Vec3::new(*x, *y, *z), *ease));
                 }
                 Q::Paused(kname, paused) if *kname == name.as_str() => {
                     paused_next = Some(*paused);
@@ -819,17 +1368,31 @@ pub fn setup_anim(
                 }
                 Q::Rot(kname, rad) if *kname == name.as_str() => {
                     rot_next = Some(Quat::from_rotation_z(*rad));
+                    rot_last = *rad;
+                }
+                Q::Wiggle(kname, amplitude, period, duration) if *kname == name.as_str() => {
+                    // the wiggle starts (and ends) on rot_last anyway
+                    rot_next = None;
+                    push_wiggle(
+                        &mut rot_steps,
+                        &mut rot_frames,
+                        t,
+                        rot_last,
+                        (*amplitude, *period, *duration),
+                    );
                 }
                 Q::Flip(kname, flip) if *kname == name.as_str() => {
                     flip_next = Some(*flip);
                 }
+                Q::AnimReset(kname) if *kname == name.as_str() => {
+                    reset_next = true;
+                }
                 Q::Subtitle(sub) => {
                     sub_next = Some(*sub);
                 }
                 Q::Tick(dt) => {
-                    if let Some(pos_next) = pos_next.take() {
-                        pos_frames.push(pos_next);
-                        pos_steps.push(t);
+                    if let Some((pos_next, ease)) = pos_next.take() {
+                        push_eased(&mut pos_steps, &mut pos_frames, t, pos_next, ease);
                     }
                     if let Some(rot) = rot_next.take() {
                         rot_frames.push(rot);
@@ -844,6 +1407,9 @@ pub fn setup_anim(
                     if let Some(flip) = flip_next.take() {
                         flip_cues.push((t, flip));
                     }
+                    if std::mem::take(&mut reset_next) {
+                        reset_cues.push(t);
+                    }
                     if let Some(sub) = sub_next.take() {
                         sub_cues.push((t, sub));
                     }
@@ -853,9 +1419,8 @@ pub fn setup_anim(
             }
         }
 
-        if let Some(pos_next) = pos_next {
-            pos_frames.push(pos_next);
-            pos_steps.push(t);
+        if let Some((pos_next, ease)) = pos_next {
+            push_eased(&mut pos_steps, &mut pos_frames, t, pos_next, ease);
         }
         if let Some(rot) = rot_next {
             rot_frames.push(rot);
@@ -876,6 +1441,9 @@ pub fn setup_anim(
         if let Some(flip) = flip_next.take() {
             flip_cues.push((t, flip));
         }
+        if reset_next {
+            reset_cues.push(t);
+        }
 
         if let Some(sub) = sub_next.take() {
             sub_cues.push((t, sub));
@@ -921,6 +1489,10 @@ pub fn setup_anim(
             sequence.flip.insert(name.clone(), flip_cues);
         }
 
+        if !reset_cues.is_empty() {
+            sequence.anim_reset.insert(name.clone(), reset_cues);
+        }
+
         if !sub_cues.is_empty() {
             sequence.subtitles = sub_cues;
         }
@@ -947,11 +1519,105 @@ pub fn setup_anim(
     ));
 }
 
-pub fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+// add a translation keyframe
+// AnimationClip only interpolates linearly, so eased segments are baked into extra keyframes
+// between the previous keyframe and this one
+fn push_eased(steps: &mut Vec<f32>, frames: &mut Vec<Vec3>, t: f32, pos: Vec3, ease: Ease) {
+    if let (Some(&prev_t), Some(&prev_pos)) = (steps.last(), frames.last()) {
+        if ease != Ease::Linear {
+            for i in 1..Ease::SAMPLES {
+                let s = i as f32 / Ease::SAMPLES as f32;
+                steps.push(prev_t + (t - prev_t) * s);
+                frames.push(ease.lerp(prev_pos, pos, s));
+            }
+        }
+    }
+    steps.push(t);
+    frames.push(pos);
+}
+
+// add rotation keyframes swinging around center, see Q::Wiggle
+fn push_wiggle(
+    steps: &mut Vec<f32>,
+    frames: &mut Vec<Quat>,
+    t: f32,
+    center: f32,
+    (amplitude, period, duration): (f32, f32, f32),
+) {
+    let half = period / 2.;
+    // a zero period would be endless swings, treat it as no wiggle
+    let swings = if half > 0. {
+        (duration / half).round() as usize
+    } else {
+        0
+    };
+    for i in 0..swings {
+        let side = if i % 2 == 0 { 1. } else { -1. };
+        steps.push(t + i as f32 * half);
+        frames.push(Quat::from_rotation_z(center + amplitude * side));
+    }
+    steps.push(t + swings as f32 * half);
+    frames.push(Quat::from_rotation_z(center));
+}
+
+// the intro's images and sounds, held from the loading screen on so they stay loaded and
+// setup_anim's loads of the same paths find them ready
+#[derive(Resource)]
+pub struct IntroAssets(Vec<UntypedHandle>);
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+pub fn start_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = (ANIM_RSC.iter())
+        .filter_map(|rsc| rsc.load(&asset_server))
+        .collect();
+    commands.insert_resource(IntroAssets(handles));
+    commands.spawn((LoadingScreen, Camera2dBundle::default()));
+    commands.spawn((
+        LoadingScreen,
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(20.)),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+// spins the loading square until every intro asset has loaded (or failed to, which the intro
+// gets by without), then starts the intro
+pub fn wait_for_assets(
+    asset_server: Res<AssetServer>,
+    intro: Res<IntroAssets>,
+    time: Res<Time>,
+    mut spinner: Query<&mut Transform, (With<LoadingScreen>, With<Sprite>)>,
+    mut next: ResMut<NextState<AppState>>,
 ) {
+    for mut t in &mut spinner {
+        t.rotate_z(4. * time.delta_seconds());
+    }
+    let pending = (intro.0.iter())
+        .filter(|h| {
+            !matches!(
+                asset_server.get_load_state(h.id()),
+                Some(LoadState::Loaded | LoadState::Failed)
+            )
+        })
+        .count();
+    if pending == 0 {
+        next.set(AppState::Intro);
+    }
+}
+
+pub fn finish_loading(mut commands: Commands, screen: Query<Entity, With<LoadingScreen>>) {
+    for e in &screen {
+        commands.entity(e).despawn();
+    }
+}
+
+pub fn setup(mut commands: Commands) {
     commands.insert_resource(CueSequencer {
         playing: true,
         ..default()
@@ -979,34 +1645,26 @@ pub fn setup(
         )),
     ));
 
-    commands.spawn((
-        DebugUi::default(),
-        TextBundle {
+    // on a dark panel like the game's, so it can be read over the intro
+    commands
+        .spawn(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
                 top: Val::Px(10.),
                 left: Val::Px(10.),
+                padding: UiRect::all(Val::Px(4.)),
                 ..default()
             },
+            background_color: if cfg!(debug_assertions) {
+                Color::rgba(0., 0., 0., 0.6).into()
+            } else {
+                Color::NONE.into()
+            },
             ..default()
-        },
-    ));
-
-    // Pillarboxes
-    let pillarbox_h_offset = (super::WINDOW_WIDTH + super::PILLARBOX_WIDTH) / 2.;
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: Mesh2dHandle(meshes.add(Rectangle::new(super::PILLARBOX_WIDTH, super::WINDOW_WIDTH))),
-        material: materials.add(Color::BLACK),
-        transform: Transform::from_xyz(pillarbox_h_offset, 0., 100.),
-        ..default()
-    });
-
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: Mesh2dHandle(meshes.add(Rectangle::new(super::PILLARBOX_WIDTH, super::WINDOW_WIDTH))),
-        material: materials.add(Color::BLACK),
-        transform: Transform::from_xyz(-pillarbox_h_offset, 0., 100.),
-        ..default()
-    });
+        })
+        .with_children(|panel| {
+            panel.spawn((DebugUi::default(), TextBundle::default()));
+        });
 }
 
 pub fn cleanup(
@@ -1016,6 +1674,7 @@ pub fn cleanup(
     meshes: Query<Entity, With<Mesh2dHandle>>,
     sounds: Query<Entity, With<Handle<AudioSource>>>,
     subtitle: Query<Entity, With<Subtitle>>,
+    dbg: Query<&Parent, With<DebugUi>>,
 ) {
     let camera = camera.get_single().unwrap();
     commands.entity(camera).despawn();
@@ -1029,6 +1688,12 @@ pub fn cleanup(
         commands.entity(s).despawn();
     }
     commands.entity(subtitle.single()).despawn();
+    // the text goes with its panel
+    for panel in dbg.iter() {
+        commands.entity(panel.get()).despawn_recursive();
+    }
+    // setup puts a fresh one in, so entering the intro again starts it from the beginning
+    commands.remove_resource::<CueSequencer>();
     println!("cleaning up intro");
 }
 
@@ -1047,3 +1712,678 @@ fn lerp(r: std::ops::RangeInclusive<f32>, t: f32) -> f32 {
         *r.start() + t * (*r.end() - *r.start())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_cues_finds_use_after_despawn() {
+        let cues = [
+            Q::Tran("baby", 0., 0., 0.),
+            Q::Wiggle("baby", 0.1, 0.5, 2.),
+            Q::Tick(1.),
+            Q::Despawn("baby"),
+            Q::Tick(0.5),
+            Q::Flip("baby", true),
+            Q::Subtitle("bye"),
+        ];
+        assert_eq!(
+            check_cues(&cues),
+            [
+                "cue 3 despawns \"baby\" at 1s, while it wiggles until 2s",
+                "cue 5 uses \"baby\" at 1.5s, after it was despawned at 1s",
+            ]
+        );
+        assert!(check_cues(&cues[..3]).is_empty());
+        assert!(check_cues(ANIM_CUE_JAZZ).is_empty());
+        assert!(check_cues(ANIM_CUE_WAIL).is_empty());
+    }
+
+    #[test]
+    fn wiggle_swings_either_side_every_half_period() {
+        let close = |a: &[f32], b: &[f32]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4)
+        };
+        let wiggle = |t, center, wiggle| {
+            let (mut steps, mut frames) = (vec![], vec![]);
+            push_wiggle(&mut steps, &mut frames, t, center, wiggle);
+            let angles: Vec<_> = (frames.iter())
+                .map(|q: &Quat| q.to_euler(EulerRot::ZYX).0)
+                .collect();
+            (steps, angles)
+        };
+        // what the hand-written Rot(1.6) Tick(0.6) Rot(1.4) Tick(0.6) ... cues used to do
+        let (steps, angles) = wiggle(2., 1.5, (0.1, 1.2, 1.8));
+        assert!(close(&steps, &[2., 2.6, 3.2, 3.8]), "{steps:?}");
+        assert!(close(&angles, &[1.6, 1.4, 1.6, 1.5]), "{angles:?}");
+        // the other way first, and a duration rounded to whole swings
+        let (steps, angles) = wiggle(0., 0., (-0.1, 1., 1.2));
+        assert!(close(&steps, &[0., 0.5, 1.]), "{steps:?}");
+        assert!(close(&angles, &[-0.1, 0.1, 0.]), "{angles:?}");
+        // no period is no wiggle, just back on the center
+        let (steps, angles) = wiggle(1., 0.3, (0.1, 0., 2.));
+        assert!(
+            close(&steps, &[1.]) && close(&angles, &[0.3]),
+            "{steps:?} {angles:?}"
+        );
+    }
+
+    // speed over each keyframe segment of a move from x = 0 to 100 over 1s
+    fn segment_speeds(ease: Ease) -> Vec<f32> {
+        let (mut steps, mut frames) = (vec![0.], vec![Vec3::ZERO]);
+        push_eased(&mut steps, &mut frames, 1., Vec3::new(100., 0., 0.), ease);
+        (steps.windows(2).zip(frames.windows(2)))
+            .map(|(t, p)| (p[1].x - p[0].x) / (t[1] - t[0]))
+            .collect()
+    }
+
+    #[test]
+    fn car_slows_into_its_stop() {
+        let out = segment_speeds(Ease::Out);
+        assert_eq!(out.len(), Ease::SAMPLES);
+        let (mid, last) = (out[out.len() / 2], out[out.len() - 1]);
+        assert!(last < mid / 2., "{out:?}");
+        assert!(out.windows(2).all(|w| w[1] < w[0]), "{out:?}");
+        // and pulls away slowly
+        let away = segment_speeds(Ease::In);
+        assert!(away[0] < away[away.len() / 2] / 2., "{away:?}");
+        assert_eq!(segment_speeds(Ease::Linear), [100.]);
+    }
+
+    // writing the pngs needs a renderer, so this stops at the requests capture_frame makes
+    #[test]
+    fn capture_writes_a_numbered_png_per_frame() {
+        use bevy::ecs::system::RunSystemOnce;
+        use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+        let dir = std::env::temp_dir().join(format!("grossbaby-capture-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut world = World::new();
+        world.init_resource::<ScreenshotManager>();
+        world.insert_resource(Capture {
+            dir: dir.to_str().unwrap().into(),
+            frame: 0,
+            shot: default(),
+        });
+        let win = world.spawn(PrimaryWindow).id();
+        // a frame the renderer would hand back
+        let rendered = || {
+            Image::new_fill(
+                Extent3d {
+                    width: 4,
+                    height: 3,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                &[255, 0, 0, 255],
+                TextureFormat::Rgba8UnormSrgb,
+                default(),
+            )
+        };
+        for n in 0..3 {
+            world.run_system_once(capture_frame);
+            // still waiting on the renderer for that one, so the next frame doesn't skip a number
+            world.run_system_once(capture_frame);
+            assert_eq!(world.resource::<Capture>().frame, n + 1);
+            let mut shots = world.resource_mut::<ScreenshotManager>();
+            assert!(
+                shots.take_screenshot(win, |_| {}).is_err(),
+                "nothing requested"
+            );
+            // the renderer took it, which is out of reach here, so stand in for it
+            *shots = ScreenshotManager::default();
+            let shot = world.resource::<Capture>().shot.clone();
+            shot.lock().unwrap().push((n, rendered()));
+            world.run_system_once(save_captures);
+        }
+        let mut pngs: Vec<_> = (std::fs::read_dir(&dir).unwrap())
+            .map(|f| f.unwrap().file_name().into_string().unwrap())
+            .collect();
+        pngs.sort();
+        assert_eq!(pngs, ["00000.png", "00001.png", "00002.png"]);
+        let png = std::fs::read(dir.join("00002.png")).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn uneven_frames_land_on_the_same_steps() {
+        let (mut even, mut uneven) = (CueSequencer::default(), CueSequencer::default());
+        // a second of frames either way, the uneven ones a mix of short, long and in between
+        let frames = [
+            0.004, 0.05, 0.0167, 0.001, 0.1, 0.03, 0.0253, 0.1, 0.2, 0.08, 0.3, 0.093,
+        ];
+        assert!((frames.iter().sum::<f32>() - 1.).abs() < 1e-4);
+        let even_times: Vec<_> = (0..=60)
+            .map(|i| {
+                if i > 0 {
+                    even.advance(1. / 60.);
+                }
+                even.time
+            })
+            .collect();
+        for dt in frames {
+            uneven.advance(dt);
+            assert!(
+                even_times.iter().any(|t| (t - uneven.time).abs() < 1e-4),
+                "{} isn't a step even frames land on",
+                uneven.time
+            );
+        }
+        assert!((even.time - 1.).abs() < 1e-4, "{}", even.time);
+        assert!(
+            (uneven.time - even.time).abs() < 1e-4,
+            "{} vs {}",
+            uneven.time,
+            even.time
+        );
+    }
+
+    // the atlas index and flip of a sprite animating idx_beg..=idx_end (or idx_left facing left)
+    // after elapsed seconds of 0.1s frames
+    fn animated(
+        cycle: Cycle,
+        range: (usize, usize),
+        left: Option<(usize, usize)>,
+        facing_left: bool,
+        elapsed: f32,
+    ) -> (usize, bool) {
+        use bevy::ecs::system::RunSystemOnce;
+        let mut world = World::new();
+        let mut time = GameTime::default();
+        time.advance(elapsed, 1.);
+        world.insert_resource(time);
+        let e = (world.spawn((
+            TextureAtlas::default(),
+            TextureAnimate {
+                frame_len: 0.1,
+                cycle,
+                idx_beg: range.0,
+                idx_end: range.1,
+                idx_left: left,
+                elapsed: 0.,
+            },
+            Facing { left: facing_left },
+            Sprite::default(),
+        )))
+        .id();
+        world.run_system_once(animate_texture);
+        let index = world.get::<TextureAtlas>(e).unwrap().index;
+        (index, world.get::<Sprite>(e).unwrap().flip_x)
+    }
+
+    #[test]
+    fn the_camera_follows_the_baby_wherever_it_is() {
+        use bevy::ecs::system::RunSystemOnce;
+        let mut world = World::new();
+        let camera = (world.spawn((
+            OrthographicProjection::default(),
+            Transform::default(),
+            MainCamera,
+            Bezier(CubicSegment::new_bezier(
+                Vec2::new(0.35, 0.),
+                Vec2::new(0.7, 1.),
+            )),
+        )))
+        .id();
+        let baby = (world.spawn((Name::new("baby"), Transform::from_xyz(60., -200., 0.)))).id();
+        let camera_at = |world: &mut World, time: f32| {
+            world.insert_resource(CueSequencer { time, ..default() });
+            world.run_system_once(sequence_camera);
+            world.get::<Transform>(camera).unwrap().translation.xy()
+        };
+        // the end of the zoom in, then the start of the pull back, both just above the baby
+        assert_eq!(camera_at(&mut world, 60.), Vec2::new(60., -185.));
+        assert_eq!(camera_at(&mut world, 65.), Vec2::new(60., -185.));
+        world.get_mut::<Transform>(baby).unwrap().translation = Vec3::new(-40., 10., 0.);
+        assert_eq!(camera_at(&mut world, 60.), Vec2::new(-40., 25.));
+        assert_eq!(camera_at(&mut world, 65.5), Vec2::new(-40., 90.));
+        // halfway through the zoom in it's partway from the origin to the baby
+        let half = camera_at(&mut world, 40.);
+        assert!(
+            half.x < 0. && half.x > -40. && half.y > 0. && half.y < 25.,
+            "{half}"
+        );
+        // and with no baby it stays put
+        world.despawn(baby);
+        assert_eq!(camera_at(&mut world, 60.), half);
+    }
+
+    #[test]
+    fn the_zoom_in_pans_and_zooms_on_their_own_curves() {
+        use bevy::ecs::system::RunSystemOnce;
+        let bezier = CubicSegment::new_bezier(Vec2::new(0.35, 0.), Vec2::new(0.7, 1.));
+        let mut world = World::new();
+        world.spawn((
+            OrthographicProjection::default(),
+            Transform::default(),
+            MainCamera,
+            Bezier(bezier.clone()),
+        ));
+        world.spawn((Name::new("baby"), Transform::from_xyz(100., -15., 0.)));
+        // halfway through the zoom in, from the origin at scale 1 to just above the baby at 0.4
+        world.insert_resource(CueSequencer {
+            time: 40.,
+            ..default()
+        });
+        world.run_system_once(sequence_camera);
+        let (proj, t) = (world.query::<(&OrthographicProjection, &Transform)>()).single(&world);
+        let panned = t.translation.x / 100.;
+        let zoomed = (1. - proj.scale) / 0.6;
+        // the pan eases out and is mostly there, the zoom is on the camera's shared curve
+        assert!((panned - Ease::Out.ease(0.5)).abs() < 1e-4, "{panned}");
+        assert!((zoomed - bezier.ease(0.5)).abs() < 1e-4, "{zoomed}");
+        assert!(panned - zoomed > 0.1, "{panned} {zoomed}");
+    }
+
+    #[test]
+    fn swapping_to_a_shorter_sheet_keeps_the_frame_on_it() {
+        use bevy::ecs::system::RunSystemOnce;
+        let sheet = |id: u128, frames: usize| Sheet {
+            texture: Handle::weak_from_u128(id),
+            layout: Handle::weak_from_u128(id),
+            idx_beg: 0,
+            idx_end: frames - 1,
+        };
+        let (idle, jump) = (sheet(1, 5), sheet(2, 3));
+        let mut world = World::new();
+        world.insert_resource(GameTime::default());
+        // on the last frame of the idle sheet
+        let e = (world.spawn((
+            SpriteSheets::new(vec![("idle", idle.clone()), ("jump", jump.clone())]),
+            idle.texture.clone(),
+            TextureAtlas {
+                layout: idle.layout.clone(),
+                index: 4,
+            },
+            TextureAnimate {
+                frame_len: 0.1,
+                cycle: Cycle::Loop,
+                idx_beg: 0,
+                idx_end: 4,
+                idx_left: None,
+                elapsed: 0.45,
+            },
+            Facing::default(),
+            Sprite::default(),
+        )))
+        .id();
+        world.get_mut::<SpriteSheets>(e).unwrap().current = "jump";
+        world.run_system_once(swap_sheets);
+        assert_eq!(world.get::<Handle<Image>>(e), Some(&jump.texture));
+        let atlas = world.get::<TextureAtlas>(e).unwrap();
+        assert_eq!((&atlas.layout, atlas.index), (&jump.layout, 0));
+        let mut seen = vec![];
+        for _ in 0..20 {
+            world.resource_mut::<GameTime>().advance(0.07, 1.);
+            world.run_system_once(animate_texture);
+            let index = world.get::<TextureAtlas>(e).unwrap().index;
+            assert!(index <= 2, "frame {index} of a 3 frame sheet");
+            if !seen.contains(&index) {
+                seen.push(index);
+            }
+        }
+        assert_eq!(seen.len(), 3);
+    }
+
+    #[test]
+    fn reversed_ranges_play_backwards() {
+        let frames = |cycle: Cycle, range: (usize, usize)| {
+            (0..8)
+                .map(|i| animated(cycle, range, None, false, i as f32 * 0.1 + 0.05).0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(frames(Cycle::Loop, (5, 2)), [5, 4, 3, 2, 5, 4, 3, 2]);
+        assert_eq!(frames(Cycle::PingPong, (5, 2)), [5, 4, 3, 2, 3, 4, 5, 4]);
+        // the same frames as the forward range, just the other way round
+        assert_eq!(frames(Cycle::Loop, (2, 5)), [2, 3, 4, 5, 2, 3, 4, 5]);
+        assert_eq!(frames(Cycle::PingPong, (3, 3)), [3; 8]);
+    }
+
+    #[test]
+    fn directional_sheets_play_their_own_frames() {
+        let frames = |facing_left: bool, left: Option<(usize, usize)>| {
+            (0..8)
+                .map(|i| {
+                    animated(
+                        Cycle::Loop,
+                        (0, 3),
+                        left,
+                        facing_left,
+                        i as f32 * 0.1 + 0.05,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        // a directional sheet plays the left range facing left, unflipped since it's drawn that way
+        let no_flip = |ids: &[usize]| ids.iter().map(|&i| (i, false)).collect::<Vec<_>>();
+        assert_eq!(
+            frames(false, Some((4, 7))),
+            no_flip(&[0, 1, 2, 3, 0, 1, 2, 3])
+        );
+        assert_eq!(
+            frames(true, Some((4, 7))),
+            no_flip(&[4, 5, 6, 7, 4, 5, 6, 7])
+        );
+        // a symmetric one plays the same frames either way, flipped facing left
+        assert_eq!(frames(false, None), no_flip(&[0, 1, 2, 3, 0, 1, 2, 3]));
+        let flipped: Vec<_> = [0, 1, 2, 3, 0, 1, 2, 3].map(|i| (i, true)).into();
+        assert_eq!(frames(true, None), flipped);
+    }
+
+    // every intro sprite's animation time and frame after a second of the intro in frames of
+    // these lengths, by name
+    fn animated_after(frames: &[f32]) -> Vec<(String, f32, usize)> {
+        let mut app = crate::tests::headless();
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        app.update();
+        for dt in frames {
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_secs_f32(*dt),
+            ));
+            app.update();
+        }
+        let mut sprites = app.world.query::<(&Name, &TextureAtlas, &TextureAnimate)>();
+        let mut anims: Vec<_> = (sprites.iter(&app.world))
+            .map(|(name, atlas, anim)| (name.to_string(), anim.elapsed, atlas.index))
+            .collect();
+        anims.sort_by(|a, b| a.0.cmp(&b.0));
+        anims
+    }
+
+    #[test]
+    fn intro_animations_dont_depend_on_frame_times() {
+        let even = animated_after(&[1. / 30.; 30]);
+        // the same second as alternating short and long frames
+        let uneven: Vec<_> = (0..30).map(|i| [1. / 60., 1. / 20.][i % 2]).collect();
+        let uneven = animated_after(&uneven);
+        assert!(!even.is_empty());
+        assert_eq!(even, uneven);
+    }
+
+    #[test]
+    fn anim_reset_starts_the_sprite_from_its_first_frame() {
+        let mut app = crate::tests::headless();
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        app.update();
+        let baby = Name::new("baby");
+        let reset = app.world.resource::<CueSequencer>().anim_reset[&baby][0];
+        // a frame and a half before the reset, with the sprite a few frames into its animation
+        app.world.resource_mut::<CueSequencer>().time = reset - 1.5 / 60.;
+        let mut sprite = app
+            .world
+            .query::<(&Name, &TextureAtlas, &mut TextureAnimate)>();
+        let (_, _, mut anim) = (sprite.iter_mut(&mut app.world))
+            .find(|(n, ..)| **n == baby)
+            .unwrap();
+        anim.elapsed = anim.frame_len * 2.5;
+        let mut frame = |app: &mut App| {
+            app.update();
+            let (_, atlas, anim) = (sprite.iter(&app.world))
+                .find(|(n, ..)| **n == baby)
+                .unwrap();
+            let time = app.world.resource::<CueSequencer>().time;
+            (time >= reset, atlas.index, anim.idx_beg)
+        };
+        let (after, index, beg) = frame(&mut app);
+        assert!(!after && index != beg, "frame {index} of {beg}..");
+        let (after, index, beg) = frame(&mut app);
+        assert!(after);
+        assert_eq!(index, beg);
+    }
+
+    // the intro run to its end with attract mode set to attract, returning every state the fades
+    // at its end went to and whether the sequence is playing again
+    fn intro_end(attract: bool) -> (Vec<Option<crate::AppState>>, bool) {
+        let mut app = crate::tests::headless();
+        app.insert_resource(AttractMode(attract));
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        app.update();
+        let mut sequence = app.world.resource_mut::<CueSequencer>();
+        sequence.time = sequence.end;
+        let (mut states, mut faded) = (vec![], bevy::ecs::event::ManualEventReader::default());
+        for _ in 0..90 {
+            app.update();
+            let ev = app.world.resource::<Events<crate::fade::FadedOut>>();
+            states.extend(
+                faded
+                    .read(ev)
+                    .map(|ev: &crate::fade::FadedOut| ev.0.clone()),
+            );
+        }
+        // the game removes the sequence along with the rest of the intro
+        let sequence = app.world.get_resource::<CueSequencer>();
+        (states, sequence.is_some_and(|s| s.playing && s.time < 1.))
+    }
+
+    #[test]
+    fn the_intro_leads_into_the_start_level() {
+        let path =
+            std::env::temp_dir().join(format!("baby-intro-start-{}.map", std::process::id()));
+        let mut start = crate::tests::open_level();
+        start.name = Some("start".into());
+        std::fs::write(&path, start.to_text()).unwrap();
+        let mut app = crate::tests::headless();
+        app.insert_resource(StartLevel(Some(path.clone())));
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        app.update();
+        // the intro starts on the usual map
+        assert_ne!(app.world.resource::<Level>().name.as_deref(), Some("start"));
+        let mut sequence = app.world.resource_mut::<CueSequencer>();
+        sequence.time = sequence.end;
+        for _ in 0..90 {
+            app.update();
+        }
+        std::fs::remove_file(path).unwrap();
+        let state = app.world.resource::<State<crate::AppState>>();
+        assert_eq!(*state.get(), crate::AppState::Game);
+        assert_eq!(app.world.resource::<Level>().name.as_deref(), Some("start"));
+        // and the game was set up on it, a floor 11 tiles wide
+        let mut tiles = app.world.query::<&crate::physics::Tile>();
+        assert_eq!(tiles.iter(&app.world).count(), 11);
+    }
+
+    #[test]
+    fn loading_waits_for_every_intro_asset() {
+        use crate::AppState;
+        let mut app = crate::tests::headless();
+        let state = |app: &App| app.world.resource::<State<AppState>>().get().clone();
+        // whether every intro asset has loaded, or failed to
+        let done = |app: &App| {
+            let server = app.world.resource::<AssetServer>();
+            (app.world.resource::<IntroAssets>().0.iter()).all(|h| {
+                matches!(
+                    server.get_load_state(h.id()),
+                    Some(LoadState::Loaded | LoadState::Failed)
+                )
+            })
+        };
+        // the sounds load for real off the disk, which takes a few frames at least
+        app.update();
+        assert_eq!(state(&app), AppState::Loading);
+        assert!(!done(&app));
+        let mut waited = 0;
+        loop {
+            let was_done = done(&app);
+            app.update();
+            if state(&app) == AppState::Intro {
+                // going on needed everything loaded as of the frame before
+                assert!(was_done);
+                break;
+            }
+            assert_eq!(state(&app), AppState::Loading);
+            waited += 1;
+            assert!(waited < 1000, "never finished loading");
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let mut screen = app.world.query_filtered::<(), With<LoadingScreen>>();
+        assert_eq!(screen.iter(&app.world).count(), 0);
+    }
+
+    #[test]
+    fn attract_mode_replays_the_intro_instead_of_starting_the_game() {
+        assert_eq!(intro_end(true), (vec![None], true));
+        assert_eq!(intro_end(false), (vec![Some(crate::AppState::Game)], false));
+    }
+
+    #[test]
+    fn subtitles_fade_across_a_change() {
+        let mut app = crate::tests::headless();
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        app.update();
+        let subs = app.world.resource::<CueSequencer>().subtitles.clone();
+        // a subtitle that's up for a good while, and the one after it
+        let i = (0..subs.len() - 1)
+            .find(|&i| !subs[i].1.is_empty() && subs[i + 1].0 - subs[i].0 > 1.)
+            .unwrap();
+        let (start, next) = (subs[i].0, subs[i + 1].0);
+        let at = |app: &mut App, time: f32| {
+            // the frame steps it on a sixtieth
+            app.world.resource_mut::<CueSequencer>().time = time - 1. / 60.;
+            app.update();
+            let mut text = app.world.query_filtered::<&Text, With<Subtitle>>();
+            let section = &text.single(&app.world).sections[0];
+            (section.value.clone(), section.style.color.a())
+        };
+        let (text, alpha) = at(&mut app, start + 0.15);
+        assert_eq!(text, subs[i].1);
+        assert!((alpha - 0.5).abs() < 0.1, "{alpha} fading in");
+        assert_eq!(at(&mut app, start + 0.5).1, 1.);
+        let (text, alpha) = at(&mut app, next - 0.1);
+        assert_eq!(text, subs[i].1);
+        assert!((alpha - 1. / 3.).abs() < 0.1, "{alpha} fading out");
+    }
+
+    // the names of everything the intro has spawned, sorted
+    fn intro_names(app: &mut App) -> Vec<String> {
+        let mut names = app.world.query::<&Name>();
+        let mut names: Vec<_> = names.iter(&app.world).map(|n| n.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    fn intro_sounds(app: &mut App) -> usize {
+        let mut audio = app.world.query::<&Handle<AudioSource>>();
+        audio.iter(&app.world).count()
+    }
+
+    #[test]
+    fn entering_the_intro_again_starts_it_over() {
+        let mut app = crate::tests::headless();
+        let enter = |app: &mut App, state| {
+            (app.world.resource_mut::<NextState<crate::AppState>>()).set(state);
+            app.update();
+        };
+        enter(&mut app, crate::AppState::Intro);
+        let (first, sounds) = (intro_names(&mut app), intro_sounds(&mut app));
+        assert!(!first.is_empty() && sounds > 0);
+        for _ in 0..120 {
+            app.update();
+        }
+        assert!(app.world.resource::<CueSequencer>().time > 1.9);
+        enter(&mut app, crate::AppState::Game);
+        assert!(app.world.get_resource::<CueSequencer>().is_none());
+        enter(&mut app, crate::AppState::Intro);
+        assert!(app.world.resource::<CueSequencer>().time < 0.02);
+        // the game's own names are still around, but every one of the intro's is back
+        let second = intro_names(&mut app);
+        assert!(
+            first.iter().all(|n| second.contains(n)),
+            "{first:?}\n{second:?}"
+        );
+        assert_eq!(intro_sounds(&mut app), sounds);
+    }
+
+    #[test]
+    fn the_active_cue_is_the_last_one_started() {
+        let mut app = crate::tests::headless();
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        let sequence = app.world.resource::<CueSequencer>();
+        // the intro is one of two at random
+        let described = |cues: &[Q]| -> Vec<String> {
+            (cues.iter().enumerate())
+                .filter(|(_, cue)| !matches!(cue, Q::Tick(_)))
+                .map(|(i, cue)| format!("{i} {cue:?}"))
+                .collect()
+        };
+        let shown: Vec<_> = sequence.cues.iter().map(|(_, cue)| cue.clone()).collect();
+        let cues = [ANIM_CUE_JAZZ, ANIM_CUE_WAIL]
+            .into_iter()
+            .find(|cues| described(cues) == shown)
+            .unwrap();
+        // a moment after the fifth wait, the cue that's running is the last one before it
+        let (mut time, mut waits, mut active) = (0., 0, None);
+        for (i, cue) in cues.iter().enumerate() {
+            match cue {
+                Q::Tick(_) if waits == 5 => break,
+                Q::Tick(t) => {
+                    time += t;
+                    waits += 1;
+                }
+                _ => active = Some(format!("{i} {cue:?}")),
+            }
+        }
+        assert_eq!(sequence.get_cue(time + 0.01), active.as_deref());
+        assert_eq!(sequence.get_cue(-1.), None);
+    }
+
+    #[test]
+    fn a_sound_without_a_vol_cue_plays_at_its_own_volume() {
+        let mut app = crate::tests::headless();
+        (app.world.resource_mut::<NextState<crate::AppState>>()).set(crate::AppState::Intro);
+        app.update();
+        let mut sounds = app.world.query::<(&Name, &PlaybackSettings)>();
+        let thump = sounds
+            .iter(&app.world)
+            .find(|(name, _)| name.as_str() == "thump");
+        assert_eq!(thump.unwrap().1.volume.get(), 0.3);
+        // thump never gets a Vol cue, so all the while it plays it stays quiet
+        let mut sequence = app.world.resource_mut::<CueSequencer>();
+        let heard: Vec<_> = (0..1200)
+            .filter_map(|i| sequence.get_audio(&Name::new("thump"), i as f32 * 0.1))
+            .filter(|(_, paused)| !paused)
+            .map(|(vol, _)| vol)
+            .collect();
+        assert!(
+            !heard.is_empty() && heard.iter().all(|&vol| vol == 0.3),
+            "{heard:?}"
+        );
+    }
+
+    #[test]
+    fn a_path_arcs_away_from_the_straight_line_and_ends_on_it() {
+        use bevy::ecs::system::RunSystemOnce;
+        let (from, to) = (Vec2::new(30., 0.), Vec2::new(-220., -100.));
+        let mut world = World::new();
+        let mut time = GameTime::default();
+        time.advance(0.5, 1.);
+        world.insert_resource(time);
+        let e = (world.spawn((
+            Transform::from_translation(from.extend(3.)),
+            PathFollow {
+                segment: CubicSegment::new_bezier(Vec2::new(0.3, -0.8), Vec2::new(0.6, -0.6)),
+                from,
+                to,
+                duration: 1.,
+                elapsed: 0.,
+            },
+        )))
+        .id();
+        // half way through it's gone up first, above the line straight to the end
+        world.run_system_once(follow_paths);
+        let half = world.get::<Transform>(e).unwrap().translation;
+        assert!(half.y > from.lerp(to, 0.5).y + 10., "{half}");
+        assert_eq!(half.z, 3.);
+        // and gets there in the end, when it's done with the path
+        world.run_system_once(follow_paths);
+        let end = world.get::<Transform>(e).unwrap().translation;
+        assert!(end.abs_diff_eq(to.extend(3.), 1e-3), "{end}");
+        assert!(world.get::<PathFollow>(e).is_none());
+    }
+}
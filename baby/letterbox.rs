@@ -0,0 +1,96 @@
+use bevy::{prelude::*, render::camera::Viewport, window::PrimaryWindow};
+
+use crate::intro::MainCamera;
+
+// keep the main camera drawing to the largest WINDOW_WIDTH x WINDOW_HEIGHT shaped area that fits
+// in the window, centered. the rest of the window is just ClearColor, so the art stays framed the
+// same at any window size without covering the edges up with black quads
+pub fn fit_viewport(
+    win: Query<&Window, With<PrimaryWindow>>,
+    mut cams: Query<&mut Camera, With<MainCamera>>,
+) {
+    let Ok(win) = win.get_single() else {
+        return;
+    };
+    let size = UVec2::new(win.physical_width(), win.physical_height());
+    if size.x == 0 || size.y == 0 {
+        // minimized
+        return;
+    }
+    let aspect = super::WINDOW_WIDTH / super::WINDOW_HEIGHT;
+    let fit = if size.x as f32 > size.y as f32 * aspect {
+        UVec2::new((size.y as f32 * aspect) as u32, size.y)
+    } else {
+        UVec2::new(size.x, (size.x as f32 / aspect) as u32)
+    }
+    .max(UVec2::ONE);
+    let pos = (size - fit) / 2;
+    for mut cam in &mut cams {
+        let same = (cam.viewport.as_ref())
+            .is_some_and(|v| v.physical_position == pos && v.physical_size == fit);
+        if !same {
+            cam.viewport = Some(Viewport {
+                physical_position: pos,
+                physical_size: fit,
+                ..default()
+            });
+        }
+    }
+}
+
+// world position under the cursor, taking the letterbox bars into account
+// or why there isn't one, for the editor to say why a click did nothing
+pub fn cursor_to_world(
+    cam: &Camera,
+    cam_gtrans: &GlobalTransform,
+    win: &Window,
+) -> Result<Vec2, &'static str> {
+    let cursor = win
+        .cursor_position()
+        .ok_or("the cursor isn't in the window")?;
+    let offset = (cam.logical_viewport_rect())
+        .ok_or("the camera has no viewport yet")?
+        .min;
+    (cam.viewport_to_world_2d(cam_gtrans, cursor - offset))
+        .ok_or("the cursor couldn't be turned into a world position")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn viewport_keeps_the_game_aspect_in_any_window() {
+        let aspect = crate::WINDOW_WIDTH / crate::WINDOW_HEIGHT;
+        for (w, h) in [
+            (1280., 720.),
+            (800., 800.),
+            (3000., 600.),
+            (333., 1000.),
+            (1., 1.),
+        ] {
+            let mut world = World::new();
+            let mut window = Window::default();
+            window.resolution.set_scale_factor_override(Some(1.));
+            window.resolution.set(w, h);
+            world.spawn((window, PrimaryWindow));
+            let cam = world.spawn((Camera::default(), MainCamera)).id();
+            world.run_system_once(fit_viewport);
+            let view = world.get::<Camera>(cam).unwrap().viewport.clone().unwrap();
+            let (pos, size) = (view.physical_position, view.physical_size);
+            let got = size.x as f32 / size.y as f32;
+            // a pixel either way from rounding down to whole pixels
+            let slack = 1. / size.x.min(size.y) as f32 + 1e-3;
+            // a single pixel can't be any shape but square
+            if w > 1. {
+                assert!((got / aspect - 1.).abs() <= slack, "{w}x{h}: {size}");
+            }
+            // as big as fits, touching both sides or the top and bottom, and centered
+            let win = UVec2::new(w as u32, h as u32);
+            assert!(size.x == win.x || size.y == win.y, "{w}x{h}: {size}");
+            assert!(size.x <= win.x && size.y <= win.y, "{w}x{h}: {size}");
+            assert_eq!(pos, (win - size) / 2, "{w}x{h}");
+        }
+    }
+}
@@ -1,14 +1,21 @@
-use std::collections::HashMap as Map;
+use std::collections::VecDeque;
 use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
 
+use crate::fade::{fade_transition, Fade, FadedOut};
 use crate::intro::Cycle;
-use crate::intro::TextureAnimate;
+use crate::intro::GameTime;
+use crate::intro::{Facing, Sheet, SpriteSheets, TextureAnimate};
+use crate::letterbox;
+use crate::map::{bmp_color, world_to_tile, Level, BMP_PALETTE};
+use crate::physics::{self, GameFeel, MaxVelocity, Movement, Tile};
 use crate::AppState;
 
 use bevy::{
     app::AppExit,
+    ecs::system::SystemParam,
     input::mouse::MouseWheel,
-    math::bounding::{Aabb2d, BoundingVolume, IntersectsVolume},
+    math::bounding::{Aabb2d, Bounded2d, BoundingVolume, IntersectsVolume},
     prelude::*,
     render::camera::ScalingMode,
     window::PrimaryWindow,
@@ -16,52 +23,500 @@ use bevy::{
 
 #[derive(Component)]
 pub struct Control;
+// the phases of a game frame, run in this order. a system goes in the phase it belongs to rather
+// than being chained in by hand, so nothing sees the player where it was last frame or moves it
+// twice: input sets Movement, physics works out Movement::out, move applies it to the transform,
+// and the camera follows where the player ended up
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    Input,
+    Physics,
+    Move,
+    Camera,
+}
+// physics ticks not yet run, carried over to the next frame. a frame never runs more than
+// max_ticks of them, so a long stall plays out a little slower instead of running a pile of
+// ticks that makes the next frame long too
 #[derive(Resource)]
-pub struct PhysicsTick(pub f32);
-#[derive(Component, Default)]
-pub struct Movement {
-    ctl: Vec2,
-    force: Vec2,
-    out: Vec2,
-    climb: bool,
-}
-#[derive(Component, Deref, DerefMut, Clone, Copy, Debug, PartialEq)]
-pub struct Tile(u8);
+pub struct PhysicsTick {
+    pub rem: f32,
+    pub max_ticks: f32,
+}
+impl Default for PhysicsTick {
+    fn default() -> Self {
+        PhysicsTick {
+            rem: 0.,
+            max_ticks: 8.,
+        }
+    }
+}
+// transform units per physics unit. physics runs in its own units (the tile cache, the player's
+// box and Movement are all in them), check_collide converts the player's transform on the way in
+// and update_movement converts Movement::out on the way back. 1 keeps them the same
+#[derive(Resource, Clone, Copy)]
+pub struct PhysicsScale(pub f32);
+impl Default for PhysicsScale {
+    fn default() -> Self {
+        PhysicsScale(1.)
+    }
+}
+impl PhysicsScale {
+    pub fn to_physics(self, aabb: Aabb2d) -> Aabb2d {
+        Aabb2d::new(aabb.center() / self.0, aabb.half_size() / self.0)
+    }
+    pub fn to_render(self, aabb: Aabb2d) -> Aabb2d {
+        Aabb2d::new(aabb.center() * self.0, aabb.half_size() * self.0)
+    }
+}
 #[derive(Event)]
 pub struct Quit; // custom quit event used to save map before actual AppExit
 #[derive(Component)]
 pub struct WinText;
 #[derive(Resource)]
-pub struct End(bool);
-
-impl Tile {
-    const SZ: f32 = 50.;
-    const NUM: usize = 6;
-    const SQUARE: Tile = Tile(1);
-    const STEPR: Tile = Tile(2);
-    const STEPL: Tile = Tile(3);
-    const SLOPER: Tile = Tile(4);
-    const SLOPEL: Tile = Tile(5);
-
-    const COLORS: [Color; 6] = [
-        Color::NONE,
-        Color::WHITE,
-        Color::RED,
-        Color::BLUE,
-        Color::ORANGE,
-        Color::GREEN,
-    ];
+pub struct End(pub bool);
+// sent by check_collide every time the player is pushed out of a tile
+#[derive(Event, Debug)]
+pub struct CollisionEvent {
+    // the player that hit it
+    pub entity: Entity,
+    pub tile: Tile,
+    // out of the surface that was hit, up for a floor
+    pub normal: Vec2,
+    // player velocity (ctl + force) going into the collision
+    pub vel: Vec2,
+}
+// sent by check_collide when a moving platform squeezes the player against something with no
+// room left between them, which kills them like running out of health
+#[derive(Event, Debug)]
+pub struct Crushed(pub Entity);
+// sent once when a player lands on a plate tile, and PlateReleased once when the last player
+// steps off it
+#[derive(Event, Debug)]
+pub struct PlatePressed {
+    pub entity: Entity,
+}
+#[derive(Event, Debug)]
+pub struct PlateReleased {
+    pub entity: Entity,
+}
+// players (and anything else with Control) pass through each other unless this is set,
+// tiles always collide
+#[derive(Resource)]
+pub struct PlayerCollide(pub bool);
+// where escape saves map.map and friends, the working directory unless it's moved somewhere else
+#[derive(Resource)]
+pub struct SaveDir(pub PathBuf);
+impl Default for SaveDir {
+    fn default() -> Self {
+        SaveDir(PathBuf::from("."))
+    }
+}
+// world space box around every tile of the loaded map, filled in by setup
+#[derive(Resource)]
+pub struct MapBounds(pub Rect);
+
+impl MapBounds {
+    // how far below the map the player can fall before the kill box catches them
+    const KILL_DEPTH: f32 = 10. * Tile::SZ;
+
+    // the tiles' centers, grown by half a tile so the box covers the tiles themselves
+    pub fn from_tiles(tiles: impl IntoIterator<Item = Vec2>) -> MapBounds {
+        let mut tiles = tiles.into_iter();
+        let Some(first) = tiles.next() else {
+            return MapBounds(Rect::default());
+        };
+        let rect = tiles.fold(Rect::from_center_size(first, Vec2::ZERO), |r, pos| {
+            r.union_point(pos)
+        });
+        MapBounds(rect.inset(Tile::SZ / 2.))
+    }
+}
+
+// how far the player can get from the camera (plus offset) on each axis before it follows
+#[derive(Resource)]
+pub struct CameraFollow {
+    pub deadzone: Vec2,
+    // where the camera centers relative to the player, eg. a positive y shows more above the player
+    pub offset: Vec2,
+    // fraction of the way to its target the camera moves each 60th of a second, 1 snaps
+    pub gain: Vec2,
+    // only follow vertically while the player is standing, where it recenters on them, so jumps
+    // don't bob the camera. leaving the deadzone in the air (a long fall) still follows
+    pub settle_on_ground: bool,
+    // off while the editor camera has been dragged somewhere else
+    pub enabled: bool,
+}
+impl Default for CameraFollow {
+    fn default() -> Self {
+        CameraFollow {
+            deadzone: Vec2::new(100., 100.),
+            offset: Vec2::ZERO,
+            gain: Vec2::new(1., 0.1),
+            settle_on_ground: true,
+            enabled: true,
+        }
+    }
+}
+// counts changes to the tiles (moved, retyped, added, removed, or made decor or composite),
+// bumped by watch_tiles so anything built from the tiles can tell when the editor changed them
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub struct MapVersion(pub u64);
+// the tiles' collision boxes, rebuilt only when the map version changes
+#[derive(Resource, Default)]
+pub struct TileCache {
+    version: Option<MapVersion>,
+    tiles: Vec<(Tile, Aabb2d)>,
+    // rows of squares collide as one box each (physics::merge_squares) and ramps of slopes as one
+    // triangle (physics::merge_slopes), toggled in the editor with G. the tiles are drawn the
+    // same either way
+    pub merge: bool,
+}
+// what holding left and right together does. BABY_SOCD=last makes the one pressed most recently
+// win, BABY_SOCD=first the one held longer, and otherwise they cancel out
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Socd {
+    #[default]
+    Neutral,
+    LastWins,
+    FirstWins,
+}
+
+impl Socd {
+    pub fn from_env() -> Socd {
+        match std::env::var("BABY_SOCD").as_deref() {
+            Err(_) | Ok("neutral") => Socd::Neutral,
+            Ok("last") => Socd::LastWins,
+            Ok("first") => Socd::FirstWins,
+            Ok(other) => {
+                eprintln!("unknown BABY_SOCD {other:?}, should be neutral, last or first");
+                Socd::Neutral
+            }
+        }
+    }
+
+    // sideways input, newest is the direction of the last of the two keys to go down
+    pub fn resolve(self, left: bool, right: bool, newest: f32) -> f32 {
+        match (left, right, self) {
+            (true, true, Socd::Neutral) => 0.,
+            (true, true, Socd::LastWins) => newest,
+            (true, true, Socd::FirstWins) => -newest,
+            (true, false, _) => -1.,
+            (false, true, _) => 1.,
+            (false, false, _) => 0.,
+        }
+    }
+}
+
+// X dashes in the held direction (or the way the player faces) for a moment, once per landing
+// and no sooner than GameFeel::dash_cooldown after the last one
+#[derive(Component, Default)]
+pub struct Dash {
+    ready: bool,
+    // seconds of dash left, and which way it's going
+    left: f32,
+    dir: Vec2,
+    // seconds until the next afterimage
+    trail: f32,
+}
+// a tile that collides as smaller shapes instead of its own, each (shape, offset, size) with
+// offset and size in tiles from the tile's center. alt-click puts RAMPED on a tile in the editor.
+// two parts for now
+#[derive(Component, Clone, Copy)]
+pub struct Composite(pub [(Tile, Vec2, Vec2); 2]);
+impl Composite {
+    // a square bottom half with a slope on top
+    pub const RAMPED: Composite = Composite([
+        (Tile::SQUARE, Vec2::new(0., -0.25), Vec2::new(1., 0.5)),
+        (Tile::SLOPER, Vec2::new(0., 0.25), Vec2::new(1., 0.5)),
+    ]);
+
+    fn colliders(&self, t: &Transform) -> impl Iterator<Item = (Tile, Aabb2d)> + '_ {
+        let (pos, size) = (t.translation.xy(), t.scale.xy());
+        (self.0.iter()).map(move |&(c, off, sz)| (c, Aabb2d::new(pos + off * size, sz * size / 2.)))
+    }
+}
+// an area that pushes the player by this much every physics tick while they overlap it, without
+// blocking anything. the area is the transform's translation and scale, like a tile's
+#[derive(Component)]
+pub struct Wind(pub Vec2);
+
+// a solid block that eases out from home by travel and back again every period seconds, the block
+// is the transform's translation and scale, like a tile's
+#[derive(Component)]
+pub struct Platform {
+    home: Vec2,
+    travel: Vec2,
+    period: f32,
+    time: f32,
+}
+
+impl Platform {
+    // slowest at either end, so it speeds up heading down from the top
+    fn at(&self) -> Vec2 {
+        let phase = std::f32::consts::TAU * self.time / self.period;
+        self.home + self.travel * (1. - phase.cos()) / 2.
+    }
+}
+
+// while the player is inside one the camera eases over to look at target at scale, instead of
+// following them, and goes back to following once they leave. the area is the transform's
+// translation and scale, like a tile's
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct CameraTrigger {
+    pub target: Vec2,
+    pub scale: f32,
+}
+
+// the trigger the player is in, and the camera scale from before the first one took over, to go
+// back to once they're out
+#[derive(Resource, Default)]
+pub struct CameraCue {
+    pub active: Option<CameraTrigger>,
+    restore: Option<f32>,
+}
+
+impl CameraCue {
+    // fraction of the way to the cue the camera moves each 60th of a second
+    const GAIN: f32 = 0.05;
+}
+
+// a breakable tile on its way out, it's already lost its Tile so nothing collides with it
+#[derive(Component)]
+pub struct Breaking(Timer);
+impl Breaking {
+    const TIME: f32 = 0.3;
+    // downward speed (per physics tick) a landing needs to break the tile
+    const STOMP_SPEED: f32 = 4.;
+    const DEBRIS: usize = 5;
+}
+// a bit of a broken tile flying off, in transform units per physics tick
+#[derive(Component)]
+pub struct Debris {
+    vel: Vec2,
+    life: Timer,
+}
+// a faded copy of the player left behind while dashing, gone when the timer runs out
+#[derive(Component)]
+pub struct Afterimage(Timer);
+
+impl Dash {
+    const TIME: f32 = 0.15;
+    // pixels per physics tick
+    const SPEED: f32 = 12.;
+    const TRAIL_EVERY: f32 = 0.03;
+    const TRAIL_FADE: f32 = 0.25;
+    const MAX_TRAIL: usize = 8;
+}
+#[derive(Component)]
+pub struct Health(pub f32);
+// invulnerability frames after getting hurt, removed once the timer runs out
+#[derive(Component)]
+pub struct Invulnerable(Timer);
+#[derive(Component)]
+pub struct HealthBar;
+
+impl Health {
+    const MAX: f32 = 3.;
+    // how long the player can't be hurt again after a hit
+    const IFRAMES: f32 = 1.;
+}
+
+// everything a player needs to move around and get hurt, the look (atlas, animation) goes on top
+#[derive(Bundle)]
+pub struct PlayerBundle {
+    control: Control,
+    movement: Movement,
+    facing: Facing,
+    health: Health,
+    dash: Dash,
+    sprite: SpriteBundle,
+}
+impl PlayerBundle {
+    pub const SPAWN: Vec2 = Vec2::ZERO;
+    // fraction of the height (and run speed) left while crouching
+    const CROUCH: f32 = 0.5;
+
+    // the transform's scale is the player's size, which is also its collision box. sprite is the
+    // drawn size relative to that, the sprite ends up sprite * size pixels
+    pub fn new(pos: Vec2, size: PlayerSize, sprite: Vec2, texture: Handle<Image>) -> PlayerBundle {
+        PlayerBundle {
+            control: Control,
+            movement: Movement::default(),
+            facing: Facing::default(),
+            health: Health(Health::MAX),
+            dash: Dash::default(),
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(sprite),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: pos.extend(Layer::Player.z()),
+                    scale: size.0.extend(1.),
+                    ..default()
+                },
+                texture,
+                ..default()
+            },
+        }
+    }
+}
+
+// the player's collision box in pixels, BABY_SIZE=<w>x<h> (or just <n> for a square) changes it
+// from 45x45 to try out a bigger or smaller baby. the sprite scales along with it
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PlayerSize(pub Vec2);
+
+impl Default for PlayerSize {
+    fn default() -> Self {
+        PlayerSize(Vec2::splat(45.))
+    }
+}
+
+impl PlayerSize {
+    pub fn from_env() -> PlayerSize {
+        let Ok(size) = std::env::var("BABY_SIZE") else {
+            return PlayerSize::default();
+        };
+        let parsed = match size.split_once('x') {
+            Some((w, h)) => w
+                .trim()
+                .parse()
+                .and_then(|w| Ok(Vec2::new(w, h.trim().parse()?))),
+            None => size.trim().parse().map(Vec2::splat),
+        };
+        match parsed {
+            Ok(v) if v.x > 0. && v.y > 0. && v.is_finite() => PlayerSize(v),
+            _ => {
+                eprintln!("BABY_SIZE should be <w>x<h> or <n> pixels, not {size:?}");
+                PlayerSize::default()
+            }
+        }
+    }
+}
+
+// how the game's sprites stack up, back to front. each layer's z is whole, anything drawn just in
+// front of or behind something (debris, afterimages) stays within a tenth or so of it so it can't
+// cross into the next layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    // the map's flat background color, behind everything
+    Fill,
+    // the big garbage texture behind everything else
+    Backdrop,
+    // the level's painted background
+    Background,
+    // wind and camera trigger areas
+    Zone,
+    Tile,
+    Ghost,
+    Player,
+    // the level's painted foreground, and tiles placed in front of the player
+    Foreground,
+    // the win screen
+    Overlay,
+}
+
+impl Layer {
+    pub fn z(self) -> f32 {
+        match self {
+            Layer::Fill => -11.,
+            Layer::Backdrop => -10.,
+            Layer::Background => -2.,
+            Layer::Zone => -1.,
+            Layer::Tile => 0.,
+            Layer::Ghost => 0.5,
+            Layer::Player => 1.,
+            Layer::Foreground => 2.,
+            Layer::Overlay => 20.,
+        }
+    }
+}
+
+// the map's background color, a quad kept over the camera's view. ClearColor would be simpler,
+// but it also colors the letterbox bars around the view, which stay black
+#[derive(Component)]
+pub struct BackgroundFill;
+
+pub fn fill_background(
+    level: Res<Level>,
+    cam: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut fill: Query<(&mut Transform, &mut Sprite), (With<BackgroundFill>, Without<MainCamera>)>,
+) {
+    let Ok((cam, proj)) = cam.get_single() else {
+        return;
+    };
+    for (mut t, mut sprite) in &mut fill {
+        t.translation = cam.translation.xy().extend(Layer::Fill.z());
+        // twice the view, so it still covers when the editor moves the camera after this runs
+        sprite.custom_size = Some(proj.area.size() * cam.scale.xy() * 2.);
+        if level.is_changed() {
+            sprite.color = level.background_color();
+        }
+    }
+}
+
+// a sprite drawn in front of the player, it fades out while the player is behind it
+// tiles get one when they're spawned above the player's z
+#[derive(Component)]
+pub struct Foreground {
+    // the sprite's own alpha, what it goes back to once the player is clear
+    alpha: f32,
+    // how much of that alpha is showing, eases between FADED and 1
+    shown: f32,
+}
+
+// a tile that's only there to look at, it keeps its shape (for the editor and the map) but
+// nothing collides with it. foreground tiles are always decoration
+#[derive(Component)]
+pub struct Decor;
+
+impl Foreground {
+    const FADED: f32 = 0.35;
+    // fraction of full alpha per second
+    const SPEED: f32 = 4.;
+}
+
+// the tile type clicking empty space places, Tab and Shift+Tab cycle it
+#[derive(Resource)]
+pub struct Paint(pub Tile);
+// one square of the palette strip along the bottom of the editor
+#[derive(Component)]
+pub struct PaletteSwatch(Tile);
+// L shows every tile id with its kind, its color in the game and its color in map.bmp
+#[derive(Component)]
+pub struct PaletteLegend;
+
+// tile types the player passes through, N toggles the paint's type. for telling whether a
+// collision bug is in the physics or in what's drawn, debug builds only
+#[derive(Resource, Default)]
+pub struct NoCollide([bool; Tile::NUM]);
+
+impl Paint {
+    // steps through the placeable types (1..NUM, 0 isn't a tile), wrapping around
+    fn cycle(&mut self, back: bool) {
+        let n = Tile::NUM as u8 - 1;
+        let p = self.0 .0.clamp(1, n);
+        self.0 .0 = if back { (p + n - 2) % n + 1 } else { p % n + 1 };
+    }
 }
 
 pub struct DebugGamePlugin;
 impl Plugin for DebugGamePlugin {
     fn build(&self, app: &mut App) {
         if cfg!(debug_assertions) {
-            app.add_systems(OnEnter(AppState::Game), debug_setup)
+            app.insert_resource(Paint(Tile(1)))
+                .init_resource::<NoCollide>()
+                .init_resource::<ZoomEase>()
+                .init_resource::<EditRepeat>()
+                .add_systems(OnEnter(AppState::Game), debug_setup)
                 .add_systems(
                     PostUpdate,
                     (
-                        debug_check_mouse, //
+                        debug_tick_repeat,
+                        (debug_check_mouse, debug_check_kbd).after(debug_tick_repeat),
+                        debug_dump_movement,
+                        debug_ease_zoom,
                         debug_draw,
                     )
                         .run_if(in_state(AppState::Game)),
@@ -70,209 +525,440 @@ impl Plugin for DebugGamePlugin {
     }
 }
 
+// BABY_PHYSLOG=<file> writes one json object per physics tick to <file>, for charting a run
+// outside the game. nothing is formatted when it's not set, the resource just doesn't exist
+pub struct PhysicsLogPlugin;
+impl Plugin for PhysicsLogPlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(path) = std::env::var("BABY_PHYSLOG") else {
+            return;
+        };
+        match PhysicsLog::create(&path) {
+            Ok(log) => {
+                app.insert_resource(log);
+            }
+            Err(e) => eprintln!("can't log physics to {path:?}: {e}"),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct PhysicsLog {
+    out: std::io::BufWriter<std::fs::File>,
+    tick: u64,
+}
+
+impl PhysicsLog {
+    pub fn create(path: impl AsRef<std::path::Path>) -> std::io::Result<PhysicsLog> {
+        Ok(PhysicsLog {
+            out: std::io::BufWriter::new(std::fs::File::create(path)?),
+            tick: 0,
+        })
+    }
+
+    // {"tick":0,"pos":[x,y],"vel":[x,y],"grounded":false,"pushes":[{"pass":0,"tile":1,"delta":[x,y]}]}
+    fn record(&mut self, step: &physics::Step, v: &Movement) -> std::io::Result<()> {
+        use std::io::Write as _;
+        let (pos, vel) = (step.aabb.center(), v.ctl + v.force);
+        write!(
+            self.out,
+            r#"{{"tick":{},"pos":[{},{}],"vel":[{},{}],"grounded":{},"pushes":["#,
+            self.tick, pos.x, pos.y, vel.x, vel.y, v.grounded,
+        )?;
+        for (i, c) in step.contacts.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(
+                self.out,
+                r#"{sep}{{"pass":{},"tile":{},"delta":[{},{}]}}"#,
+                c.pass, c.tile.0, c.push.delta.x, c.push.delta.y,
+            )?;
+        }
+        writeln!(self.out, "]}}")?;
+        self.tick += 1;
+        Ok(())
+    }
+}
+
 #[derive(Component, Default)]
 pub struct DebugUi {
-    text: Map<&'static str, String>,
+    // in the order they were first watched, so the overlay lines don't jump around
+    text: Vec<(&'static str, String)>,
+    // text changed since the overlay was last rebuilt
+    dirty: bool,
     collisions: Vec<(Tile, Aabb2d)>,
     ctl_aabb: Option<Aabb2d>,
+    ctl_color: Color,
     cursor: Vec2,
+    // draw colliders filled in rather than as outlines, toggled with C
+    fill_colliders: bool,
+    // the player's box at the start and end of the last frame's physics, drawn with lines joining
+    // their corners when show_sweep is on (toggled with S)
+    sweep: Option<(Aabb2d, Aabb2d)>,
+    show_sweep: bool,
+    // bars over the player for how much is left of the coyote window, the jump buffer and the
+    // dash cooldown, toggled with T
+    show_timers: bool,
+    // recent history of graphed values, drawn as lines in the corner of the view
+    graphs: Vec<(&'static str, VecDeque<f32>)>,
 }
 
 impl DebugUi {
-    fn watch(&mut self, key: &'static str, val: impl std::fmt::Debug) {
-        self.text.insert(key, format!("{:?}", val));
+    pub fn watch(&mut self, key: &'static str, val: impl std::fmt::Debug) {
+        let val = format!("{:?}", val);
+        match self.text.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) if *v == val => return,
+            Some((_, v)) => *v = val,
+            None => self.text.push((key, val)),
+        }
+        self.dirty = true;
+    }
+
+    // the overlay's lines, if anything changed since they were last taken
+    fn take_sections(&mut self) -> Option<Vec<TextSection>> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        let lines = self.text.iter().map(|(k, v)| format!("{k}: {v}\n"));
+        Some(lines.map(|l| TextSection::new(l, default())).collect())
+    }
+
+    // like watch, but keeps the last GRAPH_LEN values to draw as a line
+    fn graph(&mut self, key: &'static str, val: f32) {
+        let buf = match self.graphs.iter().position(|(k, _)| *k == key) {
+            Some(i) => &mut self.graphs[i].1,
+            None => {
+                self.graphs.push((key, VecDeque::with_capacity(GRAPH_LEN)));
+                &mut self.graphs.last_mut().unwrap().1
+            }
+        };
+        if buf.len() == GRAPH_LEN {
+            buf.pop_front();
+        }
+        buf.push_back(val);
     }
 }
 
+// samples kept per graph, and the size of each graph on screen in pixels
+const GRAPH_LEN: usize = 120;
+const GRAPH_SIZE: Vec2 = Vec2::new(240., 60.);
+
+// bottom left of the i'th graph, stacking up from the bottom right corner of the view since the
+// palette strip has the bottom left and the overlay text the top left
+fn graph_corner(view_min: Vec2, view_max: Vec2, scale: Vec2, i: usize) -> Vec2 {
+    let (size, pad) = (GRAPH_SIZE * scale, 10. * scale);
+    let x = view_max.x - pad.x - size.x;
+    let y = view_min.y + pad.y + (size.y + pad.y) * i as f32;
+    Vec2::new(x, y)
+}
+
 use crate::intro::MainCamera;
 
-const MAP: (Vec2, usize, [u8; 27 * 112]) = (
-    Vec2::new(-200.0, -400.0),
-    27,
-    [
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, // 0
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 1
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 2
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, // 3
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 1, // 4
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, // 5
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 1, // 6
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 7
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 1, // 8
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, // 9
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 10
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 11
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 12
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 13
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 14
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 15
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 16
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 17
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 18
-        1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 19
-        1, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 20
-        1, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 21
-        1, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 22
-        1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 23
-        1, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 24
-        1, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 25
-        1, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 26
-        1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 27
-        1, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 28
-        1, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 29
-        1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 30
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 31
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 32
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 33
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 34
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 35
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 36
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 37
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 38
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 39
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 40
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 41
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 4, 1, 5, 0, 0, 0, 0, 1, // 42
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 43
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 44
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 45
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, // 46
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 47
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 48
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 49
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 50
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 51
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, // 52
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 53
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 54
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 55
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 56
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 57
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 58
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 59
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, // 60
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 61
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 62
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 63
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 64
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 65
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 66
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 67
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 68
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 69
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 70
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 71
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 1, // 72
-        1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 1, // 73
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 74
-        1, 0, 0, 0, 0, 0, 0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 75
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, // 76
-        1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 77
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 1, 5, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, // 78
-        1, 0, 0, 0, 0, 0, 0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, // 79
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, // 80
-        1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, // 81
-        1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 82
-        1, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 83
-        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 84
-        1, 0, 0, 0, 0, 0, 1, 1, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 85
-        1, 0, 0, 0, 1, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 86
-        1, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 87
-        3, 0, 0, 0, 0, 0, 4, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 88
-        1, 5, 0, 0, 0, 0, 1, 5, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 89
-        3, 0, 0, 0, 0, 4, 1, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 90
-        1, 5, 0, 0, 0, 0, 1, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 91
-        3, 0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 92
-        1, 5, 0, 0, 0, 0, 1, 5, 0, 1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 93
-        1, 0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 94
-        1, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 95
-        1, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 96
-        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 97
-        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 98
-        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 99
-        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0,
-        1, // 100
-        1, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0,
-        1, // 101
-        1, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0,
-        1, // 102
-        1, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0,
-        1, // 103
-        1, 0, 1, 0, 0, 0, 0, 1, 1, 1, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
-        1, // 104
-        1, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
-        1, // 105
-        1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
-        1, // 106
-        1, 0, 1, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
-        1, // 107
-        1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 3, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
-        1, // 108
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        1, // 109
-        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        1, // 110
-        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-        1, // 111
-    ],
-);
-const MAP_TOP: f32 = {
-    let map_bot = MAP.0.y;
-    ((MAP.2.len() / MAP.1) as f32) * Tile::SZ + map_bot
-};
+// editor camera scale bounds
+const ZOOM_MIN: f32 = 0.25;
+const ZOOM_MAX: f32 = 8.;
+
+// (target scale, world point to zoom about) while the editor camera is zooming
+#[derive(Resource, Default)]
+pub struct ZoomEase(Option<(f32, Vec2)>);
+
+impl ZoomEase {
+    // how quickly the scale closes in on the target, roughly 1/seconds
+    const SPEED: f32 = 15.;
+}
 
-impl Tile {
-    fn spawn<'c>(
-        commands: &'c mut Commands,
-        t: u8,
-        pos: Vec3,
-        tex_cfg: (Handle<Image>, (f32, f32), f32),
-    ) -> bevy::ecs::system::EntityCommands<'c> {
-        let color = if cfg!(debug_assertions) {
-            Tile::COLORS[t as usize]
+// seconds until a held editor key or button acts again, so holding one repeats at the same rate
+// everywhere instead of at the os key repeat rate or every frame
+#[derive(Resource, Default)]
+pub struct EditRepeat(f32);
+
+impl EditRepeat {
+    const PERIOD: f32 = 0.2;
+
+    // a fresh press always acts, holding acts again each time the cooldown runs out. the epsilon
+    // keeps float error from holding it a frame longer when frames are a whole fraction of it
+    fn ready(&mut self, just_pressed: bool, pressed: bool) -> bool {
+        if just_pressed || (pressed && self.0 <= 1e-6) {
+            self.0 = Self::PERIOD;
+            true
         } else {
-            Color::NONE
-        };
-        let (hndl, (w, h), s) = tex_cfg;
-        let u = (pos.x * s / Tile::SZ).rem_euclid(w);
-        let v = ((-pos.y) * s / Tile::SZ).rem_euclid(h);
-        let rect = Some(Rect::new(u, v, u + s, v + s));
-        let tex = hndl.clone();
-
-        commands.spawn((
-            Tile(t),
-            SpriteBundle {
-                sprite: Sprite {
-                    color,
-                    custom_size: Some(Vec2::ONE),
-                    rect,
-                    ..default()
-                },
-                transform: Transform {
-                    translation: pos,
-                    scale: Vec3::new(Tile::SZ, Tile::SZ, 1.),
-                    ..default()
-                },
-                texture: tex,
+            false
+        }
+    }
+}
+
+// (texture, its size in pixels, pixels per tile), tiles are cut from the texture by position
+fn tile_texture(assets: &AssetServer, level: &Level) -> (Handle<Image>, (f32, f32), f32) {
+    let hndl = (level.texture.as_ref()).map_or_else(Handle::default, |p| assets.load(p.clone()));
+    (hndl, (1500., 1000.), 200.)
+}
+
+// None for an id that isn't a tile type (eg. a map saved with a newer palette), which is
+// skipped with a warning rather than spawned as a tile nothing knows how to collide with
+fn spawn_tile<'c>(
+    commands: &'c mut Commands,
+    t: u8,
+    pos: Vec3,
+    tex_cfg: (Handle<Image>, (f32, f32), f32),
+    level: &Level,
+) -> Option<bevy::ecs::system::EntityCommands<'c>> {
+    if t as usize >= Tile::NUM {
+        warn!("skipping tile at {pos}, there's no tile type {t}");
+        return None;
+    }
+    let color = if cfg!(debug_assertions) {
+        level.palette[t as usize]
+    } else {
+        Color::NONE
+    };
+    let (hndl, (w, h), s) = tex_cfg;
+    let cell = world_to_tile(Vec2::ZERO, Tile::SZ, pos.xy()).as_vec2();
+    let u = (cell.x * s).rem_euclid(w);
+    let v = (-cell.y * s).rem_euclid(h);
+    let rect = Some(Rect::new(u, v, u + s, v + s));
+    let tex = hndl.clone();
+
+    let mut ecmd = commands.spawn((
+        Tile(t),
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::ONE),
+                rect,
                 ..default()
             },
-        ))
+            transform: Transform {
+                translation: pos,
+                scale: Vec3::new(Tile::SZ, Tile::SZ, 1.),
+                ..default()
+            },
+            texture: tex,
+            ..default()
+        },
+    ));
+    if pos.z > Layer::Player.z() {
+        ecmd.insert((
+            Foreground {
+                alpha: color.a(),
+                shown: 1.,
+            },
+            Decor,
+        ));
     }
+    Some(ecmd)
 }
 
-fn debug_setup(mut command: Commands) {
-    command.spawn((
-        DebugUi::default(),
-        TextBundle {
+// every tile of the level, as setup does it. a generated level can be spawned the same way with
+// Level::from_grid, or better, inserted as the Level resource before the game starts
+pub fn spawn_tiles(commands: &mut Commands, assets: &AssetServer, level: &Level) -> Vec<Entity> {
+    let tex_cfg = tile_texture(assets, level);
+    (level.tiles())
+        .filter_map(|(t, pos)| {
+            let z = pos.extend(Layer::Tile.z());
+            let mut e = spawn_tile(commands, t.0, z, tex_cfg.clone(), level)?;
+            if level.is_decor(pos) {
+                e.insert(Decor);
+            }
+            if level.is_composite(pos) {
+                e.insert(Composite::RAMPED);
+            }
+            Some(e.id())
+        })
+        .collect()
+}
+
+// the level's wind zones and camera triggers, faintly visible in debug builds
+pub fn spawn_zones(commands: &mut Commands, level: &Level) {
+    let zone = |area: &Rect, color: Color| SpriteBundle {
+        sprite: Sprite {
+            color: if cfg!(debug_assertions) {
+                color
+            } else {
+                Color::NONE
+            },
+            custom_size: Some(Vec2::ONE),
+            ..default()
+        },
+        transform: Transform {
+            translation: area.center().extend(Layer::Zone.z()),
+            scale: area.size().extend(1.),
+            ..default()
+        },
+        ..default()
+    };
+    for (area, push) in &level.winds {
+        commands.spawn((Wind(*push), zone(area, Color::rgba(0.5, 0.8, 1., 0.15))));
+    }
+    for &(area, target, scale) in &level.cameras {
+        let trigger = CameraTrigger { target, scale };
+        commands.spawn((trigger, zone(&area, Color::rgba(1., 0.5, 1., 0.1))));
+    }
+    for &(area, travel, period) in &level.platforms {
+        let platform = Platform {
+            home: area.center(),
+            travel,
+            period,
+            time: 0.,
+        };
+        let mut block = zone(&area, Color::NONE);
+        block.sprite.color = level.palette[Tile::SQUARE.0 as usize];
+        block.transform.translation.z = Layer::Tile.z();
+        commands.spawn((platform, block));
+    }
+}
+
+// the tile whose solid part covers pos, if any
+// (the empty half of a slope or step doesn't count, so the tile next to it can be picked)
+fn tile_at<'a>(
+    tiles: impl IntoIterator<Item = (Entity, &'a Transform, &'a Tile)>,
+    pos: Vec2,
+) -> Option<Entity> {
+    (tiles.into_iter())
+        .find(|(_, t, c)| covers(t, c, pos))
+        .map(|(e, ..)| e)
+}
+
+fn covers(t: &Transform, tile: &Tile, pos: Vec2) -> bool {
+    let aabb = Aabb2d::new(t.translation.xy(), t.scale.xy() / 2.);
+    physics::tile_contains(tile, &aabb, pos)
+}
+
+// read-only view of the map for systems that need to ask what's at a point
+#[derive(SystemParam)]
+pub struct Tiles<'w, 's> {
+    tiles: Query<'w, 's, (Entity, &'static Transform, &'static Tile), Without<Decor>>,
+}
+
+impl Tiles<'_, '_> {
+    // the solid tile at a world position, or None if it's empty (or only decoration)
+    pub fn is_solid(&self, pos: Vec2) -> Option<Tile> {
+        (self.tiles.iter())
+            .find(|(_, t, c)| covers(t, c, pos))
+            .map(|(_, _, tile)| *tile)
+    }
+}
+
+fn debug_setup(mut command: Commands, paint: Res<Paint>, level: Res<Level>) {
+    // a dark panel behind the text so it can be read over the background
+    command
+        .spawn(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
                 top: Val::Px(10.),
                 left: Val::Px(10.),
+                padding: UiRect::all(Val::Px(4.)),
                 ..default()
             },
+            background_color: if cfg!(debug_assertions) {
+                Color::rgba(0., 0., 0., 0.6).into()
+            } else {
+                Color::NONE.into()
+            },
             ..default()
-        },
-    ));
+        })
+        .with_children(|panel| {
+            panel.spawn((DebugUi::default(), TextBundle::default()));
+        });
+    command
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                left: Val::Px(10.),
+                column_gap: Val::Px(4.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|strip| {
+            for t in 1..Tile::NUM as u8 {
+                strip
+                    .spawn((
+                        PaletteSwatch(Tile(t)),
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Px(24.),
+                                height: Val::Px(24.),
+                                border: UiRect::all(Val::Px(2.)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: level.palette[t as usize].into(),
+                            border_color: swatch_border(Tile(t) == paint.0).into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|swatch| {
+                        swatch.spawn(TextBundle::from_section(
+                            t.to_string(),
+                            TextStyle {
+                                font_size: 16.,
+                                color: Color::BLACK,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+    command
+        .spawn((
+            PaletteLegend,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.),
+                    padding: UiRect::all(Val::Px(4.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|legend| {
+            let swatch = |color: Color| NodeBundle {
+                style: Style {
+                    width: Val::Px(16.),
+                    height: Val::Px(16.),
+                    ..default()
+                },
+                background_color: color.into(),
+                ..default()
+            };
+            for (id, [r, g, b]) in BMP_PALETTE.iter().enumerate() {
+                let row = NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(4.),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                };
+                legend.spawn(row).with_children(|row| {
+                    row.spawn(swatch(level.palette[id]));
+                    row.spawn(swatch(Color::rgb_u8(*r, *g, *b)));
+                    row.spawn(TextBundle::from_section(
+                        format!("{id} {}", Tile(id as u8).kind()),
+                        TextStyle {
+                            font_size: 16.,
+                            ..default()
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+fn swatch_border(active: bool) -> Color {
+    if active {
+        Color::YELLOW
+    } else {
+        Color::NONE
+    }
 }
 
 pub fn setup(
@@ -280,6 +966,8 @@ pub fn setup(
     assets: Res<AssetServer>,
     mut win: Query<&mut Window, With<PrimaryWindow>>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    level: Res<Level>,
+    size: Res<PlayerSize>,
 ) {
     command.spawn((
         MainCamera,
@@ -294,10 +982,10 @@ pub fn setup(
         },
     ));
     let img_size = Vec2::new(
-        (MAP.1 + 1) as f32 * Tile::SZ,
-        ((MAP.2.len() / MAP.1) + 1) as f32 * Tile::SZ + 110.,
+        (level.width + 1) as f32 * Tile::SZ,
+        (level.height() + 1) as f32 * Tile::SZ + 110.,
     );
-    let img_pos = MAP.0 + (img_size / 2.) - Vec2::new(0.5 * Tile::SZ, 157.);
+    let img_pos = level.origin + (img_size / 2.) - Vec2::new(0.5 * Tile::SZ, 157.);
     command.spawn(SpriteBundle {
         sprite: Sprite {
             custom_size: Some(img_size),
@@ -305,7 +993,7 @@ pub fn setup(
         },
         texture: assets.load("level/bg.png"),
         transform: Transform {
-            translation: img_pos.extend(-1.),
+            translation: img_pos.extend(Layer::Background.z()),
             scale: Vec3::ONE,
             ..default()
         },
@@ -318,7 +1006,7 @@ pub fn setup(
         },
         texture: assets.load("level/fg.png"),
         transform: Transform {
-            translation: img_pos.extend(2.),
+            translation: img_pos.extend(Layer::Foreground.z()),
             scale: Vec3::ONE,
             ..default()
         },
@@ -347,6 +1035,18 @@ pub fn setup(
     // ));
 
     command.insert_resource(End(false));
+    command.insert_resource(MapBounds::from_tiles(level.tiles().map(|(_, pos)| pos)));
+    command.spawn((
+        BackgroundFill,
+        SpriteBundle {
+            sprite: Sprite {
+                color: level.background_color(),
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., Layer::Fill.z()),
+            ..default()
+        },
+    ));
     command.spawn((
         WinText,
         SpriteBundle {
@@ -354,72 +1054,103 @@ pub fn setup(
             texture: assets.load("scenes/end.png"),
             transform: Transform {
                 scale: Vec3::ONE,
-                translation: Vec3::new(0., 0., 20.),
+                translation: Vec3::Z * Layer::Overlay.z(),
                 ..default()
             },
             ..default()
         },
     ));
 
-    let layout = TextureAtlasLayout::from_grid(Vec2::new(251., 377.), 3, 2, None, None);
-    command.spawn((
-        Control,
-        Movement::default(),
-        SpriteBundle {
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(1.2, 1.4)),
-                // rect: Some(Rect::new(0., 0., 251., 350.)),
-                ..default()
-            },
-            transform: Transform {
-                translation: Vec3::new(0., 0., 1.),
-                scale: Vec3::new(45., 45., 1.),
-                ..default()
-            },
-            texture: assets.load("baby-idle-sheet.png"),
-            ..default()
-        },
+    let idle = Sheet {
+        texture: assets.load("baby-idle-sheet.png"),
+        layout: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+            Vec2::new(251., 377.),
+            3,
+            2,
+            None,
+            None,
+        )),
+        idx_beg: 0,
+        idx_end: 4,
+    };
+    let jump = Sheet {
+        texture: assets.load("baby-thrown.png"),
+        layout: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+            Vec2::new(161., 312.),
+            1,
+            1,
+            None,
+            None,
+        )),
+        idx_beg: 0,
+        idx_end: 0,
+    };
+    command.spawn((
+        PlayerBundle::new(
+            PlayerBundle::SPAWN,
+            *size,
+            Vec2::new(1.2, 1.4),
+            idle.texture.clone(),
+        ),
         TextureAtlas {
-            layout: texture_atlas_layouts.add(layout),
+            layout: idle.layout.clone(),
             index: 0,
         },
+        SpriteSheets::new(vec![("idle", idle), ("jump", jump)]),
         TextureAnimate {
             frame_len: 0.1,
             cycle: Cycle::PingPong,
             idx_beg: 0,
             idx_end: 4,
+            idx_left: None,
+            elapsed: 0.,
         },
     ));
+    command
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                width: Val::Px(100.),
+                height: Val::Px(10.),
+                ..default()
+            },
+            background_color: Color::rgb(0.2, 0., 0.).into(),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                HealthBar,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: Color::RED.into(),
+                    ..default()
+                },
+            ));
+        });
 
-    // let garbage_bg = (assets.load("tiled_garbage.png"), (1500., 1000.), 200.);
-    let garbage_bg = (Handle::<Image>::default(), (1500., 1000.), 200.);
+    // a map with `// texture: tiled_garbage.png` cuts its tiles out of that instead of plain squares
+    let garbage_bg = tile_texture(&assets, &level);
     command.spawn(SpriteBundle {
         sprite: Sprite {
             color: Color::rgb(0.2, 0.2, 0.5),
             ..default()
         },
         transform: Transform {
-            translation: Vec3::new(0., 0., -10.),
+            translation: Vec3::Z * Layer::Backdrop.z(),
             scale: Vec3::splat(0.6),
             ..default()
         },
-        texture: garbage_bg.0.clone(),
+        texture: garbage_bg.0,
         ..default()
     });
-    let map_origin = MAP.0;
-    for (i, &t) in MAP.2.iter().rev().enumerate() {
-        if t == 0 {
-            continue;
-        }
-        let (x, y) = (MAP.1 - (i % MAP.1) - 1, i / MAP.1);
-        let v = Vec2::new(x as f32, y as f32) * Vec2::splat(Tile::SZ);
-        Tile::spawn(
-            &mut command,
-            t,
-            (map_origin + v).extend(0.),
-            garbage_bg.clone(),
-        );
-    }
+    spawn_tiles(&mut command, &assets, &level);
+    spawn_zones(&mut command, &level);
 
     for mut win in &mut win {
         win.cursor.icon = CursorIcon::Pointer;
@@ -427,30 +1158,64 @@ pub fn setup(
     }
 }
 
-pub fn check_kbd(
-    kbd: Res<ButtonInput<KeyCode>>,
-    mut quit: EventWriter<AppExit>,
-    mut ctl: Query<&mut Movement, With<Control>>,
-    tiles: Query<(&Transform, &Tile)>,
+// fade out, the win screen is revealed by show_win once the screen is black
+fn win(commands: &mut Commands, fades: &Query<(), With<Fade>>, end: &End) {
+    if !end.0 && fades.is_empty() {
+        fade_transition(commands, 1., None);
+    }
+}
+
+pub fn show_win(
+    mut faded_out: EventReader<FadedOut>,
     mut win_text: Query<&mut Visibility, With<WinText>>,
     mut end: ResMut<End>,
+) {
+    // fades that switch state aren't ours
+    if faded_out.read().any(|ev| ev.0.is_none()) {
+        *win_text.single_mut() = Visibility::Visible;
+        end.0 = true;
+    }
+}
+
+// escape quits, saving the map first in debug builds. not in a GameSet, so it works in edit and
+// puzzle mode too
+pub fn quit_on_escape(
+    kbd: Res<ButtonInput<KeyCode>>,
+    tiles: Query<(&Transform, &Tile, Has<Decor>, Has<Composite>)>,
+    level: Res<Level>,
+    dir: Res<SaveDir>,
+    mut quit: EventWriter<AppExit>,
 ) {
     if kbd.pressed(KeyCode::Escape) {
         if cfg!(debug_assertions) {
-            save_map(tiles);
+            save_map(tiles, &level, &dir.0);
         }
         quit.send(AppExit);
     }
+}
 
-    let mut vx = 0.;
+pub fn check_kbd(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut time: ResMut<GameTime>,
+    feel: Res<GameFeel>,
+    mut ctl: Query<&mut Movement, With<Control>>,
+    mut commands: Commands,
+    fades: Query<(), With<Fade>>,
+    end: Res<End>,
+    assets: Res<AssetServer>,
+    socd: Res<Socd>,
+    mut newest: Local<f32>,
+) {
     let mut vy = 0.;
-    if kbd.pressed(KeyCode::ArrowLeft) {
-        vx -= 1.;
+    let jump_keys = [KeyCode::Space, KeyCode::ArrowUp];
+    let (left, right) = (KeyCode::ArrowLeft, KeyCode::ArrowRight);
+    match (kbd.just_pressed(left), kbd.just_pressed(right)) {
+        (true, false) => *newest = -1.,
+        (false, true) => *newest = 1.,
+        _ => {}
     }
-    if kbd.pressed(KeyCode::ArrowRight) {
-        vx += 1.;
-    }
-    if kbd.pressed(KeyCode::Space) || kbd.pressed(KeyCode::ArrowUp) {
+    let vx = socd.resolve(kbd.pressed(left), kbd.pressed(right), *newest);
+    if kbd.any_pressed(jump_keys) {
         vy += 1.;
     }
     if kbd.pressed(KeyCode::ArrowDown) {
@@ -458,13 +1223,333 @@ pub fn check_kbd(
     }
 
     if kbd.pressed(KeyCode::KeyO) {
-        *win_text.single_mut() = Visibility::Visible;
-        end.0 = true;
+        win(&mut commands, &fades, &end);
+    }
+    if kbd.just_pressed(KeyCode::KeyP) {
+        time.paused = !time.paused;
     }
 
-    let v = Vec2::new(vx, vy);
+    // accelerations are per physics tick
+    let ticks = time.delta_seconds() * 60.;
     for mut c in &mut ctl {
-        c.ctl = v * 5.;
+        let speed = if c.crouch { PlayerBundle::CROUCH } else { 1. };
+        let target = vx * feel.run_speed * speed;
+        let accel = if !c.grounded {
+            feel.air_accel
+        } else if target == 0. || target.signum() != c.ctl.x.signum() {
+            feel.run_decel
+        } else {
+            feel.run_accel
+        };
+        // inf * 0 is NaN on a zero-length frame, max() turns that into no movement
+        let max_dx = (accel * ticks).max(0.);
+        let dx = (target - c.ctl.x).clamp(-max_dx, max_dx);
+        c.ctl.x += dx;
+
+        // the forgiveness windows, a jump a moment after walking off a ledge still goes, and so
+        // does one pressed a moment before landing
+        if c.grounded {
+            c.air_time = 0.;
+            c.air_jumps = feel.air_jumps;
+        } else {
+            c.air_time += time.delta_seconds();
+        }
+        if kbd.any_just_pressed(jump_keys) {
+            c.jump_buffer = feel.jump_buffer;
+        } else {
+            c.jump_buffer -= time.delta_seconds();
+        }
+        let supported = c.grounded || c.wall_dir != 0.;
+        let can_jump = supported || c.air_time <= feel.coyote;
+        let mut vy = vy * feel.jump_impulse;
+        if vy > 0. && c.ctl.y <= 0. {
+            if c.jump_buffer > 0. && can_jump {
+                c.jump_buffer = 0.;
+                if supported {
+                    // off a wall is a bit higher and louder, so it's told apart from a normal jump
+                    let (speed, volume) = if c.grounded { (1., 0.5) } else { (1.3, 0.7) };
+                    let settings = PlaybackSettings::DESPAWN
+                        .with_volume(bevy::audio::Volume::new(volume))
+                        .with_speed(speed);
+                    play_once(&mut commands, assets.load("sounds/woosh.wav"), settings);
+                }
+            } else if c.jump_buffer > 0. && c.air_jumps > 0 {
+                // a jump in the air starts over from standing still, whatever the fall was
+                c.jump_buffer = 0.;
+                c.air_jumps -= 1;
+                c.force.y = 0.;
+                let settings = PlaybackSettings::DESPAWN
+                    .with_volume(bevy::audio::Volume::new(0.5))
+                    .with_speed(1.5);
+                play_once(&mut commands, assets.load("sounds/woosh.wav"), settings);
+            } else {
+                // held, but too late or (so far) too early
+                vy = 0.;
+            }
+        }
+        if c.ctl.y > 0. && vy <= 0. && !feel.jump_cut.is_nan() {
+            // jump released, carry some of the upward speed over into force
+            let rise = c.ctl.y + c.force.y;
+            if rise > 0. {
+                c.force.y = rise * feel.jump_cut;
+            }
+        }
+        c.ctl.y = vy;
+    }
+}
+
+// holding down on the ground squashes the player to CROUCH of its height, feet staying put,
+// so it fits under low ceilings. it only stands back up once the full height box is clear
+pub fn crouch(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut ctl: Query<(&mut Transform, &mut Movement), With<Control>>,
+    tiles: Query<&Transform, (With<Tile>, Without<Control>, Without<Decor>)>,
+    size: Res<PlayerSize>,
+) {
+    let full = size.0.y;
+    let drop = full * (1. - PlayerBundle::CROUCH) / 2.;
+    for (mut t, mut v) in &mut ctl {
+        let want = kbd.pressed(KeyCode::ArrowDown) && v.grounded;
+        if want && !v.crouch {
+            v.crouch = true;
+            t.scale.y = full * PlayerBundle::CROUCH;
+            t.translation.y -= drop;
+        } else if !want && v.crouch {
+            // boxes that only touch don't count, and the empty half of a slope still blocks
+            let stand = Aabb2d::new(
+                t.translation.xy() + Vec2::new(0., drop),
+                Vec2::new(t.scale.x, full) / 2. - 0.5,
+            );
+            let blocked = (tiles.iter())
+                .any(|tt| Aabb2d::new(tt.translation.xy(), tt.scale.xy() / 2.).intersects(&stand));
+            if !blocked {
+                v.crouch = false;
+                t.scale.y = full;
+                t.translation.y += drop;
+            }
+        }
+    }
+}
+
+// runs after check_kbd, and takes over the player's movement while the dash lasts
+pub fn dash(
+    kbd: Res<ButtonInput<KeyCode>>,
+    time: Res<GameTime>,
+    feel: Res<GameFeel>,
+    mut commands: Commands,
+    mut ctl: Query<
+        (
+            &mut Movement,
+            &mut Dash,
+            &Facing,
+            &Transform,
+            &Sprite,
+            &Handle<Image>,
+            Option<&TextureAtlas>,
+        ),
+        With<Control>,
+    >,
+    trail: Query<(), With<Afterimage>>,
+) {
+    let mut trail_len = trail.iter().count();
+    for (mut v, mut dash, facing, t, sprite, tex, atlas) in &mut ctl {
+        v.dash_cooldown = (v.dash_cooldown - time.delta_seconds()).max(0.);
+        if dash.left <= 0. {
+            if v.grounded {
+                dash.ready = true;
+            }
+            let cooled = v.dash_cooldown <= 0.;
+            if !(dash.ready && cooled && kbd.just_pressed(KeyCode::KeyX)) {
+                continue;
+            }
+            let mut dir = Vec2::ZERO;
+            for (key, d) in [
+                (KeyCode::ArrowLeft, Vec2::NEG_X),
+                (KeyCode::ArrowRight, Vec2::X),
+                (KeyCode::ArrowUp, Vec2::Y),
+                (KeyCode::ArrowDown, Vec2::NEG_Y),
+            ] {
+                if kbd.pressed(key) {
+                    dir += d;
+                }
+            }
+            if dir == Vec2::ZERO {
+                dir.x = if facing.left { -1. } else { 1. };
+            }
+            dash.ready = false;
+            v.dash_cooldown = feel.dash_cooldown;
+            dash.left = Dash::TIME;
+            dash.dir = dir.normalize();
+            dash.trail = 0.;
+        }
+
+        dash.left -= time.delta_seconds();
+        if dash.left <= 0. {
+            // stop dead rather than fly on, gravity takes it from here
+            v.force = Vec2::ZERO;
+            continue;
+        }
+        v.ctl = Vec2::ZERO;
+        v.force = dash.dir * Dash::SPEED;
+
+        dash.trail -= time.delta_seconds();
+        if dash.trail > 0. {
+            continue;
+        }
+        dash.trail += Dash::TRAIL_EVERY;
+        if trail_len >= Dash::MAX_TRAIL {
+            continue;
+        }
+        trail_len += 1;
+        let mut copy = commands.spawn((
+            Afterimage(Timer::from_seconds(Dash::TRAIL_FADE, TimerMode::Once)),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: sprite.color.with_a(0.5),
+                    flip_x: sprite.flip_x,
+                    custom_size: sprite.custom_size,
+                    ..default()
+                },
+                transform: t.with_translation(t.translation - Vec3::Z * 0.1),
+                texture: tex.clone(),
+                ..default()
+            },
+        ));
+        if let Some(atlas) = atlas {
+            copy.insert(atlas.clone());
+        }
+    }
+}
+
+pub fn fade_afterimages(
+    time: Res<GameTime>,
+    mut commands: Commands,
+    mut trail: Query<(Entity, &mut Afterimage, &mut Sprite)>,
+) {
+    for (e, mut img, mut sprite) in &mut trail {
+        img.0.tick(time.delta());
+        if img.0.finished() {
+            commands.entity(e).despawn();
+        } else {
+            sprite.color.set_a(0.5 * img.0.fraction_remaining());
+        }
+    }
+}
+
+// D prints everything about the player's movement to stderr, to look at after a collision goes
+// wrong (pausing with P first helps)
+pub fn debug_dump_movement(
+    kbd: Res<ButtonInput<KeyCode>>,
+    ctl: Query<(&Transform, &Movement), With<Control>>,
+    tick: Res<PhysicsTick>,
+    cache: Res<TileCache>,
+    scale: Res<PhysicsScale>,
+) {
+    if !kbd.just_pressed(KeyCode::KeyD) {
+        return;
+    }
+    for (t, v) in &ctl {
+        let aabb = scale.to_physics(ctl_aabb(t));
+        eprintln!("{}", movement_dump(&aabb, v, tick.rem, &cache.tiles));
+    }
+}
+
+// one line per field, then the tiles touching the player's box (in physics units)
+pub fn movement_dump(aabb: &Aabb2d, v: &Movement, tick: f32, tiles: &[(Tile, Aabb2d)]) -> String {
+    let mut out = format!("{v:#?}\nphysics tick remainder: {tick}\n");
+    out += &format!("box: {} to {}\n", aabb.min, aabb.max);
+    // a resting player is a skin away from what it's resting on
+    let grown = Aabb2d::new(aabb.center(), aabb.half_size() + physics::SKIN);
+    let touching: Vec<_> = (tiles.iter())
+        .filter(|(_, col)| col.intersects(&grown))
+        .collect();
+    out += &format!("touching {} tiles\n", touching.len());
+    for (tile, col) in touching {
+        out += &format!("  {} {} to {}\n", tile.kind(), col.min, col.max);
+    }
+    out
+}
+
+// the editor's paint type and the palette strip and legend showing it
+#[derive(SystemParam)]
+pub struct PaletteKeys<'w, 's> {
+    paint: ResMut<'w, Paint>,
+    swatches: Query<'w, 's, (&'static PaletteSwatch, &'static mut BorderColor)>,
+    legend: Query<'w, 's, &'static mut Visibility, With<PaletteLegend>>,
+    no_collide: ResMut<'w, NoCollide>,
+    repeat: ResMut<'w, EditRepeat>,
+}
+
+pub fn debug_check_kbd(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut dbg: Query<&mut DebugUi>,
+    mut follow: ResMut<CameraFollow>,
+    mut cache: ResMut<TileCache>,
+    mut edit: ResMut<EditMode>,
+    mut palette: PaletteKeys,
+) {
+    if kbd.just_pressed(KeyCode::KeyL) {
+        for mut vis in &mut palette.legend {
+            *vis = match *vis {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+    if kbd.just_pressed(KeyCode::KeyE) {
+        edit.0 = !edit.0;
+        for mut dbg in &mut dbg {
+            dbg.watch("edit", edit.0);
+        }
+    }
+
+    if kbd.just_pressed(KeyCode::KeyF) {
+        follow.enabled = true;
+    }
+    if kbd.just_pressed(KeyCode::KeyC) {
+        for mut dbg in &mut dbg {
+            dbg.fill_colliders = !dbg.fill_colliders;
+        }
+    }
+    if kbd.just_pressed(KeyCode::KeyS) {
+        for mut dbg in &mut dbg {
+            dbg.show_sweep = !dbg.show_sweep;
+        }
+    }
+    if kbd.just_pressed(KeyCode::KeyT) {
+        for mut dbg in &mut dbg {
+            dbg.show_timers = !dbg.show_timers;
+        }
+    }
+    if kbd.just_pressed(KeyCode::KeyG) {
+        cache.merge = !cache.merge;
+        // rebuilt on the next frame
+        cache.version = None;
+        for mut dbg in &mut dbg {
+            dbg.watch("merge", cache.merge);
+        }
+    }
+
+    let tab = (kbd.just_pressed(KeyCode::Tab), kbd.pressed(KeyCode::Tab));
+    if palette.repeat.ready(tab.0, tab.1) {
+        let paint = &mut palette.paint;
+        paint.cycle(kbd.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]));
+        for (swatch, mut border) in &mut palette.swatches {
+            border.0 = swatch_border(swatch.0 == paint.0);
+        }
+    }
+    if kbd.just_pressed(KeyCode::KeyN) {
+        let no_collide = &mut palette.no_collide;
+        if let Some(off) = no_collide.0.get_mut(palette.paint.0 .0 as usize) {
+            *off = !*off;
+        }
+        let off: Vec<_> = (0..Tile::NUM)
+            .filter(|&id| no_collide.0[id])
+            .map(|id| Tile(id as u8).kind())
+            .collect();
+        for mut dbg in &mut dbg {
+            dbg.watch("no_collide", &off);
+        }
     }
 }
 
@@ -478,52 +1563,131 @@ pub fn debug_check_mouse(
         &mut Tile,
         &mut Sprite,
         &mut Handle<Image>,
+        Has<Decor>,
+        Has<Composite>,
     )>,
     mut commands: Commands,
+    kbd: Res<ButtonInput<KeyCode>>,
     mut ev_scroll: EventReader<MouseWheel>,
-    mut cam_trans: Query<&mut Transform, (With<Camera>, With<MainCamera>, Without<Tile>)>,
+    mut cam_trans: Query<
+        (&mut Transform, &OrthographicProjection),
+        (With<Camera>, With<MainCamera>, Without<Tile>),
+    >,
     mut dbg: Query<&mut DebugUi>,
+    level: Res<Level>,
+    paint: Res<Paint>,
+    assets: Res<AssetServer>,
+    mut ease: ResMut<ZoomEase>,
+    mut follow: ResMut<CameraFollow>,
+    (mut drag, mut ignored, mut on_tile): (
+        Local<Option<Vec2>>,
+        Local<Option<&'static str>>,
+        Local<bool>,
+    ),
+    mut repeat: ResMut<EditRepeat>,
 ) {
-    let Some(cursor) = ({
-        let (cam, cam_gtrans) = cam.single_mut();
-        let Some(cursor) = win.single().cursor_position() else {
+    let cursor = match (cam.get_single_mut(), win.get_single()) {
+        (Ok((cam, cam_gtrans)), Ok(win)) => letterbox::cursor_to_world(cam, cam_gtrans, win),
+        (Err(_), _) => Err("there isn't exactly one main camera"),
+        (_, Err(_)) => Err("there's no window"),
+    };
+    let cursor = match cursor {
+        Ok(cursor) => {
+            *ignored = None;
+            cursor
+        }
+        Err(why) => {
+            // once per reason until the cursor works again, rather than every click
+            if mouse.get_just_pressed().next().is_some() && *ignored != Some(why) {
+                warn!("editor click ignored, {why}");
+                *ignored = Some(why);
+            }
             return;
-        };
-        cam.viewport_to_world_2d(cam_gtrans, cursor)
-    }) else {
-        return;
+        }
     };
     let mut dbg = dbg.single_mut();
 
     dbg.cursor = cursor;
+    dbg.watch("paint", paint.0 .0);
 
-    if mouse.just_pressed(MouseButton::Left) {
-        let cursor_pt = Aabb2d::new(cursor, Vec2::ZERO);
-        for (e, trans, mut tile, mut s, _img) in &mut tiles {
-            let tile_box = Aabb2d::new(trans.translation.xy(), trans.scale.xy() / 2.);
-            if !tile_box.contains(&cursor_pt) {
-                continue;
-            }
+    // dragging with the middle button pans the camera, and stops it following the player
+    // until F picks the player back up
+    let screen = win.single().cursor_position();
+    if let (true, Some(from), Some(to)) = (mouse.pressed(MouseButton::Middle), *drag, screen) {
+        let vp = cam.single().0.logical_viewport_size().unwrap_or(Vec2::ONE);
+        let (mut cam, proj) = cam_trans.single_mut();
+        let d = drag_pan(from, to, proj.area.height() / vp.y * cam.scale.x);
+        cam.translation += d.extend(0.);
+        follow.enabled = false;
+    }
+    *drag = screen.filter(|_| mouse.pressed(MouseButton::Middle));
 
+    // holding the button keeps cycling the tile under it, but only if the press started on one,
+    // otherwise the tile a click just placed would start cycling too
+    let just = mouse.just_pressed(MouseButton::Left);
+    let held = mouse.pressed(MouseButton::Left) && *on_tile;
+    if repeat.ready(just, held) {
+        let hit = tile_at(tiles.iter().map(|(e, t, c, ..)| (e, t, c)), cursor);
+        if just {
+            *on_tile = hit.is_some();
+        }
+        if let Some((e, _, mut tile, mut s, _img, decor, composite)) =
+            hit.and_then(|e| tiles.get_mut(e).ok())
+        {
+            // the alt and shift toggles only happen on a fresh click, holding them doesn't flicker
+            let mods = [
+                KeyCode::AltLeft,
+                KeyCode::AltRight,
+                KeyCode::ShiftLeft,
+                KeyCode::ShiftRight,
+            ];
+            if !just && kbd.any_pressed(mods) {
+                return;
+            }
+            // alt-click switches a tile between its own shape and Composite::RAMPED
+            if kbd.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+                if composite {
+                    commands.entity(e).remove::<Composite>();
+                } else {
+                    commands.entity(e).insert(Composite::RAMPED);
+                }
+                return;
+            }
+            // shift-click switches a tile between solid and decoration
+            if kbd.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+                if decor {
+                    commands.entity(e).remove::<Decor>();
+                } else {
+                    commands.entity(e).insert(Decor);
+                }
+                return;
+            }
             // rotate tile type
             tile.0 = (tile.0 + 1) % (Tile::NUM as u8);
             if tile.0 == 0 {
                 // type 0 is special, it means no tile
                 commands.get_entity(e).unwrap().despawn();
             } else if cfg!(debug_assertions) {
-                let color = Tile::COLORS[tile.0 as usize];
-                s.color = color;
+                s.color = level.palette[tile.0 as usize];
             }
             return;
         }
 
         // no tile, need to insert
-        let tile_pos = (cursor / Tile::SZ).round() * Tile::SZ;
-        Tile::spawn(
+        if !just {
+            return;
+        }
+        let tile_pos = level.snap(cursor);
+        if (tiles.iter()).any(|(_, t, ..)| t.translation.xy().distance(tile_pos) < Tile::SZ / 2.) {
+            // clicked the empty half of a triangle, there's already a tile here
+            return;
+        }
+        spawn_tile(
             &mut commands,
-            1,
-            tile_pos.extend(0.),
-            (Handle::default(), (1500., 1000.), 200.),
+            paint.0 .0,
+            tile_pos.extend(Layer::Tile.z()),
+            tile_texture(&assets, &level),
+            &level,
         );
     }
 
@@ -533,104 +1697,151 @@ pub fn debug_check_mouse(
     for ev in ev_scroll.read() {
         zoom += ev.y;
     }
-    let mut cam_trans = cam_trans.single_mut();
+    if zoom == 0. {
+        return;
+    }
     let zoom = (1.1f32).powf(zoom.round());
-    cam_trans.scale *= Vec3::new(zoom, zoom, 1.);
+    // notches scrolled while still easing add up, rather than starting over from wherever it is
+    let from = (ease.0).map_or(cam_trans.single().0.scale.x, |(target, _)| target);
+    ease.0 = Some(((from * zoom).clamp(ZOOM_MIN, ZOOM_MAX), cursor));
 }
 
-// calculate how much we have to push aabb to no longer collide with col
-// for instance, if aabb is not intersection col_aabb, then we don't need to push it away at all
-// if aabb is intersecting col_aabb, col is square, and it would
-//
-// col determines the shape and characteristics:
-// - Square is a square block. standing on this dampens gravity's pull
-// - StepL/R are left or right steps
-//   the collider is the shape of left or right triangles,
-//   and they allow you to stand on them by dampening gravity
-// - SlopeL/R are left or right slopes,
-//   the collider is the shape of left or right triangles,
-//   but standing on them does not dampen gravity
-//
-fn collide_push(aabb: &Aabb2d, col: &Tile, col_aabb: &Aabb2d) -> (Vec2, bool, bool) {
-    let lt = col_aabb.min.x - aabb.max.x;
-    let rt = col_aabb.max.x - aabb.min.x;
-    let up = col_aabb.max.y - aabb.min.y;
-    let dn = col_aabb.min.y - aabb.max.y;
-    let horz = if lt.abs() < rt.abs() { lt } else { rt };
-    let vert = if dn.abs() < up.abs() { dn } else { up };
-
-    use std::f32::consts::FRAC_1_SQRT_2;
-    // normalized vectors
-    const UNIT_DN_RT: Vec2 = Vec2::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2); // y = -x
-    const UNIT_DN_LT: Vec2 = Vec2::new(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2); // y = x
-    let pt_line_dist = |left, p: Vec2| {
-        let a = col_aabb.center();
-        let n = if left { UNIT_DN_RT } else { UNIT_DN_LT };
-        // wikipedia taught me how to do this
-        let dist = (p - a - ((p - a).dot(n) * n)).length();
-        // does point lie above or below the line
-        let sign = if left {
-            (p - a).y > -(p - a).x
-        } else {
-            (p - a).y > (p - a).x
-        };
-        (dist, sign)
+pub fn debug_tick_repeat(time: Res<Time>, mut repeat: ResMut<EditRepeat>) {
+    repeat.0 = (repeat.0 - time.delta_seconds()).max(0.);
+}
+
+// eases the editor camera's scale to where the scroll wheel last sent it, keeping the world point
+// that was under the cursor when scrolling fixed on screen all the way
+pub fn debug_ease_zoom(
+    time: Res<Time>,
+    mut ease: ResMut<ZoomEase>,
+    mut cam: Query<&mut Transform, (With<Camera>, With<MainCamera>)>,
+) {
+    let Some((target, anchor)) = ease.0 else {
+        return;
     };
+    let mut cam = cam.single_mut();
+    let s = 1. - (-ZoomEase::SPEED * time.delta_seconds()).exp();
+    // eased in log space so zooming in is as quick as zooming out
+    let (from, to) = (cam.scale.x.ln(), target.ln());
+    let mut scale = (from + (to - from) * s).exp();
+    if (scale / target - 1.).abs() < 1e-3 {
+        scale = target;
+        ease.0 = None;
+    }
+    let zoom = scale / cam.scale.x;
+    zoom_about(&mut cam, anchor, zoom);
+}
 
-    match *col {
-        Tile::SQUARE => {
-            if horz.abs() > vert.abs() {
-                (Vec2::new(0., vert), false, true)
-            } else {
-                (Vec2::new(horz, 0.), true, false)
-            }
-        }
-        Tile::STEPL | Tile::SLOPEL => {
-            // collide like a left triangle |\
-            let (dist, sign) = pt_line_dist(true, aabb.min);
-            if sign {
-                return (Vec2::ZERO, false, false);
-            }
-            let dampv = (*col == Tile::STEPL) || vert <= 0.;
-            let (vert_dist, vert_v) = (vert.abs(), (Vec2::new(0., vert), false, dampv));
-            let (horz_dist, horz_v) = (horz.abs(), (Vec2::new(horz, 0.), true, false));
-            let diag_v = (
-                Vec2::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2) * dist,
-                false,
-                *col == Tile::STEPL,
-            );
-            if dampv && vert_dist < horz_dist && vert_dist < dist {
-                vert_v
-            } else if horz_dist < dist {
-                horz_v
-            } else {
-                diag_v
-            }
-        }
-        Tile::STEPR | Tile::SLOPER => {
-            // collide like a right triangle /|
-            let p = Vec2::new(aabb.max.x, aabb.min.y);
-            let (dist, sign) = pt_line_dist(false, p);
-            if sign {
-                return (Vec2::ZERO, false, false);
-            }
-            let dampv = (*col == Tile::STEPR) || vert <= 0.;
-            let (vert_dist, vert_v) = (vert.abs(), (Vec2::new(0., vert), false, dampv));
-            let (horz_dist, horz_v) = (horz.abs(), (Vec2::new(horz, 0.), true, false));
-            let diag_v = (
-                Vec2::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2) * dist,
-                false,
-                *col == Tile::STEPR,
-            );
-            if dampv && vert_dist < horz_dist && vert_dist < dist {
-                vert_v
-            } else if horz_dist < dist {
-                horz_v
-            } else {
-                diag_v
+// how far to move the camera for the cursor going from one screen position to another with px
+// world units to a screen pixel, so the world point that was under the cursor stays under it
+fn drag_pan(from: Vec2, to: Vec2, px: f32) -> Vec2 {
+    // screen y points down, world y up
+    (to - from) * Vec2::new(-px, px)
+}
+
+// scale the camera by zoom while keeping the world point at anchor under the same screen pixel
+//
+// the world point under a screen position is cam.translation + offset * cam.scale,
+// so to keep anchor fixed its offset from the camera has to scale by the same amount
+fn zoom_about(cam: &mut Transform, anchor: Vec2, zoom: f32) {
+    // a scale of zero (or a negative one) breaks viewport_to_world_2d, so keep it in a sane range
+    let scale = (cam.scale.x * zoom).clamp(ZOOM_MIN, ZOOM_MAX);
+    let zoom = scale / cam.scale.x;
+    let cam_pos = anchor + (cam.translation.xy() - anchor) * zoom;
+    cam.translation = cam_pos.extend(cam.translation.z);
+    cam.scale = Vec3::new(scale, scale, 1.);
+}
+
+// the player's collision box, matching the sprite's rotation
+// (update_movement turns the player on its side while climbing, which swaps the box's width and height)
+fn ctl_aabb(t: &Transform) -> Aabb2d {
+    let (axis, angle) = t.rotation.to_axis_angle();
+    Rectangle::from_size(t.scale.xy()).aabb_2d(t.translation.xy(), angle * axis.z)
+}
+
+// the box debug_draw outlines around the player every frame, whether anything is touching it or not
+fn hitbox_outline(t: &Transform) -> Rect {
+    let aabb = ctl_aabb(t);
+    Rect::from_center_half_size(aabb.center(), aabb.half_size())
+}
+
+// change detection rather than looking at every tile, so a map nobody is editing costs nothing
+pub fn watch_tiles(
+    changed: Query<
+        (),
+        (
+            With<Tile>,
+            Or<(
+                Changed<Transform>,
+                Changed<Tile>,
+                Changed<Decor>,
+                Changed<Composite>,
+            )>,
+        ),
+    >,
+    mut removed: (
+        RemovedComponents<Tile>,
+        RemovedComponents<Decor>,
+        RemovedComponents<Composite>,
+    ),
+    mut version: ResMut<MapVersion>,
+) {
+    // every reader is read, so old removals don't turn up next frame
+    let gone = [
+        removed.0.read().count(),
+        removed.1.read().count(),
+        removed.2.read().count(),
+    ];
+    if !changed.is_empty() || gone.iter().any(|n| *n > 0) {
+        version.0 += 1;
+    }
+}
+
+pub fn cache_tiles(
+    tiles: Query<(&Transform, &Tile, Option<&Composite>), Without<Decor>>,
+    version: Res<MapVersion>,
+    scale: Res<PhysicsScale>,
+    mut cache: ResMut<TileCache>,
+) {
+    if cache.version == Some(*version) && !scale.is_changed() {
+        return;
+    }
+    cache.tiles.clear();
+    for (t, &c, composite) in &tiles {
+        match composite {
+            Some(composite) => (cache.tiles)
+                .extend((composite.colliders(t)).map(|(c, aabb)| (c, scale.to_physics(aabb)))),
+            None => {
+                let aabb = Aabb2d::new(t.translation.xy(), t.scale.xy() / 2.);
+                cache.tiles.push((c, scale.to_physics(aabb)));
+            }
+        }
+    }
+    if cache.merge {
+        let before = cache.tiles.len();
+        physics::merge_slopes(&mut cache.tiles);
+        physics::merge_squares(&mut cache.tiles);
+        info!("merged {before} colliders into {}", cache.tiles.len());
+    }
+    cache.version = Some(*version);
+}
+
+// platforms move before the player does, taking whoever is stuck to them along
+pub fn move_platforms(
+    time: Res<GameTime>,
+    mut platforms: Query<(Entity, &mut Transform, &mut Platform), Without<Control>>,
+    mut ctl: Query<(&mut Transform, &Movement), With<Control>>,
+) {
+    for (e, mut t, mut platform) in &mut platforms {
+        platform.time += time.delta_seconds();
+        let delta = platform.at() - t.translation.xy();
+        t.translation += delta.extend(0.);
+        for (mut ct, v) in &mut ctl {
+            if v.platform == Some(e) {
+                ct.translation += delta.extend(0.);
             }
         }
-        _ => unreachable!(),
     }
 }
 
@@ -639,258 +1850,1136 @@ fn collide_push(aabb: &Aabb2d, col: &Tile, col_aabb: &Aabb2d) -> (Vec2, bool, bo
 //
 // this is not working correctly as it sees collisions where it shouldn't
 pub fn check_collide(
-    time: Res<Time>,
+    time: Res<GameTime>,
+    feel: Res<GameFeel>,
+    max_v: Res<MaxVelocity>,
     mut update_rem: ResMut<PhysicsTick>,
-    mut ctl: Query<(&Transform, &mut Movement), With<Control>>,
-    col: Query<(&Transform, &Tile)>,
+    mut ctl: Query<(Entity, &Transform, &mut Movement), With<Control>>,
+    cache: Res<TileCache>,
     mut dbg: Query<&mut DebugUi>,
-    mut win_text: Query<&mut Visibility, With<WinText>>,
-    mut end: ResMut<End>,
+    mut commands: Commands,
+    fades: Query<(), With<Fade>>,
+    end: Res<End>,
+    level: Res<Level>,
+    (mut ev_collide, mut ev_crushed): (EventWriter<CollisionEvent>, EventWriter<Crushed>),
+    mut log: Option<ResMut<PhysicsLog>>,
+    no_collide: Option<Res<NoCollide>>,
+    scale: Res<PhysicsScale>,
+    (winds, platforms): (
+        Query<(&Transform, &Wind)>,
+        Query<(Entity, &Transform), With<Platform>>,
+    ),
 ) {
-    let (t, mut v) = ctl.single_mut();
-    if v.ctl + v.force == Vec2::ZERO {
-        v.out = Vec2::ZERO;
-        return;
-    }
-    if t.translation.y > (MAP_TOP - 2. * Tile::SZ - 3.) {
-        *win_text.single_mut() = Visibility::Visible;
-        end.0 = true;
-    }
-
-    let mut dt = update_rem.0;
-    // 60 physics ticks a second
-    dt += time.delta_seconds() * 60.;
-    if dt < 1. {
-        update_rem.0 = dt;
-    }
-    let mut aabb = Aabb2d::new(t.translation.xy(), t.scale.xy() / 2.);
-    v.climb = false;
-    let mut collisions = vec![];
-    let mut pushes = vec![];
-    while dt >= 1. {
-        collisions = vec![];
-        v.force += Vec2::new(0., -9.8 / 60.);
-        aabb = Aabb2d::new(aabb.center() + v.ctl.xy() + v.force.xy(), aabb.half_size());
-        for (col, &c) in &col {
-            let col_aabb = Aabb2d::new(col.translation.xy(), col.scale.xy() / 2.);
-            if aabb.intersects(&col_aabb) {
-                collisions.push((
-                    (col_aabb.center() - aabb.center()).length_squared(),
-                    c,
-                    col_aabb,
-                ));
-            }
+    let winds: Vec<_> = (winds.iter())
+        .map(|(t, w)| {
+            let area = Aabb2d::new(t.translation.xy(), t.scale.xy() / 2.);
+            (scale.to_physics(area), w.0)
+        })
+        .collect();
+    let platforms: Vec<_> = (platforms.iter())
+        .map(|(e, t)| {
+            let block = Aabb2d::new(t.translation.xy(), t.scale.xy() / 2.);
+            (e, scale.to_physics(block))
+        })
+        .collect();
+    let pressed = |aabb: &Aabb2d| platforms.iter().any(|(_, block)| block.intersects(aabb));
+    let solid;
+    let tiles = match no_collide.filter(|off| off.0.contains(&true)) {
+        Some(off) => {
+            solid = (cache.tiles.iter())
+                .filter(|(tile, _)| !off.0.get(tile.0 as usize).copied().unwrap_or(false))
+                .copied()
+                .chain(platforms.iter().map(|(_, block)| (Tile::SQUARE, *block)))
+                .collect::<Vec<_>>();
+            &solid
+        }
+        // platforms move every frame, so they're added here rather than cached with the tiles
+        None if !platforms.is_empty() => {
+            solid = (cache.tiles.iter().copied())
+                .chain(platforms.iter().map(|(_, block)| (Tile::SQUARE, *block)))
+                .collect::<Vec<_>>();
+            &solid
+        }
+        None => &cache.tiles,
+    };
+    // 60 physics ticks a second, the same number for every player
+    let ticks = (update_rem.rem + time.delta_seconds() * 60.).min(update_rem.max_ticks);
+    // what's left over after stepping, only taken if someone was actually stepped
+    let mut rem = None;
+    for (e, t, mut v) in &mut ctl {
+        // a NaN or inf would stick around in force forever, and float drift can leave denormal
+        // leftovers that keep the player creeping, so clean both up before using them
+        v.ctl = physics::sanitize(v.ctl);
+        v.force = physics::sanitize(v.force);
+        let mut aabb = scale.to_physics(ctl_aabb(t));
+        let start = aabb;
+        // standing still in a breeze still needs stepping, and so does a platform running into you
+        let in_wind = winds.iter().any(|(area, _)| area.intersects(&aabb));
+        if v.ctl + v.force == Vec2::ZERO && !in_wind && !pressed(&aabb) {
+            v.wind = Vec2::ZERO;
+            v.out = Vec2::ZERO;
+            continue;
+        }
+        if t.translation.y > (level.top() - 2. * Tile::SZ - 3.) {
+            win(&mut commands, &fades, &end);
         }
 
-        // sort by distance to aabb
-        collisions.sort_by(|c1, c2| c1.0.total_cmp(&c2.0));
-
-        // three tries outta be enough
-        for i in 0..3 {
-            let mut pushed = false;
-            for (_, col, col_aabb) in &collisions {
-                if !aabb.intersects(col_aabb) {
-                    continue;
-                }
-                let (push, damph, dampv) = collide_push(&aabb, col, col_aabb);
-                if push == Vec2::ZERO {
-                    continue;
-                }
-                pushes.push((i, *col, push, damph, dampv));
-
-                if dampv {
-                    if v.ctl.y > 0. && push.y < 0. {
-                        v.climb = true;
-                    }
-                    v.force.y = 0.;
-                }
-                if damph {
-                    if push.x.signum() != v.force.x.signum() {
-                        v.force.x = 0.;
-                    }
-                }
-                aabb.min += push;
-                aabb.max += push;
-                pushed = true;
-            }
-            if !pushed {
+        let mut dt = ticks;
+        v.climb = false;
+        let mut collisions = vec![];
+        let mut pushes = vec![];
+        while dt >= 1. {
+            max_v.clamp(&mut v);
+            // overlapping zones add up
+            v.wind = (winds.iter())
+                .filter(|(area, _)| area.intersects(&aabb))
+                .map(|(_, push)| *push)
+                .sum();
+            let before = aabb;
+            let step = physics::step(aabb, &mut v, &feel, tiles);
+            aabb = step.aabb;
+            // pushed out both ways at once with a platform in the box means there's nowhere to go
+            let opposed = (step.contacts.iter())
+                .any(|a| (step.contacts.iter()).any(|b| a.push.normal.dot(b.push.normal) < -0.5));
+            if opposed && pressed(&before) {
+                ev_crushed.send(Crushed(e));
+                dt = 0.;
                 break;
             }
+            if let Some(Err(err)) = log.as_mut().map(|log| log.record(&step, &v)) {
+                eprintln!("physics log stopped: {err}");
+                commands.remove_resource::<PhysicsLog>();
+                log = None;
+            }
+            for c in &step.contacts {
+                ev_collide.send(CollisionEvent {
+                    entity: e,
+                    tile: c.tile,
+                    normal: c.push.normal,
+                    vel: c.vel,
+                });
+            }
+            pushes.extend(step.contacts.iter().map(|c| (c.pass, c.tile, c.push)));
+            collisions = step.collisions;
+            dt -= 1.;
         }
-        dt -= 1.;
-    }
-    if cfg!(debug_assertions) && !collisions.is_empty() {
-        let mut dbg = dbg.single_mut();
-        dbg.watch("vctl", v.ctl);
-        dbg.watch("vforce", v.force);
-        dbg.watch("pos", t.translation);
-        dbg.watch("rot", t.rotation.to_axis_angle());
-        dbg.watch("climb", v.climb);
-        dbg.watch("pushes", pushes);
-        dbg.collisions = collisions
-            .into_iter()
-            .map(|(_, c, aabb)| (c, aabb))
-            .collect();
-        dbg.ctl_aabb = Some(aabb);
+        rem = Some(rem.map_or(dt, |r: f32| r.min(dt)));
+        // landing on a platform sticks to it, leaving the ground lets go
+        v.platform = (platforms.iter())
+            .filter(|_| v.grounded)
+            .find(|(_, block)| {
+                (aabb.min.y - block.max.y).abs() <= 1.
+                    && aabb.max.x > block.min.x
+                    && aabb.min.x < block.max.x
+            })
+            .map(|(e, _)| *e);
+        if cfg!(debug_assertions) && !collisions.is_empty() {
+            let mut dbg = dbg.single_mut();
+            dbg.watch("vctl", v.ctl);
+            dbg.watch("vforce", v.force);
+            dbg.graph("force.y", v.force.y);
+            dbg.watch("pos", t.translation);
+            dbg.watch("rot", t.rotation.to_axis_angle());
+            dbg.watch("climb", v.climb);
+            dbg.watch("grounded", v.grounded);
+            dbg.watch("wall_dir", v.wall_dir);
+            dbg.watch("hang", v.hang);
+            dbg.watch("pushes", pushes);
+            // the overlay draws in transform units
+            dbg.collisions = (collisions.into_iter())
+                .map(|(c, aabb)| (c, scale.to_render(aabb)))
+                .collect();
+        }
+        if cfg!(debug_assertions) {
+            let mut dbg = dbg.single_mut();
+            dbg.ctl_aabb = Some(scale.to_render(aabb));
+            dbg.ctl_color = movement_color(&v);
+            dbg.sweep = Some((scale.to_render(start), scale.to_render(aabb)));
+        }
+
+        let tnew = aabb.center();
+        v.out = tnew - t.translation.xy() / scale.0;
     }
 
-    let tnew = aabb.center();
-    v.out = tnew - t.translation.xy();
-    if dt != update_rem.0 {
-        update_rem.0 = dt;
+    if let Some(log) = &mut log {
+        // the game usually exits without dropping resources, so don't leave records in the buffer
+        let _ = std::io::Write::flush(&mut log.out);
+    }
+    if let Some(rem) = rem {
+        update_rem.rem = rem;
     }
 }
 
-pub fn update_movement(mut movers: Query<(&mut Transform, &Movement, &mut Sprite)>) {
-    for (mut t, v, mut s) in &mut movers {
-        t.translation.x += v.out.x;
-        t.translation.y += v.out.y;
-
-        if !v.climb {
-            t.rotation = Quat::IDENTITY;
-            if v.ctl.x < 0. {
-                s.flip_x = true;
-            } else if v.ctl.x > 0. {
-                s.flip_x = false;
-            }
+// push overlapping players apart, half each, along whichever axis they overlap least
+// runs after check_collide so it works on where they're about to move to
+pub fn push_players(
+    collide: Res<PlayerCollide>,
+    mut players: Query<(&Transform, &mut Movement), With<Control>>,
+    scale: Res<PhysicsScale>,
+) {
+    if !collide.0 {
+        return;
+    }
+    let mut pairs = players.iter_combinations_mut();
+    while let Some([(ta, mut va), (tb, mut vb)]) = pairs.fetch_next() {
+        // out is in physics units, so the boxes are too
+        let a = scale.to_physics(ctl_aabb(ta));
+        let a = Aabb2d::new(a.center() + va.out, a.half_size());
+        let b = scale.to_physics(ctl_aabb(tb));
+        let b = Aabb2d::new(b.center() + vb.out, b.half_size());
+        if !a.intersects(&b) {
+            continue;
+        }
+        let overlap = a.max.min(b.max) - a.min.max(b.min);
+        let dir = b.center() - a.center();
+        let push = if overlap.x < overlap.y {
+            Vec2::new(overlap.x * dir.x.signum(), 0.)
+        } else {
+            Vec2::new(0., overlap.y * dir.y.signum())
+        } / 2.;
+        va.out -= push;
+        vb.out += push;
+    }
+}
+
+// a small xorshift generator for things that only have to look random, like sound pitch.
+// BABY_SEED=<n> starts it from n so a run sounds the same every time, otherwise from std's
+// per-process hash keys (the clock isn't there to read on the web)
+#[derive(Resource)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift stays at 0 forever, and a small seed takes a while to look random
+        let mut rng = Rng((seed ^ 0x9e37_79b9_7f4a_7c15).max(1));
+        rng.next_u64();
+        rng
+    }
+
+    pub fn from_env() -> Rng {
+        let seed = std::env::var("BABY_SEED").ok().and_then(|s| s.parse().ok());
+        Rng::new(seed.unwrap_or_else(|| {
+            use std::hash::BuildHasher;
+            std::collections::hash_map::RandomState::new().hash_one(0u8)
+        }))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // uniform in lo..hi
+    pub fn range(&mut self, lo: f32, hi: f32) -> f32 {
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        lo + (hi - lo) * unit
+    }
+}
+
+// a sound effect on an entity of its own that goes away when it's done, so the same effect can
+// be played again while the last one is still going. the intro's sounds are one entity each that
+// it pauses and plays, which a Once sound can't be played again on
+pub fn play_once(commands: &mut Commands, source: Handle<AudioSource>, settings: PlaybackSettings) {
+    let settings = PlaybackSettings {
+        mode: bevy::audio::PlaybackMode::Despawn,
+        ..settings
+    };
+    commands.spawn(AudioBundle { source, settings });
+}
+
+// plays a sound once with its speed (and so its pitch) nudged up or down by up to spread of
+// itself, so one played over and over doesn't sound like the same sample every time
+pub fn play_varied(
+    commands: &mut Commands,
+    source: Handle<AudioSource>,
+    settings: PlaybackSettings,
+    spread: f32,
+    rng: &mut Rng,
+) {
+    let speed = settings.speed * (1. + rng.range(-spread, spread));
+    play_once(commands, source, settings.with_speed(speed));
+}
+
+// thump when the player hits the ground hard enough
+// resting on the ground only ever builds up a single tick of gravity, so it stays under the threshold
+pub fn play_land_sound(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut ev_collide: EventReader<CollisionEvent>,
+    mut rng: ResMut<Rng>,
+) {
+    const LAND_SPEED: f32 = 4.;
+    let hard_landing = ev_collide
+        .read()
+        .any(|ev| ev.normal.y > 0. && -ev.vel.y > LAND_SPEED);
+    if hard_landing {
+        let thump = assets.load("sounds/thump.wav");
+        play_varied(
+            &mut commands,
+            thump,
+            PlaybackSettings::DESPAWN,
+            0.1,
+            &mut rng,
+        );
+    }
+}
+
+// pressed plates click and go dark until they're let go
+pub fn show_plates(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut ev_pressed: EventReader<PlatePressed>,
+    mut ev_released: EventReader<PlateReleased>,
+    mut sprites: Query<&mut Sprite>,
+    level: Res<Level>,
+) {
+    let color = if cfg!(debug_assertions) {
+        level.palette[Tile::PLATE.0 as usize]
+    } else {
+        Color::NONE
+    };
+    for ev in ev_pressed.read() {
+        if let Ok(mut sprite) = sprites.get_mut(ev.entity) {
+            sprite.color = color * 0.5;
+        }
+        let settings = PlaybackSettings::DESPAWN.with_speed(1.6);
+        play_once(&mut commands, assets.load("sounds/thump.wav"), settings);
+    }
+    for ev in ev_released.read() {
+        if let Ok(mut sprite) = sprites.get_mut(ev.entity) {
+            sprite.color = color;
+        }
+    }
+}
+
+// one footstep per stride of ground covered, so slow walking steps slowly
+pub fn play_footsteps(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut ctl: Query<&mut Movement, With<Control>>,
+    mut rng: ResMut<Rng>,
+) {
+    const STRIDE: f32 = 40.;
+    for mut v in &mut ctl {
+        if !v.grounded {
+            continue;
+        }
+        v.step_dist += v.out.x.abs();
+        while v.step_dist >= STRIDE {
+            v.step_dist -= STRIDE;
+            let settings = PlaybackSettings::DESPAWN
+                .with_volume(bevy::audio::Volume::new(0.2))
+                .with_speed(1.5);
+            let thump = assets.load("sounds/thump.wav");
+            play_varied(&mut commands, thump, settings, 0.1, &mut rng);
+        }
+    }
+}
+
+// touching a hazard costs health once, then the player is invulnerable for a bit
+// so standing on one drains health every IFRAMES seconds instead of every tick
+pub fn take_damage(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut ev_collide: EventReader<CollisionEvent>,
+    mut ev_crushed: EventReader<Crushed>,
+    mut ctl: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Movement,
+            &mut Health,
+            Option<&mut Invulnerable>,
+        ),
+        With<Control>,
+    >,
+) {
+    let crushed: Vec<_> = ev_crushed.read().map(|ev| ev.0).collect();
+    let hits: Vec<_> = ev_collide.read().map(|ev| (ev.entity, ev.tile)).collect();
+    for (e, mut t, mut v, mut hp, iframes) in &mut ctl {
+        // being crushed kills whatever the health, invulnerable or not
+        if crushed.contains(&e) {
+            commands.entity(e).remove::<Invulnerable>();
+            respawn(&mut t, &mut v);
+            hp.0 = Health::MAX;
+            continue;
+        }
+        let damage = (hits.iter())
+            .filter(|(hit, _)| *hit == e)
+            .map(|(_, tile)| tile.damage())
+            .fold(0., f32::max);
+        if let Some(mut iframes) = iframes {
+            iframes.0.tick(time.delta());
+            if iframes.0.finished() {
+                commands.entity(e).remove::<Invulnerable>();
+            }
+            continue;
+        }
+        if damage <= 0. {
+            continue;
+        }
+        hp.0 -= damage;
+        if hp.0 > 0. {
+            commands.entity(e).insert(Invulnerable(Timer::from_seconds(
+                Health::IFRAMES,
+                TimerMode::Once,
+            )));
+            continue;
+        }
+        // out of health, back to the same spot the kill box sends you to
+        respawn(&mut t, &mut v);
+        hp.0 = Health::MAX;
+    }
+}
+
+// back to the spawn point at a standstill, so nothing from the fall carries over
+fn respawn(t: &mut Transform, v: &mut Movement) {
+    t.translation = PlayerBundle::SPAWN.extend(Layer::Player.z());
+    v.ctl = Vec2::ZERO;
+    v.force = Vec2::ZERO;
+    v.out = Vec2::ZERO;
+    v.climb = false;
+    v.slide = 0.;
+    v.hang = 0.;
+    v.platform = None;
+}
+
+pub fn fade_foreground(
+    time: Res<Time>,
+    ctl: Query<&Transform, With<Control>>,
+    mut fg: Query<(&Transform, &mut Sprite, &mut Foreground)>,
+) {
+    let players: Vec<_> = ctl.iter().map(ctl_aabb).collect();
+    for (t, mut s, mut fg) in &mut fg {
+        let size = s.custom_size.unwrap_or(Vec2::ONE) * t.scale.xy();
+        let aabb = Aabb2d::new(t.translation.xy(), size / 2.);
+        let behind = players.iter().any(|p| p.intersects(&aabb));
+        let target = if behind { Foreground::FADED } else { 1. };
+        let step = Foreground::SPEED * time.delta_seconds();
+        let shown = fg.shown + (target - fg.shown).clamp(-step, step);
+        if shown != fg.shown {
+            fg.shown = shown;
+            s.color.set_a(fg.alpha * shown);
+        }
+    }
+}
+
+pub fn update_health_bar(
+    ctl: Query<&Health, (With<Control>, Changed<Health>)>,
+    mut bar: Query<&mut Style, With<HealthBar>>,
+) {
+    let Ok(hp) = ctl.get_single() else {
+        return;
+    };
+    for mut s in &mut bar {
+        s.width = Val::Percent(100. * (hp.0 / Health::MAX).clamp(0., 1.));
+    }
+}
+
+pub fn update_movement(
+    mut movers: Query<(&mut Transform, &mut Movement, &mut Facing)>,
+    bounds: Res<MapBounds>,
+    scale: Res<PhysicsScale>,
+) {
+    for (mut t, mut v, mut facing) in &mut movers {
+        if v.out.is_finite() {
+            t.translation.x += v.out.x * scale.0;
+            t.translation.y += v.out.y * scale.0;
+        }
+
+        if !v.climb {
+            t.rotation = Quat::IDENTITY;
+            if v.ctl.x < 0. {
+                facing.left = true;
+            } else if v.ctl.x > 0. {
+                facing.left = false;
+            }
         } else {
-            s.flip_x = true;
+            facing.left = true;
             t.rotation = Quat::from_rotation_z(-PI / 2.);
         }
 
         // kill box
-        if t.translation.y < -1000. {
-            t.translation = Vec3::ZERO;
+        if t.translation.y < bounds.0.min.y - MapBounds::KILL_DEPTH {
+            respawn(&mut t, &mut v);
         }
     }
 }
 
-pub fn pan_camera(
-    mut cam: Query<&mut Transform, (With<Camera>, Without<Control>)>,
+// something the player can use or talk to, its area is the transform's translation and scale
+// like a tile's. nothing spawns these yet
+#[derive(Component)]
+pub struct Interactable;
+
+// the closest interactable in reach in front of the player, for whatever ends up using it
+#[derive(Resource, Default)]
+pub struct InteractTarget(pub Option<Entity>);
+
+impl InteractTarget {
+    // how far in front of the player things are in reach
+    const REACH: f32 = 30.;
+
+    // the area in front of a player box, as tall as the player
+    pub fn reach(player: &Aabb2d, left: bool) -> Aabb2d {
+        let half = Vec2::new(Self::REACH / 2., player.half_size().y);
+        let x = if left {
+            player.min.x - half.x
+        } else {
+            player.max.x + half.x
+        };
+        Aabb2d::new(Vec2::new(x, player.center().y), half)
+    }
+}
+
+pub fn find_interactable(
+    ctl: Query<(&Transform, &Facing), With<Control>>,
+    things: Query<(Entity, &Transform), With<Interactable>>,
+    mut target: ResMut<InteractTarget>,
+) {
+    let found = ctl.get_single().ok().and_then(|(t, facing)| {
+        let player = ctl_aabb(t);
+        let reach = InteractTarget::reach(&player, facing.left);
+        (things.iter())
+            .filter(|(_, tt)| {
+                Aabb2d::new(tt.translation.xy(), tt.scale.xy() / 2.).intersects(&reach)
+            })
+            .min_by(|(_, a), (_, b)| {
+                let (a, b) = (a.translation.xy(), b.translation.xy());
+                let c = player.center();
+                a.distance_squared(c).total_cmp(&b.distance_squared(c))
+            })
+            .map(|(e, _)| e)
+    });
+    if target.0 != found {
+        target.0 = found;
+    }
+}
+
+// E in the editor stops the player where it is, with input and physics off, so tiles can be
+// placed around it without it falling through them. E again goes back to playing from there
+#[derive(Resource, Clone, Copy, Default)]
+pub struct EditMode(pub bool);
+
+impl EditMode {
+    pub fn off(mode: Res<EditMode>) -> bool {
+        !mode.0
+    }
+}
+
+// BABY_PUZZLE=1 swaps the physics for moving one whole tile per arrow press, sliding over to the
+// next cell and staying put if it's solid. there's no gravity, the player goes where it's pushed
+#[derive(Resource, Clone, Copy, Default)]
+pub struct PuzzleMode(pub bool);
+
+impl PuzzleMode {
+    // seconds to slide from one cell to the next
+    const SLIDE: f32 = 0.15;
+
+    pub fn from_env() -> PuzzleMode {
+        PuzzleMode(std::env::var_os("BABY_PUZZLE").is_some())
+    }
+
+    pub fn off(mode: Res<PuzzleMode>) -> bool {
+        !mode.0
+    }
+
+    pub fn on(mode: Res<PuzzleMode>) -> bool {
+        mode.0
+    }
+
+    // the cell one step from the center of cell `from` in dir, or from again if that's solid
+    pub fn target(from: Vec2, dir: Vec2, solid: impl Fn(Vec2) -> bool) -> Vec2 {
+        let to = from + dir * Tile::SZ;
+        if solid(to) {
+            from
+        } else {
+            to
+        }
+    }
+}
+
+// stands in for check_kbd, check_collide and update_movement in puzzle mode
+pub fn puzzle_move(
+    kbd: Res<ButtonInput<KeyCode>>,
+    time: Res<GameTime>,
+    level: Res<Level>,
+    tiles: Tiles,
+    mut ctl: Query<(&mut Transform, &mut Facing), (With<Control>, Without<Tile>)>,
+    // (from, to, seconds into the slide)
+    mut slide: Local<Option<(Vec2, Vec2, f32)>>,
+) {
+    let Ok((mut t, mut facing)) = ctl.get_single_mut() else {
+        return;
+    };
+    if let Some((from, to, elapsed)) = slide.as_mut() {
+        *elapsed += time.delta_seconds();
+        let f = (*elapsed / PuzzleMode::SLIDE).min(1.);
+        t.translation = from.lerp(*to, f).extend(t.translation.z);
+        if f < 1. {
+            return;
+        }
+        *slide = None;
+    }
+    let dir = [
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, Vec2::X),
+        (KeyCode::ArrowUp, Vec2::Y),
+        (KeyCode::ArrowDown, Vec2::NEG_Y),
+    ]
+    .into_iter()
+    .find(|(key, _)| kbd.just_pressed(*key));
+    let Some((_, dir)) = dir else {
+        return;
+    };
+    if dir.x != 0. {
+        facing.left = dir.x < 0.;
+    }
+    let from = level.snap(t.translation.xy());
+    let to = PuzzleMode::target(from, dir, |p| tiles.is_solid(p).is_some());
+    *slide = Some((t.translation.xy(), to, 0.));
+}
+
+// whether a player box is resting on top of the tile, with the skin between them
+fn standing_on(player: &Aabb2d, tile: &Transform, scale: PhysicsScale) -> bool {
+    let tile = Aabb2d::new(tile.translation.xy(), tile.scale.xy() / 2.);
+    (player.min.y - tile.max.y).abs() <= 2. * physics::SKIN * scale.0
+        && player.min.x < tile.max.x
+        && player.max.x > tile.min.x
+}
+
+// landing hard on a breakable tile starts it breaking: it stops colliding right away, shrinks
+// and fades out over Breaking::TIME and throws off some debris
+pub fn stomp_tiles(
+    mut commands: Commands,
+    mut ev_collide: EventReader<CollisionEvent>,
+    players: Query<&Transform, With<Control>>,
+    tiles: Query<(Entity, &Transform, &Tile, &Sprite)>,
+    scale: Res<PhysicsScale>,
+) {
+    let stomped = (ev_collide.read())
+        .any(|ev| ev.tile == Tile::BREAK && ev.normal.y > 0. && -ev.vel.y > Breaking::STOMP_SPEED);
+    if !stomped {
+        return;
+    }
+    for t in &players {
+        let p = ctl_aabb(t);
+        for (e, tt, tile, sprite) in &tiles {
+            if *tile != Tile::BREAK || !standing_on(&p, tt, *scale) {
+                continue;
+            }
+            commands
+                .entity(e)
+                .remove::<Tile>()
+                .insert(Breaking(Timer::from_seconds(
+                    Breaking::TIME,
+                    TimerMode::Once,
+                )));
+            for i in 0..Breaking::DEBRIS {
+                // fanned out upwards
+                let a = PI * (i as f32 + 0.5) / Breaking::DEBRIS as f32;
+                commands.spawn((
+                    Debris {
+                        vel: Vec2::new(a.cos(), a.sin()) * 3.,
+                        life: Timer::from_seconds(0.5, TimerMode::Once),
+                    },
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: sprite.color,
+                            custom_size: Some(Vec2::ONE),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: tt.translation + Vec3::new(0., 0., 0.1),
+                            scale: Vec3::new(8., 8., 1.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+            }
+        }
+    }
+}
+
+pub fn animate_breaking(
+    time: Res<GameTime>,
+    mut commands: Commands,
+    mut breaking: Query<(Entity, &mut Breaking, &mut Sprite), Without<Debris>>,
+    mut debris: Query<(Entity, &mut Debris, &mut Transform, &mut Sprite)>,
+) {
+    for (e, mut b, mut sprite) in &mut breaking {
+        b.0.tick(time.delta());
+        if b.0.finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        let left = b.0.fraction_remaining();
+        sprite.custom_size = Some(Vec2::splat(left));
+        sprite.color.set_a(left);
+    }
+    // debris speeds are per physics tick like the player's
+    let ticks = time.delta_seconds() * 60.;
+    for (e, mut d, mut t, mut sprite) in &mut debris {
+        d.life.tick(time.delta());
+        if d.life.finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        d.vel.y -= 0.3 * ticks;
+        t.translation += (d.vel * ticks).extend(0.);
+        let left = d.life.fraction_remaining();
+        sprite.color.set_a(left);
+    }
+}
+
+// which plates have a player standing on them, sending events only when that changes
+pub fn press_plates(
+    players: Query<(&Transform, &Movement), With<Control>>,
+    plates: Query<(Entity, &Transform, &Tile)>,
+    scale: Res<PhysicsScale>,
+    mut pressed: Local<Vec<Entity>>,
+    mut ev_pressed: EventWriter<PlatePressed>,
+    mut ev_released: EventWriter<PlateReleased>,
+) {
+    let mut now = vec![];
+    for (t, v) in &players {
+        if !v.grounded {
+            continue;
+        }
+        let p = ctl_aabb(t);
+        for (e, pt, tile) in &plates {
+            if *tile == Tile::PLATE && standing_on(&p, pt, *scale) && !now.contains(&e) {
+                now.push(e);
+            }
+        }
+    }
+    for &e in now.iter().filter(|e| !pressed.contains(e)) {
+        ev_pressed.send(PlatePressed { entity: e });
+    }
+    for &e in pressed.iter().filter(|e| !now.contains(e)) {
+        ev_released.send(PlateReleased { entity: e });
+    }
+    *pressed = now;
+}
+
+// the player's sheet follows whether it's on something
+pub fn pick_sheet(mut players: Query<(&Movement, &mut SpriteSheets), With<Control>>) {
+    for (v, mut sheets) in &mut players {
+        let want = if v.grounded || v.climb {
+            "idle"
+        } else {
+            "jump"
+        };
+        // only write on a change, swap_sheets looks for changed components
+        if sheets.current != want {
+            sheets.current = want;
+        }
+    }
+}
+
+pub fn camera_triggers(
+    mut cam: Query<&mut Transform, (With<MainCamera>, Without<Control>)>,
     ctl: Query<&Transform, With<Control>>,
+    triggers: Query<(&Transform, &CameraTrigger), Without<MainCamera>>,
+    mut cue: ResMut<CameraCue>,
+    time: Res<Time>,
+) {
+    let (Ok(mut cam), Ok(t)) = (cam.get_single_mut(), ctl.get_single()) else {
+        return;
+    };
+    let player = ctl_aabb(t);
+    let active = (triggers.iter())
+        .find(|(tt, _)| Aabb2d::new(tt.translation.xy(), tt.scale.xy() / 2.).intersects(&player))
+        .map(|(_, trigger)| *trigger);
+    if cue.active != active {
+        cue.active = active;
+    }
+    let frames = time.delta_seconds() * 60.;
+    let gain = 1. - (1. - CameraCue::GAIN).powf(frames);
+    match (active, cue.restore) {
+        (Some(trigger), restore) => {
+            if restore.is_none() {
+                cue.restore = Some(cam.scale.x);
+            }
+            let to = trigger.target.extend(cam.translation.z);
+            cam.translation = cam.translation.lerp(to, gain);
+            cam.scale = cam
+                .scale
+                .lerp(Vec3::new(trigger.scale, trigger.scale, 1.), gain);
+        }
+        // out again, pan_camera is following but the zoom still has to go back
+        (None, Some(scale)) => {
+            cam.scale = cam.scale.lerp(Vec3::new(scale, scale, 1.), gain);
+            if (cam.scale.x / scale - 1.).abs() < 1e-3 {
+                cam.scale = Vec3::new(scale, scale, 1.);
+                cue.restore = None;
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+pub fn pan_camera(
+    mut cam: Query<(&mut Transform, &OrthographicProjection), (With<Camera>, Without<Control>)>,
+    ctl: Query<(&Transform, &Movement), With<Control>>,
+    follow: Res<CameraFollow>,
+    bounds: Res<MapBounds>,
     end: Res<End>,
+    time: Res<Time>,
+    cue: Res<CameraCue>,
 ) {
-    // move the camera to track the player when he gets too close to the edge of the window
-    let ctl = ctl.single().translation;
-    let mut cam = cam.single_mut();
+    let (mut cam, proj) = cam.single_mut();
     if end.0 {
         cam.translation = Vec3::ZERO;
         cam.scale = Vec3::ONE;
         return;
     }
-    // hardcoded 100x100 pixel box
-    let cam_bound = 100.;
-    if (ctl.x - cam.translation.x).abs() > cam_bound {
-        let dx = ctl.x - cam.translation.x;
-        if dx < 0. {
-            cam.translation.x += dx + cam_bound;
-        } else {
-            cam.translation.x += dx - cam_bound;
+    if !follow.enabled || cue.active.is_some() {
+        return;
+    }
+    // the player can be missing (or doubled up) for a frame while it's being respawned,
+    // the camera just stays put until there's one to follow
+    let (ctl, grounded) = match ctl.get_single() {
+        Ok((t, v)) => (t.translation.xy() + follow.offset, v.grounded),
+        Err(e) => {
+            trace!("not panning the camera: {e}");
+            return;
         }
+    };
+    // move the camera to track the player when he gets too close to the edge of the window
+    let d = ctl - cam.translation.xy();
+    let bound = follow.deadzone;
+    let mut out = Vec2::ZERO;
+    if d.x.abs() > bound.x {
+        out.x = d.x - bound.x * d.x.signum();
+    }
+    if d.y.abs() > bound.y {
+        out.y = d.y - bound.y * d.y.signum();
+    }
+    // the same gain per 60th of a second whatever the frame rate
+    let frames = time.delta_seconds() * 60.;
+    let gain = Vec2::ONE - (Vec2::ONE - follow.gain.clamp(Vec2::ZERO, Vec2::ONE)).powf(frames);
+    if follow.settle_on_ground {
+        // the deadzone edge is a hard limit so the player can't fall off screen, inside it the
+        // camera only drifts to the player once they land
+        cam.translation.x += out.x * gain.x;
+        cam.translation.y += out.y;
+        if grounded {
+            cam.translation.y += (d.y - out.y) * gain.y;
+        }
+    } else {
+        cam.translation += (out * gain).extend(0.);
     }
-    if (ctl.y - cam.translation.y).abs() > cam_bound {
-        let dy = ctl.y - cam.translation.y;
-        if dy < 0. {
-            cam.translation.y += dy + cam_bound;
+
+    // don't show past the edge of the map, or center on it if the view is bigger than the map
+    let half_view = proj.area.half_size() * cam.scale.xy();
+    let lo = bounds.0.min + half_view;
+    let hi = bounds.0.max - half_view;
+    let center = bounds.0.center();
+    let keep_in = |pos: f32, lo: f32, hi: f32, center: f32| {
+        if lo > hi {
+            center
         } else {
-            cam.translation.y += dy - cam_bound;
+            pos.clamp(lo, hi)
         }
+    };
+    cam.translation.x = keep_in(cam.translation.x, lo.x, hi.x, center.x);
+    cam.translation.y = keep_in(cam.translation.y, lo.y, hi.y, center.y);
+}
+
+// debug tint for the player's box: on the ground, against a wall, or in the air
+fn movement_color(v: &Movement) -> Color {
+    if v.grounded {
+        Color::GREEN
+    } else if v.wall_dir != 0. {
+        Color::ORANGE
+    } else {
+        Color::YELLOW
     }
 }
 
-pub fn debug_draw(mut gizmos: Gizmos, mut dbg: Query<(&mut Text, &DebugUi)>) {
-    let (mut txt, dbg) = dbg.single_mut();
-    txt.sections = (dbg.text.iter())
-        .map(|(k, v)| TextSection::new(format!("{k}: {v}\n"), default()))
-        .collect();
+// lines from each corner of start to the same corner of end, which with the two boxes outline
+// everything the box passed through moving between them
+pub fn sweep_edges(start: &Aabb2d, end: &Aabb2d) -> [(Vec2, Vec2); 4] {
+    let corner = |b: &Aabb2d, x: bool, y: bool| {
+        Vec2::new(
+            if x { b.max.x } else { b.min.x },
+            if y { b.max.y } else { b.min.y },
+        )
+    };
+    [(false, false), (true, false), (true, true), (false, true)]
+        .map(|(x, y)| (corner(start, x, y), corner(end, x, y)))
+}
+
+// the bars T shows over the player, one for each of the coyote window, the jump buffer and the
+// dash cooldown that has time left, as (start, end, color)
+fn timer_bars(aabb: &Aabb2d, v: &Movement, feel: &GameFeel) -> Vec<(Vec2, Vec2, Color)> {
+    let width = aabb.half_size().x * 2.;
+    // (left, whole window), windows that are infinite or off have nothing to show
+    let coyote = (!v.grounded).then_some(feel.coyote - v.air_time);
+    let timers = [
+        (coyote.unwrap_or(0.), feel.coyote, Color::YELLOW),
+        (v.jump_buffer, feel.jump_buffer, Color::CYAN),
+        (v.dash_cooldown, feel.dash_cooldown, Color::FUCHSIA),
+    ];
+    let active = (timers.into_iter())
+        .filter(|(left, whole, _)| whole.is_finite() && *whole > 0. && *left > 0.);
+    // stacked up from the top of the player, each shrinking to its left end
+    (active.enumerate())
+        .map(|(i, (left, whole, color))| {
+            let start = Vec2::new(aabb.min.x, aabb.max.y + 6. + 5. * i as f32);
+            let len = width * (left / whole).min(1.);
+            (start, start + Vec2::X * len, color)
+        })
+        .collect()
+}
+
+// the i'th of len collisions, bright red for the first fading to half as bright for the last
+pub fn collision_color(i: usize, len: usize) -> Color {
+    let along = if len > 1 {
+        (i as f32 / (len - 1) as f32).clamp(0., 1.)
+    } else {
+        0.
+    };
+    Color::rgb(1. - 0.5 * along, 0., 0.)
+}
+
+// the grid lines crossing the view, as (start, end)
+// tiles are centered Tile::SZ apart from the map origin, so lines sit halfway between them
+fn grid_lines(origin: Vec2, view_min: Vec2, view_max: Vec2) -> Vec<(Vec2, Vec2)> {
+    let first = (((view_min - origin) / Tile::SZ) - 0.5).ceil();
+    let last = (((view_max - origin) / Tile::SZ) - 0.5).floor();
+    let xs = ((first.x as i32)..=(last.x as i32)).map(|x| {
+        let x = origin.x + (x as f32 + 0.5) * Tile::SZ;
+        (Vec2::new(x, view_min.y), Vec2::new(x, view_max.y))
+    });
+    let ys = ((first.y as i32)..=(last.y as i32)).map(|y| {
+        let y = origin.y + (y as f32 + 0.5) * Tile::SZ;
+        (Vec2::new(view_min.x, y), Vec2::new(view_max.x, y))
+    });
+    xs.chain(ys).collect()
+}
+
+pub fn debug_draw(
+    mut gizmos: Gizmos,
+    mut dbg: Query<(&mut Text, &mut DebugUi)>,
+    cam: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    level: Res<Level>,
+    ctl: Query<(&Transform, &Movement), With<Control>>,
+    feel: Res<GameFeel>,
+) {
+    let (mut txt, mut dbg) = dbg.single_mut();
+    if let Ok((cam, proj)) = cam.get_single() {
+        // tile grid across the visible part of the world
+        let view_min = cam.translation.xy() + proj.area.min * cam.scale.xy();
+        let view_max = cam.translation.xy() + proj.area.max * cam.scale.xy();
+        for (a, b) in grid_lines(level.origin, view_min, view_max) {
+            gizmos.line_2d(a, b, Color::rgba(1., 1., 1., 0.1));
+        }
+
+        // each graph scaled to fit its own range
+        let size = GRAPH_SIZE * cam.scale.xy();
+        for (i, (_, buf)) in dbg.graphs.iter().enumerate() {
+            let min = graph_corner(view_min, view_max, cam.scale.xy(), i);
+            gizmos.rect_2d(min + size / 2., 0., size, Color::rgba(1., 1., 1., 0.2));
+            let (lo, hi) = (buf.iter()).fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(*v), hi.max(*v))
+            });
+            let range = if hi > lo { hi - lo } else { 1. };
+            gizmos.linestrip_2d(
+                buf.iter().enumerate().map(|(x, v)| {
+                    min + size * Vec2::new(x as f32 / (GRAPH_LEN - 1) as f32, (v - lo) / range)
+                }),
+                Color::YELLOW,
+            );
+        }
+    }
+    if let Some(sections) = dbg.take_sections() {
+        txt.sections = sections;
+    }
     if !dbg.collisions.is_empty() {
-        let n = {
-            let n = dbg.collisions.len() - 1;
-            if n == 0 {
-                1.
-            } else {
-                0.5 / n as f32
-            }
-        };
+        let len = dbg.collisions.len();
         for (i, (col, aabb)) in dbg.collisions.iter().enumerate() {
-            let color = Color::rgb(1. - (i as f32 * n), 0., 0.);
-            match *col {
-                Tile::SQUARE => {
-                    gizmos.rect_2d(aabb.center(), 0., aabb.max - aabb.min, color);
-                }
-                Tile::STEPL | Tile::SLOPEL => {
-                    gizmos.linestrip_2d(
-                        [
-                            aabb.min,
-                            Vec2::new(aabb.max.x, aabb.min.y),
-                            Vec2::new(aabb.min.x, aabb.max.y),
-                            aabb.min,
-                        ],
-                        color,
-                    );
-                }
-                Tile::STEPR | Tile::SLOPER => {
-                    gizmos.linestrip_2d(
-                        [
-                            aabb.min,
-                            Vec2::new(aabb.max.x, aabb.min.y),
-                            aabb.max,
-                            aabb.min,
-                        ],
-                        color,
-                    );
-                }
-                _ => unreachable!(),
+            let color = collision_color(i, len);
+            let shape = collider_shape(*col, aabb);
+            if dbg.fill_colliders {
+                fill_convex(&mut gizmos, &shape, color.with_a(0.4), 2.);
             }
+            gizmos.linestrip_2d(shape.iter().chain(shape.first()).copied(), color);
         }
-        if let Some(aabb) = &dbg.ctl_aabb {
-            gizmos.rect_2d(aabb.center(), 0., aabb.half_size() * 2., Color::GREEN);
+    }
+    // check_collide skips standing still and the editor can move the player, so the box it left
+    // behind can be stale. always outline where the hitbox is right now
+    for (t, _) in &ctl {
+        let outline = hitbox_outline(t);
+        gizmos.rect_2d(
+            outline.center(),
+            0.,
+            outline.size(),
+            Color::rgba(1., 1., 1., 0.3),
+        );
+    }
+    for (t, v) in ctl.iter().filter(|_| dbg.show_timers) {
+        for (start, end, color) in timer_bars(&ctl_aabb(t), v, &feel) {
+            gizmos.line_2d(start, end, color);
         }
     }
-    let cursor = (dbg.cursor / Tile::SZ).round() * Tile::SZ;
+    if let Some(aabb) = &dbg.ctl_aabb {
+        gizmos.rect_2d(aabb.center(), 0., aabb.half_size() * 2., dbg.ctl_color);
+    }
+    if let Some((start, end)) = dbg.sweep.filter(|_| dbg.show_sweep) {
+        let color = Color::rgba(0., 1., 1., 0.6);
+        for aabb in [start, end] {
+            gizmos.rect_2d(aabb.center(), 0., aabb.half_size() * 2., color);
+        }
+        for (a, b) in sweep_edges(&start, &end) {
+            gizmos.line_2d(a, b, color);
+        }
+    }
+    let cursor = level.snap(dbg.cursor);
     gizmos.rect_2d(cursor, 0., Vec2::new(Tile::SZ, Tile::SZ), Color::GREEN);
 }
 
-pub fn save_map(tiles: Query<(&Transform, &Tile)>) {
-    let mut data: Vec<_> = tiles
-        .iter()
-        .map(|(t, s)| (t.translation.xy(), *s))
-        .collect();
-    data.sort_by(|(t1, _), (t2, _)| match t1.y.total_cmp(&t2.y) {
-        std::cmp::Ordering::Equal => t1.x.total_cmp(&t2.x),
-        c => c,
+// corners of the part of a tile that collides, counter-clockwise
+fn collider_shape(col: Tile, aabb: &Aabb2d) -> Vec<Vec2> {
+    let (min, max) = (aabb.min, aabb.max);
+    match col {
+        Tile::STEPL | Tile::SLOPEL => vec![min, Vec2::new(max.x, min.y), Vec2::new(min.x, max.y)],
+        Tile::STEPR | Tile::SLOPER => vec![min, Vec2::new(max.x, min.y), max],
+        _ => vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)],
+    }
+}
+
+// gizmos only draw lines, so a shape is filled with horizontal lines `spacing` apart
+fn fill_convex(gizmos: &mut Gizmos, pts: &[Vec2], color: Color, spacing: f32) {
+    for (l, r) in fill_lines(pts, spacing) {
+        gizmos.line_2d(l, r, color);
+    }
+}
+
+// the horizontal lines `spacing` apart that fill the convex shape pts, each from its left end
+fn fill_lines(pts: &[Vec2], spacing: f32) -> Vec<(Vec2, Vec2)> {
+    let (lo, hi) = (pts.iter()).fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| {
+        (lo.min(p.y), hi.max(p.y))
     });
-    let mut min = data[0].0;
-    let mut max = data[data.len() - 1].0;
-    for (d, _) in &data {
-        if d.x < min.x {
-            min.x = d.x;
-        }
-        if d.x > max.x {
-            max.x = d.x;
+    let mut lines = vec![];
+    let mut y = lo + spacing / 2.;
+    while y < hi {
+        // where the line crosses the edges, a convex shape has at most two crossings
+        let xs: Vec<f32> = (0..pts.len())
+            .map(|i| (pts[i], pts[(i + 1) % pts.len()]))
+            .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+            .map(|(a, b)| a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x))
+            .collect();
+        if let (Some(l), Some(r)) = (
+            xs.iter().copied().reduce(f32::min),
+            xs.iter().copied().reduce(f32::max),
+        ) {
+            lines.push((Vec2::new(l, y), Vec2::new(r, y)));
         }
+        y += spacing;
     }
+    lines
+}
 
-    let width = ((max.x - min.x) / Tile::SZ) as usize + 1;
-    let height = ((max.y - min.y) / Tile::SZ) as usize + 1;
-    println!("const MAP: (Vec2, usize, [u8; {width} * {height}]) = (");
-    println!("  Vec2::new({:?}, {:?}),", min.x.floor(), min.y.floor());
-    println!("  {width},");
-    println!("  [");
+// the map as it stands in the editor, cropped to its tiles, keeping level's name, palette and
+// the rest. each tile comes with whether it's decor and whether it's composite. None when there
+// are no tiles
+pub fn snapshot_level<'a>(
+    tiles: impl IntoIterator<Item = (&'a Transform, &'a Tile, bool, bool)>,
+    level: &Level,
+) -> Option<Level> {
+    // snapped to the level's grid first, so float error in a tile's position can't put it (or the
+    // origin) a row or column off
+    let tiles: Vec<_> = (tiles.into_iter())
+        .map(|(t, s, decor, composite)| (level.snap(t.translation.xy()), *s, decor, composite))
+        .collect();
+    let decor = (tiles.iter()).filter(|t| t.2).map(|t| t.0).collect();
+    let composite = (tiles.iter()).filter(|t| t.3).map(|t| t.0).collect();
+    let data: Vec<_> = tiles.into_iter().map(|(pos, s, ..)| (pos, s)).collect();
+    let first = data.first()?.0;
+    let (min, max) = (data.iter()).fold((first, first), |(min, max), (p, _)| {
+        (min.min(*p), max.max(*p))
+    });
+
+    let size = world_to_tile(min, Tile::SZ, max) + 1;
+    let (width, height) = (size.x as usize, size.y as usize);
     let mut map = vec![vec![0u8; width]; height];
     for (trans, tile) in data {
-        let trans = (trans - min) / Tile::SZ;
-        map[trans.y as usize][trans.x as usize] = tile.0;
+        let cell = world_to_tile(min, Tile::SZ, trans);
+        map[cell.y as usize][cell.x as usize] = tile.0;
+    }
+    let labels = (0..height)
+        .filter_map(|row| {
+            let label = level.label_at(max.y - row as f32 * Tile::SZ)?;
+            Some((row, label.to_string()))
+        })
+        .collect();
+    let ids = map.iter().rev().flatten().copied().collect();
+    Some(Level {
+        name: level.name.clone(),
+        labels,
+        palette: level.palette,
+        texture: level.texture.clone(),
+        background: level.background,
+        winds: level.winds.clone(),
+        cameras: level.cameras.clone(),
+        platforms: level.platforms.clone(),
+        decor,
+        composite,
+        ..Level::from_grid(min, width, ids).unwrap()
+    })
+}
+
+// the map as the const MAP source save_map prints, with the extra bits Level::parse reads out of
+// its comments first
+fn map_source(saved: &Level) -> String {
+    let (width, height) = (saved.width, saved.height());
+    let min = saved.origin;
+    let max_y = min.y + (height - 1) as f32 * Tile::SZ;
+    let mut out = String::new();
+    if let Some(name) = &saved.name {
+        out += &format!("// name: {name}\n");
+    }
+    if let Some(texture) = &saved.texture {
+        out += &format!("// texture: {texture}\n");
+    }
+    if let Some(line) = saved.background_line() {
+        out += &format!("{line}\n");
     }
-    for (y, row) in map.iter().rev().enumerate() {
-        print!("    ");
+    for line in (saved.palette_lines())
+        .chain(saved.zone_lines())
+        .chain(saved.tile_lines())
+    {
+        out += &format!("{line}\n");
+    }
+    out += &format!("const MAP: (Vec2, usize, [u8; {width} * {height}]) = (\n");
+    out += &format!("  Vec2::new({:?}, {:?}),\n", min.x, min.y);
+    out += &format!("  {width},\n");
+    out += "  [\n";
+    // top row first
+    for (y, row) in saved.data.chunks(width).enumerate() {
+        out += "    ";
         for t in row {
-            print!("{t}, ");
+            out += &format!("{t}, ");
         }
-        println!(" // {y}");
+        out += &match saved.label_at(max_y - y as f32 * Tile::SZ) {
+            Some(label) => format!(" // {y} {label}\n"),
+            None => format!(" // {y}\n"),
+        };
     }
-    println!("  ],");
-    println!(");");
+    out += "  ],\n";
+    out += ");\n";
+    out
+}
+
+// the map as a 24 bit bmp with a pixel per tile, in BMP_PALETTE's colors
+fn map_bmp(saved: &Level) -> Vec<u8> {
+    let (width, height) = (saved.width, saved.height());
+    // rows from the bottom up
+    let map: Vec<_> = saved.data.chunks(width).rev().collect();
 
     const BMP_SZ: usize = 0x02;
     const BMP_PX_W: usize = 0x12;
@@ -918,17 +3007,11 @@ pub fn save_map(tiles: Query<(&Transform, &Tile)>) {
         0x00, 0x00, 0x00, 0x00, // (unused)
               // pixel array/bitmap data
     ];
-    for row in map.iter() {
+    for &row in &map {
         for x in row {
-            match x {
-                0 => bmp_buf.extend([0x00, 0x00, 0x00]), // black  - empty
-                1 => bmp_buf.extend([0xff, 0xff, 0xff]), // white  - Square
-                2 => bmp_buf.extend([0x00, 0x00, 0xff]), // red    - StepR
-                3 => bmp_buf.extend([0xff, 0x00, 0x00]), // blue   - StepL
-                4 => bmp_buf.extend([0x00, 0x88, 0xff]), // orange - SlopeR
-                5 => bmp_buf.extend([0x00, 0xff, 0x00]), // green  - SlopeL
-                _ => unimplemented!(),
-            }
+            let [r, g, b] = bmp_color(*x);
+            // bmp pixels are blue first
+            bmp_buf.extend([b, g, r]);
         }
         let pad = (row.len() * 3) % 4;
         if pad != 0 {
@@ -940,12 +3023,9 @@ pub fn save_map(tiles: Query<(&Transform, &Tile)>) {
     }
     let data_sz = bmp_buf.len() - BMP_START_DATA;
     let file_sz = bmp_buf.len();
-    let px_w = if (map[0].len() % 4) != 0 {
-        map[0].len() + 4 - (map[0].len() % 4)
-    } else {
-        map[0].len()
-    };
-    let px_h = map.len();
+    // the row padding is bytes, not pixels, so the width is the real pixel count
+    let px_w = width;
+    let px_h = height;
 
     use std::io::Write as _;
     for (off, val) in [
@@ -958,5 +3038,1139 @@ pub fn save_map(tiles: Query<(&Transform, &Tile)>) {
             .write(&(val as u32).to_le_bytes())
             .unwrap();
     }
-    std::fs::write("./map.bmp", bmp_buf).unwrap();
+    bmp_buf
+}
+
+pub fn save_map(
+    tiles: Query<(&Transform, &Tile, Has<Decor>, Has<Composite>)>,
+    level: &Level,
+    dir: &Path,
+) {
+    let Some(saved) = snapshot_level(&tiles, level) else {
+        eprintln!("no tiles, nothing to save");
+        return;
+    };
+    // a summary to sanity check before overwriting a map file, on stderr so stdout stays pastable
+    eprint!("{}", map_summary(&saved));
+
+    print!("{}", map_source(&saved));
+
+    // this runs on the way out, so a file that can't be written is only worth a mention
+    let write = |name: &str, contents: &[u8]| {
+        let path = dir.join(name);
+        if let Err(e) = std::fs::write(&path, contents) {
+            eprintln!("can't save {path:?}: {e}");
+        }
+    };
+    write("map.bmp", &map_bmp(&saved));
+
+    // the same map in the text format, which diffs a lot better than the array
+    write("map.map", saved.to_text().as_bytes());
+    // and as a picture of the colliders for docs, the bmp only has a pixel per tile
+    write("map.svg", map_svg(&saved).as_bytes());
+}
+
+// the map's size, how many of each kind of tile it has, and whether the player spawns in it
+fn map_summary(level: &Level) -> String {
+    let (width, height) = (level.width, level.height());
+    let min = level.origin;
+    let max = min + Vec2::new((width - 1) as f32, (height - 1) as f32) * Tile::SZ;
+    let mut counts = [0usize; Tile::NUM + 1];
+    for &id in level.data.iter().filter(|&&id| id != 0) {
+        counts[(id as usize).min(Tile::NUM)] += 1;
+    }
+    let total: usize = counts.iter().sum();
+    let mut out = format!("{width}x{height} map, {total} tiles\n");
+    for (id, n) in counts.iter().enumerate().filter(|(_, n)| **n > 0) {
+        out += &format!("  {:>7}: {n}\n", Tile(id as u8).kind());
+    }
+    let half = Vec2::splat(Tile::SZ / 2.);
+    let bounds = Rect::from_corners(min - half, max + half);
+    if !bounds.contains(PlayerBundle::SPAWN) {
+        out += &format!("player spawn {} is outside the map\n", PlayerBundle::SPAWN);
+    }
+    out
+}
+
+// every tile as the shape it collides as, in its palette color at its world coordinates (svg y
+// points down, so y is negated)
+fn map_svg(level: &Level) -> String {
+    let half = Vec2::splat(Tile::SZ / 2.);
+    let min = level.origin - half;
+    let size = Vec2::new(level.width as f32, level.height() as f32) * Tile::SZ;
+    let top = min.y + size.y;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min.x, -top, size.x, size.y
+    );
+    for (tile, pos) in level.tiles() {
+        let [r, g, b, _] = level.tile_color(tile).as_rgba_u8();
+        let fill = format!("#{r:02x}{g:02x}{b:02x}");
+        let aabb = Aabb2d::new(pos, half);
+        let shape = collider_shape(tile, &aabb);
+        if shape.len() == 4 {
+            svg += &format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{fill}\"/>\n",
+                aabb.min.x,
+                -aabb.max.y,
+                Tile::SZ,
+                Tile::SZ
+            );
+        } else {
+            let points: Vec<_> = (shape.iter())
+                .map(|p| format!("{},{}", p.x, -p.y))
+                .collect();
+            svg += &format!(
+                "  <polygon points=\"{}\" fill=\"{fill}\"/>\n",
+                points.join(" ")
+            );
+        }
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_repeats_from_a_seed() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        let picks: Vec<_> = (0..1000).map(|_| a.range(0.9, 1.1)).collect();
+        assert!(picks.iter().all(|p| (0.9..1.1).contains(p)));
+        assert!(picks.iter().any(|p| *p < 0.95) && picks.iter().any(|p| *p > 1.05));
+        assert_ne!(Rng::new(0).next_u64(), 0);
+    }
+
+    // the level's tiles as the editor has them, each its own entity
+    fn spawned(level: &Level) -> Vec<(Transform, Tile)> {
+        (level.tiles())
+            .map(|(tile, pos)| (Transform::from_translation(pos.extend(0.)), tile))
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_keeps_zones() {
+        let text = "wind: 0 0 100 100 1 0
+camera: 0 0 100 100 0 0 2
+platform: 0 100 100 20 200 0 3
+map -50 -50 3
+...
+.#.
+###
+";
+        let level = Level::parse_text(text).unwrap();
+        let tiles = spawned(&level);
+        let saved =
+            snapshot_level(tiles.iter().map(|(t, c)| (t, c, false, false)), &level).unwrap();
+        assert_eq!(saved.platforms, level.platforms);
+        assert_eq!(saved.winds, level.winds);
+        assert_eq!(saved.cameras, level.cameras);
+        let reread = Level::parse_text(&saved.to_text()).unwrap();
+        assert_eq!(reread.platforms, level.platforms);
+    }
+
+    #[test]
+    fn map_version_follows_tile_edits() {
+        let mut app = App::new();
+        app.init_resource::<MapVersion>()
+            .add_systems(Update, watch_tiles);
+        let tiles: Vec<_> = (spawned(&Level::builtin()).into_iter().take(3))
+            .map(|tile| app.world.spawn(tile).id())
+            .collect();
+        let mut last = 0;
+        // each edit bumps the version once, and nothing bumps it again
+        let mut bumped = |app: &mut App, what: &str| {
+            app.update();
+            let now = app.world.resource::<MapVersion>().0;
+            assert!(now > last, "{what}");
+            app.update();
+            assert_eq!(
+                app.world.resource::<MapVersion>().0,
+                now,
+                "{what} counted twice"
+            );
+            last = now;
+        };
+        bumped(&mut app, "spawned");
+        let mut tile = app.world.get_mut::<Transform>(tiles[0]).unwrap();
+        tile.translation.x += 25.;
+        bumped(&mut app, "moved");
+        app.world.entity_mut(tiles[1]).insert(Decor);
+        bumped(&mut app, "made decor");
+        app.world.entity_mut(tiles[1]).remove::<Decor>();
+        bumped(&mut app, "made solid");
+        app.world.despawn(tiles[2]);
+        bumped(&mut app, "despawned");
+    }
+
+    #[test]
+    fn graph_keeps_the_last_values() {
+        let mut dbg = DebugUi::default();
+        for v in 0..GRAPH_LEN + 30 {
+            dbg.graph("force.y", v as f32);
+            dbg.graph("other", 0.);
+        }
+        let (key, buf) = &dbg.graphs[0];
+        assert_eq!(*key, "force.y");
+        assert_eq!(buf.len(), GRAPH_LEN);
+        assert_eq!(buf.front(), Some(&30.));
+        assert_eq!(buf.back(), Some(&((GRAPH_LEN + 29) as f32)));
+        assert_eq!(dbg.graphs.len(), 2);
+    }
+
+    #[test]
+    fn graphs_stay_clear_of_the_palette_strip() {
+        let (min, max) = (Vec2::new(-640., -360.), Vec2::new(640., 360.));
+        for scale in [Vec2::ONE, Vec2::splat(2.)] {
+            let first = graph_corner(min * scale, max * scale, scale, 0);
+            // the strip is well under half the view wide
+            assert!(first.x > 0., "{first}");
+            assert!(first.x + GRAPH_SIZE.x * scale.x < max.x * scale.x);
+            assert!(first.y > min.y * scale.y);
+            let second = graph_corner(min * scale, max * scale, scale, 1);
+            assert!(second.y >= first.y + GRAPH_SIZE.y * scale.y);
+        }
+    }
+
+    #[test]
+    fn is_solid_answers_for_each_part_of_a_tile() {
+        let mut world = World::new();
+        let at = |x: f32| Transform {
+            translation: Vec3::new(x, 0., 0.),
+            scale: Vec3::new(Tile::SZ, Tile::SZ, 1.),
+            ..default()
+        };
+        world.spawn((Tile::SQUARE, at(0.)));
+        world.spawn((Tile::SLOPER, at(50.)));
+        world.spawn((Tile::SQUARE, at(100.), Decor));
+        let mut state = bevy::ecs::system::SystemState::<Tiles>::new(&mut world);
+        let tiles = state.get(&world);
+        assert_eq!(tiles.is_solid(Vec2::new(10., 10.)), Some(Tile::SQUARE));
+        // the slope's filled bottom right, and its empty top left
+        assert_eq!(tiles.is_solid(Vec2::new(65., -15.)), Some(Tile::SLOPER));
+        assert_eq!(tiles.is_solid(Vec2::new(35., 15.)), None);
+        // decoration and empty space
+        assert_eq!(tiles.is_solid(Vec2::new(100., 0.)), None);
+        assert_eq!(tiles.is_solid(Vec2::new(0., 100.)), None);
+    }
+
+    #[test]
+    fn zoom_stops_at_its_bounds() {
+        let mut cam = Transform::default();
+        for _ in 0..100 {
+            zoom_about(&mut cam, Vec2::ZERO, 1.1);
+        }
+        assert_eq!(cam.scale, Vec3::new(ZOOM_MAX, ZOOM_MAX, 1.));
+        for zoom in [1. / 1.1, 0., -2.] {
+            for _ in 0..100 {
+                zoom_about(&mut cam, Vec2::ZERO, zoom);
+            }
+            assert_eq!(cam.scale, Vec3::new(ZOOM_MIN, ZOOM_MIN, 1.), "zoom {zoom}");
+        }
+    }
+
+    // an editor camera at pos, easing its zoom towards target about anchor, 1/60s a frame
+    fn zoom_app(pos: Vec2, target: f32, anchor: Vec2) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_secs_f64(1. / 60.),
+            ))
+            .insert_resource(ZoomEase(Some((target, anchor))))
+            .add_systems(Update, debug_ease_zoom);
+        let cam = (app.world)
+            .spawn((
+                Transform::from_translation(pos.extend(0.)),
+                Camera::default(),
+                MainCamera,
+            ))
+            .id();
+        // the first frame has no time passing
+        app.update();
+        (app, cam)
+    }
+
+    #[test]
+    fn zoom_keeps_the_point_under_the_cursor() {
+        let anchor = Vec2::new(300., 200.);
+        let (mut app, cam) = zoom_app(Vec2::new(100., 50.), 2., anchor);
+        // where anchor is on screen, in pixels from the center
+        let on_screen = |app: &App| {
+            let t = app.world.get::<Transform>(cam).unwrap();
+            (anchor - t.translation.xy()) / t.scale.x
+        };
+        for _ in 0..60 {
+            app.update();
+            assert!((on_screen(&app) - Vec2::new(200., 150.)).length() < 1e-3);
+        }
+        assert_eq!(app.world.get::<Transform>(cam).unwrap().scale.x, 2.);
+    }
+
+    #[test]
+    fn zoom_eases_in_over_several_frames() {
+        let (mut app, cam) = zoom_app(Vec2::ZERO, 2., Vec2::ZERO);
+        let mut scales = vec![];
+        for _ in 0..60 {
+            app.update();
+            scales.push(app.world.get::<Transform>(cam).unwrap().scale.x);
+        }
+        // part of the way on the first frame, a smaller part each frame after
+        assert!(scales[0] > 1. && scales[0] < 1.5, "{scales:?}");
+        let steps: Vec<_> = scales.windows(2).map(|w| w[1] - w[0]).collect();
+        let settled = scales
+            .iter()
+            .position(|s| *s == 2.)
+            .expect("never got there");
+        assert!(settled > 5, "{scales:?}");
+        assert!(
+            steps[..settled - 1].windows(2).all(|w| w[1] < w[0]),
+            "{scales:?}"
+        );
+        assert!(scales[settled..].iter().all(|s| *s == 2.));
+        assert_eq!(app.world.resource::<ZoomEase>().0, None);
+    }
+
+    #[test]
+    fn grid_has_a_line_per_tile_across_the_view() {
+        let origin = Vec2::new(-200., -400.);
+        // 640x360 is 12.8 by 7.2 tiles, so 12 or 13 and 7 or 8 lines depending on where it starts
+        for start in [
+            Vec2::new(-320., -180.),
+            Vec2::new(-301., -157.),
+            Vec2::new(13., 7.),
+        ] {
+            let lines = grid_lines(origin, start, start + Vec2::new(640., 360.));
+            let (vertical, horizontal): (Vec<_>, Vec<_>) =
+                lines.iter().partition(|(a, b)| a.x == b.x);
+            assert!(
+                (12..=13).contains(&vertical.len()),
+                "{} at {start}",
+                vertical.len()
+            );
+            assert!(
+                (7..=8).contains(&horizontal.len()),
+                "{} at {start}",
+                horizontal.len()
+            );
+            // halfway between tile centers
+            for (a, _) in vertical {
+                assert_eq!((a.x - origin.x).rem_euclid(Tile::SZ), Tile::SZ / 2.);
+            }
+        }
+        let exact = grid_lines(Vec2::ZERO, Vec2::splat(-25.), Vec2::splat(75.));
+        assert_eq!(exact.len(), 6);
+    }
+
+    #[test]
+    fn climbing_turns_the_hitbox_on_its_side() {
+        let mut app = App::new();
+        app.insert_resource(MapBounds(Rect::new(-1000., -1000., 1000., 1000.)))
+            .init_resource::<PhysicsScale>()
+            .add_systems(Update, update_movement);
+        let player = (app.world)
+            .spawn((
+                Transform::from_scale(Vec3::new(45., 90., 1.)),
+                Movement::default(),
+                Facing::default(),
+            ))
+            .id();
+        let half_size =
+            |app: &App| ctl_aabb(app.world.get::<Transform>(player).unwrap()).half_size();
+        app.update();
+        assert_eq!(half_size(&app), Vec2::new(22.5, 45.));
+        app.world.get_mut::<Movement>(player).unwrap().climb = true;
+        app.update();
+        assert!((half_size(&app) - Vec2::new(45., 22.5)).abs().max_element() < 1e-3);
+        app.world.get_mut::<Movement>(player).unwrap().climb = false;
+        app.update();
+        assert_eq!(half_size(&app), Vec2::new(22.5, 45.));
+    }
+
+    // pan_camera alone following with follow, 1/60s a frame, and the camera it moves
+    fn follow_app(follow: CameraFollow) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_secs_f64(1. / 60.),
+            ))
+            .insert_resource(follow)
+            .insert_resource(MapBounds(Rect::new(-1e4, -1e4, 1e4, 1e4)))
+            .insert_resource(End(false))
+            .init_resource::<CameraCue>()
+            .add_systems(Update, pan_camera);
+        let cam = (app.world)
+            .spawn((
+                Transform::default(),
+                OrthographicProjection::default(),
+                Camera::default(),
+            ))
+            .id();
+        (app, cam)
+    }
+
+    #[test]
+    fn camera_follows_sooner_on_the_tighter_axis() {
+        let (mut app, cam) = follow_app(CameraFollow {
+            deadzone: Vec2::new(200., 50.),
+            gain: Vec2::ONE,
+            settle_on_ground: false,
+            ..default()
+        });
+        let player = (app.world)
+            .spawn((Transform::default(), Movement::default(), Control))
+            .id();
+        app.update();
+        let move_to = |app: &mut App, x: f32, y: f32| {
+            app.world.get_mut::<Transform>(player).unwrap().translation = Vec3::new(x, y, 0.);
+            app.update();
+            app.world.get::<Transform>(cam).unwrap().translation.xy()
+        };
+        // as far off on both axes, but only out of the deadzone vertically
+        assert_eq!(move_to(&mut app, 150., 150.), Vec2::new(0., 100.));
+        assert_eq!(move_to(&mut app, 150., -150.), Vec2::new(0., -100.));
+        // far enough to the side follows too
+        assert_eq!(move_to(&mut app, 250., -150.), Vec2::new(50., -100.));
+        // and the offset moves where the deadzone sits, here the player is kept below the middle
+        app.world.resource_mut::<CameraFollow>().offset = Vec2::new(0., 100.);
+        assert_eq!(move_to(&mut app, 250., -150.), Vec2::new(50., -100.));
+        assert_eq!(move_to(&mut app, 250., -300.), Vec2::new(50., -150.));
+    }
+
+    #[test]
+    fn watches_keep_their_order_across_frames() {
+        let mut dbg = DebugUi::default();
+        let lines = |dbg: &mut DebugUi| {
+            let sections = dbg.take_sections()?;
+            Some(sections.into_iter().map(|s| s.value).collect::<Vec<_>>())
+        };
+        for key in ["vel", "pos", "grounded", "camera", "tiles"] {
+            dbg.watch(key, 0);
+        }
+        let first = lines(&mut dbg).unwrap();
+        assert_eq!(first[..2], ["vel: 0\n", "pos: 0\n"]);
+        for frame in 1..20 {
+            // watched in a different order each frame, with only some values changing
+            for key in ["tiles", "camera", "pos", "grounded", "vel"]
+                .iter()
+                .cycle()
+                .skip(frame)
+                .take(5)
+            {
+                dbg.watch(key, if *key == "pos" { frame } else { 0 });
+            }
+            let got = lines(&mut dbg).expect("pos changed");
+            let keys: Vec<_> = got.iter().map(|l| l.split(':').next().unwrap()).collect();
+            assert_eq!(keys, ["vel", "pos", "grounded", "camera", "tiles"]);
+            assert_eq!(got[1], format!("pos: {frame}\n"));
+        }
+        // nothing changed, nothing to rebuild
+        dbg.watch("vel", 0);
+        assert_eq!(lines(&mut dbg), None);
+    }
+
+    #[test]
+    fn player_box_is_tinted_by_movement_state() {
+        let state = |grounded, wall_dir| {
+            movement_color(&Movement {
+                grounded,
+                wall_dir,
+                ..default()
+            })
+        };
+        assert_eq!(state(true, 0.), Color::GREEN);
+        assert_eq!(state(false, 0.), Color::YELLOW);
+        assert_eq!(state(false, -1.), Color::ORANGE);
+        assert_eq!(state(false, 1.), Color::ORANGE);
+        // standing in a corner counts as on the ground
+        assert_eq!(state(true, 1.), Color::GREEN);
+    }
+
+    #[test]
+    fn saved_source_parses_back() {
+        // a hazard on a block between two slopes, on a floor
+        let data = vec![0, 0, 6, 0, 0, 4, 1, 5, 1, 1, 1, 1];
+        let mut level = Level::from_grid(Vec2::new(-100., -50.), 4, data).unwrap();
+        level.name = Some("test room".into());
+        level.labels = vec![(0, "top".into()), (2, "floor".into())];
+        level.decor = vec![Vec2::new(0., 50.)];
+        let src = map_source(&level);
+        assert!(src.contains("    0, 0, 6, 0,  // 0 top\n"), "{src}");
+        let parsed = Level::parse(&src).unwrap();
+        assert_eq!(parsed.origin, level.origin);
+        assert_eq!(parsed.width, level.width);
+        assert_eq!(parsed.data, level.data);
+        assert_eq!(parsed.name, level.name);
+        assert_eq!(parsed.labels, level.labels);
+        assert_eq!(parsed.decor, level.decor);
+        // and it's a fixed point
+        assert_eq!(map_source(&parsed), src);
+    }
+
+    #[test]
+    fn summary_counts_each_kind_of_tile() {
+        let data = vec![0, 0, 6, 0, 0, 4, 1, 5, 1, 1, 1, 1];
+        let level = Level::from_grid(Vec2::new(-100., -50.), 4, data.clone()).unwrap();
+        assert_eq!(
+            map_summary(&level),
+            "4x3 map, 8 tiles\n   square: 5\n   sloper: 1\n   slopel: 1\n   hazard: 1\n"
+        );
+        // the same map moved off to the side of the spawn
+        let level = Level::from_grid(Vec2::new(500., -50.), 4, data).unwrap();
+        let summary = map_summary(&level);
+        assert!(summary.ends_with("is outside the map\n"), "{summary}");
+    }
+
+    #[test]
+    fn filled_colliders_cover_just_the_collider() {
+        let aabb = Aabb2d::new(Vec2::ZERO, Vec2::splat(Tile::SZ / 2.));
+        // where a line at height y across the tile's solid part starts and ends, from the bottom
+        // left corner
+        let square = |_: f32| (0., Tile::SZ);
+        let right = |y: f32| (y, Tile::SZ);
+        let left = |y: f32| (0., Tile::SZ - y);
+        let spans: [(Tile, &dyn Fn(f32) -> (f32, f32)); 5] = [
+            (Tile::SQUARE, &square),
+            (Tile::SLOPER, &right),
+            (Tile::STEPR, &right),
+            (Tile::SLOPEL, &left),
+            (Tile::STEPL, &left),
+        ];
+        for (tile, span) in spans {
+            let lines = fill_lines(&collider_shape(tile, &aabb), 2.);
+            assert_eq!(lines.len(), 25, "{tile:?}");
+            for (l, r) in lines {
+                let y = l.y - aabb.min.y;
+                let (x0, x1) = span(y);
+                assert_eq!(r.y, l.y);
+                assert!((l.x - aabb.min.x - x0).abs() < 1e-3, "{tile:?} {l} at {y}");
+                assert!((r.x - aabb.min.x - x1).abs() < 1e-3, "{tile:?} {r} at {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_sweep_joins_the_corners_of_this_frames_move() {
+        let start = Aabb2d::new(Vec2::new(0., 0.), Vec2::new(10., 20.));
+        let end = Aabb2d::new(Vec2::new(30., -5.), Vec2::new(10., 20.));
+        assert_eq!(
+            sweep_edges(&start, &end),
+            [
+                (Vec2::new(-10., -20.), Vec2::new(20., -25.)),
+                (Vec2::new(10., -20.), Vec2::new(40., -25.)),
+                (Vec2::new(10., 20.), Vec2::new(40., 15.)),
+                (Vec2::new(-10., 20.), Vec2::new(20., 15.)),
+            ]
+        );
+        // in the game it's the box the player moved from to the box it's in now
+        let mut app = crate::tests::landed(crate::tests::open_level(), GameFeel::CLASSIC);
+        let before = app
+            .world
+            .query_filtered::<&Transform, With<Control>>()
+            .single(&app.world);
+        let before = ctl_aabb(before);
+        let right = bevy::input::ButtonState::Pressed;
+        crate::tests::send_key(&mut app, KeyCode::ArrowRight, right);
+        app.update();
+        let after = app
+            .world
+            .query_filtered::<&Transform, With<Control>>()
+            .single(&app.world);
+        let after = ctl_aabb(after);
+        assert!(
+            after.min.x > before.min.x,
+            "{} to {}",
+            before.min,
+            after.min
+        );
+        let dbg = app.world.query::<&DebugUi>().single(&app.world);
+        let (start, end) = dbg.sweep.unwrap();
+        assert_eq!((start.min, end.max), (before.min, after.max));
+    }
+
+    #[test]
+    fn players_rest_on_both_parts_of_a_composite() {
+        use crate::tests::{game, open_level, player_mut};
+        // a ramped tile on open_level's floor, under the spawn
+        let mut level = open_level();
+        let len = level.data.len();
+        level.data[len - 22 + 5] = Tile::SQUARE.0;
+        let pos = Vec2::new(0., -450.);
+        level.composite.push(pos);
+        let mut app = game(level);
+        // the slope rises a half tile across the whole tile on top of the half-tile base
+        let top = |x: f32| pos.y + (x - pos.x + Tile::SZ / 2.) / 2.;
+        for x in [-50., 0., 30.] {
+            app.world
+                .query_filtered::<&mut Transform, With<Control>>()
+                .single_mut(&mut app.world)
+                .translation
+                .x = x;
+            player_mut(&mut app).force = Vec2::new(0., -0.01);
+            for _ in 0..200 {
+                app.update();
+            }
+            assert!(player_mut(&mut app).grounded, "dropped at {x}");
+            let t = app
+                .world
+                .query_filtered::<&Transform, With<Control>>()
+                .single(&app.world);
+            let p = ctl_aabb(t);
+            // and it stands on the slope where its right foot is over it, or on the floor beside
+            let right = p.max.x.min(pos.x + Tile::SZ / 2.);
+            let under = if right > pos.x - Tile::SZ / 2. {
+                top(right)
+            } else {
+                pos.y - Tile::SZ / 2.
+            };
+            assert!(
+                (p.min.y - under).abs() < 2.,
+                "dropped at {x}, rests at {} over {under}",
+                p.min.y
+            );
+            // nothing along the bottom of the player is inside either part
+            for i in 0..=10 {
+                let foot = p.min.x + p.half_size().x * 0.2 * i as f32;
+                if (foot - pos.x).abs() <= Tile::SZ / 2. {
+                    assert!(
+                        p.min.y > top(foot) - 1.,
+                        "dropped at {x}, sunk to {} at {foot}",
+                        p.min.y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn picking_skips_the_empty_half_of_a_ramp() {
+        let at = |x: f32| Transform {
+            translation: Vec3::new(x, 0., 0.),
+            scale: Vec3::new(Tile::SZ, Tile::SZ, 1.),
+            ..default()
+        };
+        // the corner each ramp is filled in, and the one opposite it that's empty
+        for (tile, solid, empty) in [
+            (Tile::SLOPER, Vec2::new(20., -20.), Vec2::new(-20., 20.)),
+            (Tile::STEPR, Vec2::new(20., -20.), Vec2::new(-20., 20.)),
+            (Tile::SLOPEL, Vec2::new(-20., -20.), Vec2::new(20., 20.)),
+            (Tile::STEPL, Vec2::new(-20., -20.), Vec2::new(20., 20.)),
+        ] {
+            let e = Entity::from_raw(1);
+            let tiles = [(e, &at(0.), &tile)];
+            assert_eq!(tile_at(tiles, solid), Some(e), "{tile:?}");
+            assert_eq!(tile_at(tiles, empty), None, "{tile:?}");
+        }
+        // where tiles overlap, the one whose solid part is under the cursor wins even when the
+        // other comes first
+        let (slope, square) = (Entity::from_raw(1), Entity::from_raw(2));
+        let mut over = at(-30.);
+        over.translation.y = 30.;
+        let both = [
+            (slope, &at(0.), &Tile::SLOPER),
+            (square, &over, &Tile::SQUARE),
+        ];
+        assert_eq!(tile_at(both, Vec2::new(-20., 20.)), Some(square));
+        assert_eq!(tile_at(both, Vec2::new(20., -20.)), Some(slope));
+    }
+
+    #[test]
+    fn players_only_push_apart_when_asked_to() {
+        use bevy::ecs::system::RunSystemOnce;
+        let outs = |collide: bool| {
+            let mut world = World::new();
+            world.insert_resource(PlayerCollide(collide));
+            world.init_resource::<PhysicsScale>();
+            // 45 wide, overlapping by 15 across and 40 up and down
+            let at =
+                |x: f32, y: f32| Transform::from_xyz(x, y, 0.).with_scale(Vec3::new(45., 45., 1.));
+            let a = world.spawn((Control, Movement::default(), at(0., 0.))).id();
+            let b = world
+                .spawn((Control, Movement::default(), at(30., 5.)))
+                .id();
+            world.run_system_once(push_players);
+            [a, b].map(|e| world.get::<Movement>(e).unwrap().out)
+        };
+        assert_eq!(outs(false), [Vec2::ZERO; 2]);
+        // apart along the shallower overlap, half each
+        assert_eq!(outs(true), [Vec2::new(-7.5, 0.), Vec2::new(7.5, 0.)]);
+    }
+
+    #[test]
+    fn g_merges_a_painted_run_into_one_collider() {
+        use crate::tests::{game, tap};
+        // a run of 8 squares, well below the player
+        let run = Level::from_grid(Vec2::new(-200., -500.), 8, vec![Tile::SQUARE.0; 8]).unwrap();
+        let mut app = game(run);
+        app.update();
+        let colliders = |app: &App| app.world.resource::<TileCache>().tiles.clone();
+        assert_eq!(colliders(&app).len(), 8);
+        tap(&mut app, KeyCode::KeyG);
+        let merged = colliders(&app);
+        assert_eq!(merged.len(), 1, "{merged:?}");
+        let (c, aabb) = merged[0];
+        assert_eq!(c, Tile::SQUARE);
+        assert_eq!(
+            (aabb.min, aabb.max),
+            (Vec2::new(-225., -525.), Vec2::new(175., -475.))
+        );
+        // still drawn as the tiles that were painted
+        let mut tiles = app.world.query_filtered::<(), (With<Tile>, With<Sprite>)>();
+        assert_eq!(tiles.iter(&app.world).count(), 8);
+        tap(&mut app, KeyCode::KeyG);
+        assert_eq!(colliders(&app).len(), 8);
+    }
+
+    #[test]
+    fn finds_the_nearest_interactable_in_front() {
+        use bevy::ecs::system::RunSystemOnce;
+        let mut world = World::new();
+        world.init_resource::<InteractTarget>();
+        let player = (world.spawn((
+            Control,
+            Facing::default(),
+            Transform::default().with_scale(Vec3::new(45., 45., 1.)),
+        )))
+        .id();
+        let thing = |world: &mut World, x: f32| {
+            let t = Transform::from_xyz(x, 0., 0.).with_scale(Vec3::splat(20.));
+            world.spawn((Interactable, t)).id()
+        };
+        // reach runs 30 past the player's right side at 22.5, the far one is only partly in it
+        let near = thing(&mut world, 40.);
+        let far = thing(&mut world, 55.);
+        // and this one's out of reach
+        thing(&mut world, 70.);
+        let found = |world: &mut World, left: bool| {
+            world.get_mut::<Facing>(player).unwrap().left = left;
+            world.run_system_once(find_interactable);
+            world.resource::<InteractTarget>().0
+        };
+        assert_eq!(found(&mut world, false), Some(near));
+        assert_eq!(found(&mut world, true), None);
+        world.despawn(near);
+        assert_eq!(found(&mut world, false), Some(far));
+        world.despawn(far);
+        assert_eq!(found(&mut world, false), None);
+    }
+
+    #[test]
+    fn the_movement_dump_shows_the_state_and_whats_touching() {
+        let v = Movement {
+            ctl: Vec2::new(3., 0.),
+            force: Vec2::new(0., -1.5),
+            grounded: true,
+            ..default()
+        };
+        // standing a skin above one tile, with another off to the side
+        let aabb = Aabb2d::new(Vec2::new(0., 23.), Vec2::splat(22.5));
+        let tile = |x: f32| {
+            (
+                Tile::SQUARE,
+                Aabb2d::new(Vec2::new(x, -25.), Vec2::splat(25.)),
+            )
+        };
+        let dump = movement_dump(&aabb, &v, 0.25, &[tile(0.), tile(500.)]);
+        for want in [
+            "ctl: Vec2(\n        3.0,\n        0.0,\n    ),",
+            "force: Vec2(\n        0.0,\n        -1.5,\n    ),",
+            "grounded: true,",
+            "hang: 0.0,",
+            "physics tick remainder: 0.25\n",
+            "box: [-22.5, 0.5] to [22.5, 45.5]\n",
+            "touching 1 tiles\n  square [-25, -50] to [25, 0]\n",
+        ] {
+            assert!(dump.contains(want), "no {want:?} in\n{dump}");
+        }
+        assert!(!dump.contains("[475"), "{dump}");
+    }
+
+    #[test]
+    fn slightly_off_tiles_save_into_their_own_cells() {
+        // a grid that isn't lined up with the world origin
+        let level = Level::from_grid(Vec2::new(12.5, -30.), 1, vec![1]).unwrap();
+        let at = |col: f32, row: f32, err: Vec2| {
+            Transform::from_translation(
+                (level.origin + Vec2::new(col, row) * Tile::SZ + err).extend(0.),
+            )
+        };
+        // 3 wide and 2 high, each a hair off one way or the other
+        let tiles = [
+            (at(0., 0., Vec2::new(0.04, 0.03)), Tile::SQUARE),
+            (at(1., 0., Vec2::new(-0.04, -0.02)), Tile::STEPR),
+            (at(2., 0., Vec2::new(0.03, 0.04)), Tile::HAZARD),
+            (at(0., 1., Vec2::new(-0.03, -0.04)), Tile::STEPL),
+            (at(2., 1., Vec2::new(0.02, -0.03)), Tile::PLATE),
+        ];
+        let saved =
+            snapshot_level(tiles.iter().map(|(t, c)| (t, c, false, false)), &level).unwrap();
+        assert_eq!(saved.origin, level.origin);
+        assert_eq!((saved.width, saved.height()), (3, 2));
+        // rows from the top down
+        let ids = [
+            Tile::STEPL.0,
+            0,
+            Tile::PLATE.0,
+            Tile::SQUARE.0,
+            Tile::STEPR.0,
+            Tile::HAZARD.0,
+        ];
+        assert_eq!(saved.data, ids);
+    }
+
+    #[test]
+    fn player_bundle_starts_at_rest() {
+        let mut world = World::new();
+        let size = PlayerSize(Vec2::new(30., 60.));
+        let bundle = PlayerBundle::new(Vec2::new(10., 20.), size, Vec2::ONE, default());
+        let e = world.spawn(bundle).id();
+        let e = world.entity(e);
+        assert!(e.contains::<Control>());
+        let v = e.get::<Movement>().unwrap();
+        assert_eq!(
+            (v.ctl, v.force, v.out, v.wind),
+            (Vec2::ZERO, Vec2::ZERO, Vec2::ZERO, Vec2::ZERO)
+        );
+        assert!(!v.grounded && !v.crouch && v.hang == 0. && v.platform.is_none());
+        assert_eq!(e.get::<Health>().unwrap().0, Health::MAX);
+        assert!(!e.get::<Facing>().unwrap().left);
+        assert!(!e.contains::<Invulnerable>());
+        // where it was put, above the tiles, sized as its collision box
+        let t = e.get::<Transform>().unwrap();
+        assert_eq!(t.translation, Vec3::new(10., 20., Layer::Player.z()));
+        assert_eq!(ctl_aabb(t).half_size(), Vec2::new(15., 30.));
+        assert!(Layer::Player.z() > Layer::Tile.z());
+    }
+
+    #[test]
+    fn foreground_fades_while_the_player_is_behind_it() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_secs_f64(1. / 60.),
+            ))
+            .add_systems(Update, fade_foreground);
+        let tile = (app.world)
+            .spawn((
+                Sprite {
+                    color: Color::rgba(1., 1., 1., 0.8),
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                Transform::from_xyz(100., 0., Layer::Foreground.z()).with_scale(Vec3::new(
+                    Tile::SZ,
+                    Tile::SZ,
+                    1.,
+                )),
+                Foreground {
+                    alpha: 0.8,
+                    shown: 1.,
+                },
+            ))
+            .id();
+        let player = (app.world)
+            .spawn((
+                Control,
+                Transform::from_xyz(0., -500., 0.).with_scale(Vec3::splat(45.)),
+            ))
+            .id();
+        let alphas = |app: &mut App, x: f32, y: f32, frames: usize| {
+            app.world.get_mut::<Transform>(player).unwrap().translation = Vec3::new(x, y, 0.);
+            (0..frames)
+                .map(|_| {
+                    app.update();
+                    app.world.get::<Sprite>(tile).unwrap().color.a()
+                })
+                .collect::<Vec<_>>()
+        };
+        // nowhere near it, it stays as it is
+        assert!(alphas(&mut app, 0., -500., 10).iter().all(|a| *a == 0.8));
+        // partly behind it, it fades down to FADED and holds there
+        let fading = alphas(&mut app, 80., 20., 60);
+        assert!(fading.windows(2).all(|w| w[1] <= w[0]), "{fading:?}");
+        assert!(fading[1] < 0.8, "{fading:?}");
+        let faded = 0.8 * Foreground::FADED;
+        assert!((fading[59] - faded).abs() < 1e-5, "{fading:?}");
+        // and comes back once they're clear
+        let back = alphas(&mut app, 200., 0., 60);
+        assert!(back.windows(2).all(|w| w[1] >= w[0]), "{back:?}");
+        assert!((back[59] - 0.8).abs() < 1e-5, "{back:?}");
+    }
+
+    #[test]
+    fn camera_waits_for_a_single_player() {
+        let (mut app, cam) = follow_app(CameraFollow {
+            gain: Vec2::ONE,
+            ..default()
+        });
+        let start = Transform::from_xyz(12., 34., 0.);
+        *app.world.get_mut::<Transform>(cam).unwrap() = start;
+        // none at all, then two at once while one is being respawned
+        for players in [0, 2] {
+            for _ in 0..players {
+                let far = Transform::from_xyz(1000., 1000., 0.);
+                app.world.spawn((far, Movement::default(), Control));
+            }
+            for _ in 0..3 {
+                app.update();
+            }
+            assert_eq!(*app.world.get::<Transform>(cam).unwrap(), start);
+        }
+    }
+
+    #[test]
+    fn camera_settles_vertically_only_on_the_ground() {
+        let (mut app, cam) = follow_app(CameraFollow::default());
+        let player = (app.world)
+            .spawn((Transform::default(), Movement::default(), Control))
+            .id();
+        app.update();
+        let at = |app: &mut App, y: f32, grounded: bool| {
+            app.world
+                .get_mut::<Transform>(player)
+                .unwrap()
+                .translation
+                .y = y;
+            app.world.get_mut::<Movement>(player).unwrap().grounded = grounded;
+            app.update();
+            app.world.get::<Transform>(cam).unwrap().translation.y
+        };
+        // a jump in place, up 90 and back down over a second
+        let jump: Vec<_> = (1..60)
+            .map(|i| {
+                let t = i as f32 / 60.;
+                at(&mut app, 360. * t * (1. - t), false)
+            })
+            .collect();
+        assert!(jump.iter().all(|y| *y == 0.), "{jump:?}");
+        assert_eq!(at(&mut app, 0., true), 0.);
+        // landing on a platform 80 up eases over to it
+        let settle: Vec<_> = (0..120).map(|_| at(&mut app, 80., true)).collect();
+        assert!(settle[0] > 0. && settle[0] < 20., "{settle:?}");
+        assert!(settle.windows(2).all(|w| w[1] >= w[0]), "{settle:?}");
+        assert!((settle[119] - 80.).abs() < 0.5, "{settle:?}");
+    }
+
+    #[test]
+    fn thin_maps_still_save() {
+        let level = Level::builtin();
+        let at = |x: i32, y: i32| Transform::from_xyz(x as f32 * Tile::SZ, y as f32 * Tile::SZ, 0.);
+        // a single tile, a column of five and a row of five
+        for (w, h) in [(1, 1), (1, 5), (5, 1)] {
+            let tiles: Vec<_> = (0..w)
+                .flat_map(|x| (0..h).map(move |y| (at(x, y), Tile::SQUARE)))
+                .collect();
+            let saved =
+                snapshot_level(tiles.iter().map(|(t, c)| (t, c, false, false)), &level).unwrap();
+            assert_eq!((saved.width, saved.height()), (w as usize, h as usize));
+            let bmp = map_bmp(&saved);
+            let field = |off: usize| u32::from_le_bytes(bmp[off..off + 4].try_into().unwrap());
+            // rows padded to a multiple of 4 bytes
+            let stride = (w as usize * 3).div_ceil(4) * 4;
+            assert_eq!(field(0x02) as usize, bmp.len(), "{w}x{h}");
+            assert_eq!((field(0x12), field(0x16)), (w as u32, h as u32));
+            assert_eq!(field(0x22) as usize, stride * h as usize, "{w}x{h}");
+            assert_eq!(bmp.len(), 0x36 + stride * h as usize, "{w}x{h}");
+            // the source reads back as the same tiles
+            let reread = Level::parse(&map_source(&saved)).unwrap();
+            assert_eq!(reread.data, saved.data, "{w}x{h}");
+            assert_eq!(reread.width, saved.width, "{w}x{h}");
+        }
+    }
+
+    #[test]
+    fn dragging_keeps_the_grabbed_point_under_the_cursor() {
+        // a 800x600 view of a 400x300 projection, zoomed out 2x, so a pixel is a world unit
+        let px = 300. / 600. * 2.;
+        let mut cam = Vec2::new(40., -10.);
+        // the world point under a screen position, y down from the top left
+        let under =
+            |cam: Vec2, screen: Vec2| cam + (screen - Vec2::new(400., 300.)) * Vec2::new(px, -px);
+        let grabbed = under(cam, Vec2::new(100., 100.));
+        let path = [
+            Vec2::new(100., 100.),
+            Vec2::new(130., 90.),
+            Vec2::new(300., 450.),
+            Vec2::new(-20., 0.),
+        ];
+        for w in path.windows(2) {
+            cam += drag_pan(w[0], w[1], px);
+            assert_eq!(under(cam, w[1]), grabbed, "{w:?}");
+        }
+        // all told the drag went 120 left and 100 up the screen, and the camera the opposite way
+        assert_eq!(cam - Vec2::new(40., -10.), Vec2::new(120., -100.));
+    }
+
+    #[test]
+    fn an_ignored_click_says_why_once() {
+        use bevy::log::tracing_subscriber;
+        use std::sync::{Arc, Mutex};
+        #[derive(Clone, Default)]
+        struct Logged(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Logged {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let logged = Logged::default();
+        let writer = logged.clone();
+        let subscriber = (tracing_subscriber::fmt())
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+
+        let mut app = crate::tests::game(crate::tests::open_level());
+        // one system run over and over, so it keeps what it's already warned about
+        let mut check = IntoSystem::into_system(debug_check_mouse);
+        check.initialize(&mut app.world);
+        let mut click = |world: &mut World| {
+            let mut mouse = world.resource_mut::<ButtonInput<MouseButton>>();
+            mouse.release(MouseButton::Left);
+            mouse.clear();
+            mouse.press(MouseButton::Left);
+            check.run((), world);
+            check.apply_deferred(world);
+        };
+        bevy::utils::tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                click(&mut app.world);
+            }
+            // a window without the cursor in it is a new reason
+            app.world.spawn((Window::default(), PrimaryWindow));
+            for _ in 0..3 {
+                click(&mut app.world);
+            }
+        });
+        let logged = String::from_utf8(logged.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = logged.lines().collect();
+        assert_eq!(lines.len(), 2, "{logged}");
+        assert!(
+            lines[0].contains("WARN")
+                && lines[0].ends_with("editor click ignored, there's no window")
+        );
+        assert!(lines[1].ends_with("editor click ignored, the cursor isn't in the window"));
+    }
+
+    #[test]
+    fn the_same_effect_played_twice_is_two_sounds() {
+        use bevy::audio::PlaybackMode;
+        use bevy::ecs::system::RunSystemOnce;
+        let mut world = World::new();
+        let thump = Handle::<AudioSource>::default();
+        let play = move |mut commands: Commands| {
+            play_once(&mut commands, thump.clone(), PlaybackSettings::ONCE);
+        };
+        world.run_system_once(play.clone());
+        world.run_system_once(play);
+        let mut sounds = world.query::<&PlaybackSettings>();
+        assert_eq!(sounds.iter(&world).count(), 2);
+        // each goes away once it's played, whatever it was asked for
+        assert!((sounds.iter(&world)).all(|s| matches!(s.mode, PlaybackMode::Despawn)));
+    }
+
+    #[test]
+    fn the_collision_ramp_fades_from_red_to_half_red() {
+        for len in [1, 2, 3, 10, 1000] {
+            let ramp: Vec<_> = (0..len).map(|i| collision_color(i, len)).collect();
+            for c in &ramp {
+                let channels = [c.r(), c.g(), c.b(), c.a()];
+                assert!(
+                    channels.iter().all(|c| (0.0..=1.).contains(c)),
+                    "{len}: {c:?}"
+                );
+            }
+            assert_eq!(ramp[0], Color::rgb(1., 0., 0.));
+            if len > 1 {
+                assert_eq!(ramp[len - 1], Color::rgb(0.5, 0., 0.));
+            }
+            assert!(ramp.windows(2).all(|w| w[1].r() < w[0].r()), "{len}");
+        }
+    }
+
+    #[test]
+    fn the_hitbox_is_outlined_in_open_air() {
+        let mut app = crate::tests::game(crate::tests::open_level());
+        crate::tests::player_mut(&mut app).force = Vec2::new(0., -0.01);
+        for _ in 0..5 {
+            app.update();
+        }
+        let mut dbg = app.world.query::<&DebugUi>();
+        assert!(dbg.single(&app.world).collisions.is_empty());
+        let mut player = app.world.query_filtered::<&Transform, With<Control>>();
+        let t = *player.single(&app.world);
+        let outline = hitbox_outline(&t);
+        assert_eq!(outline.center(), t.translation.xy());
+        assert_eq!(outline.size(), Vec2::splat(45.));
+        // on its side while climbing, it's as wide as the player is tall
+        let climbing = Transform::from_scale(Vec3::new(45., 30., 1.))
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        assert!(hitbox_outline(&climbing)
+            .size()
+            .abs_diff_eq(Vec2::new(30., 45.), 1e-3));
+    }
+
+    #[test]
+    fn the_svg_draws_each_tile_as_its_collider() {
+        // a square and a slope up to the right on top, a slope up to the left under the gap
+        let mut level = Level::from_grid(Vec2::new(100., -50.), 3, vec![1, 0, 4, 0, 5, 0]).unwrap();
+        let svg = map_svg(&level);
+        // bottom row first, y flipped
+        let lines: Vec<_> = svg.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="75 -25 150 100">"#,
+                r##"  <polygon points="125,75 175,75 125,25" fill="#00ff00"/>"##,
+                r##"  <polygon points="175,25 225,25 225,-25" fill="#ffa500"/>"##,
+                r##"  <rect x="75" y="-25" width="50" height="50" fill="#ffffff"/>"##,
+                "</svg>",
+            ]
+        );
+        // an id the palette has no color for is magenta, not some other tile's color
+        level.data[0] = Tile::NUM as u8 + 1;
+        let svg = map_svg(&level);
+        assert!(svg.contains(r##"fill="#ff00ff""##), "{svg}");
+    }
+
+    #[test]
+    fn a_bar_for_each_timer_with_time_left() {
+        let feel = GameFeel {
+            coyote: 0.2,
+            jump_buffer: 0.1,
+            dash_cooldown: 0.5,
+            ..GameFeel::SNAPPY
+        };
+        let player = Aabb2d::new(Vec2::ZERO, Vec2::splat(20.));
+        let mut v = Movement {
+            air_time: 0.05,
+            jump_buffer: 0.05,
+            dash_cooldown: 0.1,
+            ..default()
+        };
+        let bars = |v: &Movement| -> Vec<_> {
+            (timer_bars(&player, v, &feel).into_iter())
+                .map(|(start, end, color)| (start, (end - start).x, color))
+                .collect()
+        };
+        // as long as the player, times what's left of the window
+        assert_eq!(
+            bars(&v),
+            [
+                (Vec2::new(-20., 26.), 30., Color::YELLOW),
+                (Vec2::new(-20., 31.), 20., Color::CYAN),
+                (Vec2::new(-20., 36.), 8., Color::FUCHSIA),
+            ]
+        );
+        // on the ground there's no coyote window, and a spent one isn't shown
+        v.grounded = true;
+        v.jump_buffer = 0.;
+        assert_eq!(bars(&v), [(Vec2::new(-20., 26.), 8., Color::FUCHSIA)]);
+    }
 }
@@ -0,0 +1,281 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::level::{
+    snapshot_level, spawn_tiles, spawn_zones, CameraTrigger, Composite, Control, DebugUi, Decor,
+    Platform,
+};
+use crate::level::{MapBounds, PlayerBundle, Wind};
+use crate::map::Level;
+use crate::physics::{Movement, Tile};
+use crate::AppState;
+
+// F3 lists the map files in BABY_LEVELS (assets/level if it's not set) with their sizes, and
+// the number next to one swaps it in for the map being played. ctrl and a number saves the map
+// being edited to slot map_<n>.map, ctrl shift and the number loads it back. debug builds only
+pub struct LevelSelectPlugin;
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        if cfg!(debug_assertions) {
+            app.init_resource::<LevelMenu>()
+                .init_resource::<MapSlot>()
+                .add_event::<LoadLevel>()
+                .add_systems(OnEnter(AppState::Game), setup_menu)
+                .add_systems(
+                    Update,
+                    (level_menu, map_slots, load_level)
+                        .chain()
+                        .run_if(in_state(AppState::Game)),
+                );
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LevelMenu {
+    open: bool,
+    // rescanned every time the menu opens, so a freshly saved map shows up
+    maps: Vec<(PathBuf, Result<Level, String>)>,
+}
+
+#[derive(Component)]
+pub struct LevelMenuText;
+
+// the save slot last saved to or loaded from, if any, and where the slot files go. that's the
+// working directory like map.map, unless it's moved somewhere else
+#[derive(Resource)]
+pub struct MapSlot {
+    pub last: Option<usize>,
+    pub dir: PathBuf,
+}
+
+impl Default for MapSlot {
+    fn default() -> Self {
+        MapSlot {
+            last: None,
+            dir: PathBuf::from("."),
+        }
+    }
+}
+
+impl MapSlot {
+    pub fn path(&self, slot: usize) -> PathBuf {
+        self.dir.join(format!("map_{slot}.map"))
+    }
+}
+
+// swap the map being played for this one, tiles and all
+#[derive(Event)]
+pub struct LoadLevel(pub Level);
+
+const KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+const CTRL: [KeyCode; 2] = [KeyCode::ControlLeft, KeyCode::ControlRight];
+const SHIFT: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+
+fn levels_dir() -> PathBuf {
+    std::env::var("BABY_LEVELS").map_or_else(|_| PathBuf::from("assets/level"), PathBuf::from)
+}
+
+// the map files in dir by name, already loaded so the menu can show their size, or why they
+// won't load. anything that isn't a .map or .rs file is left out
+pub fn scan(dir: &Path) -> Vec<(PathBuf, Result<Level, String>)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut maps: Vec<_> = (entries.filter_map(|e| e.ok()))
+        .map(|e| e.path())
+        .filter(|p| (p.extension()).is_some_and(|ext| ext == "map" || ext == "rs"))
+        .map(|p| {
+            let level = Level::load(&p);
+            (p, level)
+        })
+        .collect();
+    maps.sort_by(|a, b| a.0.cmp(&b.0));
+    maps
+}
+
+// the menu's text, a line per map with the key that picks it and its size
+fn menu_lines(dir: &Path, maps: &[(PathBuf, Result<Level, String>)]) -> Vec<String> {
+    let mut lines = vec![format!("maps in {}:", dir.display())];
+    if maps.is_empty() {
+        lines.push("none".to_string());
+    }
+    for (i, (path, level)) in maps.iter().enumerate() {
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        let key = if i < KEYS.len() {
+            (i + 1).to_string()
+        } else {
+            " ".to_string()
+        };
+        lines.push(match level {
+            Ok(level) => format!("{key} {file} {}x{}", level.width, level.height()),
+            Err(e) => format!("  {file}: {e}"),
+        });
+    }
+    lines
+}
+
+fn setup_menu(mut commands: Commands) {
+    commands.spawn((
+        LevelMenuText,
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+pub fn level_menu(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<LevelMenu>,
+    mut text: Query<(&mut Text, &mut Visibility), With<LevelMenuText>>,
+    mut load: EventWriter<LoadLevel>,
+) {
+    if kbd.just_pressed(KeyCode::F3) {
+        menu.open = !menu.open;
+        if menu.open {
+            let dir = levels_dir();
+            menu.maps = scan(&dir);
+            let lines = menu_lines(&dir, &menu.maps);
+            for (mut text, _) in &mut text {
+                text.sections = (lines.iter())
+                    .map(|l| TextSection::new(format!("{l}\n"), default()))
+                    .collect();
+            }
+        }
+    } else if menu.open && !kbd.any_pressed(CTRL) {
+        let picked = (KEYS.iter().zip(&menu.maps))
+            .find(|(key, _)| kbd.just_pressed(**key))
+            .map(|(_, (_, level))| level);
+        if let Some(Ok(level)) = picked {
+            load.send(LoadLevel(level.clone()));
+            menu.open = false;
+        }
+    }
+    let shown = if menu.open {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for (_, mut vis) in &mut text {
+        if *vis != shown {
+            *vis = shown;
+        }
+    }
+}
+
+pub fn map_slots(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut slot: ResMut<MapSlot>,
+    tiles: Query<(&Transform, &Tile, Has<Decor>, Has<Composite>)>,
+    level: Res<Level>,
+    mut load: EventWriter<LoadLevel>,
+    mut dbg: Query<&mut DebugUi>,
+) {
+    if !kbd.any_pressed(CTRL) {
+        return;
+    }
+    let Some(n) = KEYS.iter().position(|key| kbd.just_pressed(*key)) else {
+        return;
+    };
+    let path = slot.path(n + 1);
+    let done = if kbd.any_pressed(SHIFT) {
+        Level::load(&path).map(|level| {
+            load.send(LoadLevel(level));
+        })
+    } else {
+        match snapshot_level(&tiles, &level) {
+            Some(saved) => (std::fs::write(&path, saved.to_text())).map_err(|e| e.to_string()),
+            None => Err("no tiles to save".to_string()),
+        }
+    };
+    match done {
+        Ok(()) => {
+            slot.last = Some(n + 1);
+            for mut dbg in &mut dbg {
+                dbg.watch("slot", &path);
+            }
+        }
+        Err(e) => eprintln!("map slot {}: {e}", path.display()),
+    }
+}
+
+pub fn load_level(
+    mut ev_load: EventReader<LoadLevel>,
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    tiles: Query<Entity, Or<(With<Tile>, With<Wind>, With<CameraTrigger>, With<Platform>)>>,
+    mut player: Query<(&mut Transform, &mut Movement), With<Control>>,
+) {
+    let Some(LoadLevel(level)) = ev_load.read().last() else {
+        return;
+    };
+    for e in &tiles {
+        commands.entity(e).despawn();
+    }
+    spawn_tiles(&mut commands, &assets, level);
+    spawn_zones(&mut commands, level);
+    commands.insert_resource(MapBounds::from_tiles(level.tiles().map(|(_, pos)| pos)));
+    commands.insert_resource(level.clone());
+    for (mut t, mut v) in &mut player {
+        t.translation = PlayerBundle::SPAWN.extend(t.translation.z);
+        *v = Movement::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn menu_lists_the_maps_and_loads_the_one_picked() {
+        let dir = std::env::temp_dir().join(format!("baby-levels-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wide = Level::from_grid(Vec2::ZERO, 3, vec![1; 6]).unwrap();
+        let tall = Level::from_grid(Vec2::ZERO, 1, vec![1; 4]).unwrap();
+        std::fs::write(dir.join("a.map"), wide.to_text()).unwrap();
+        std::fs::write(dir.join("b.map"), tall.to_text()).unwrap();
+        std::fs::write(dir.join("c.map"), "not a map").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a map either").unwrap();
+        let maps = scan(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let lines = menu_lines(&dir, &maps);
+        assert_eq!(lines.len(), 4, "{lines:?}");
+        assert_eq!(lines[1..3], ["1 a.map 3x2", "2 b.map 1x4"]);
+        assert!(lines[3].starts_with("  c.map: "), "{lines:?}");
+
+        let mut world = World::new();
+        let mut kbd = ButtonInput::<KeyCode>::default();
+        kbd.press(KeyCode::Digit2);
+        world.insert_resource(kbd);
+        world.insert_resource(LevelMenu { open: true, maps });
+        world.init_resource::<Events<LoadLevel>>();
+        world.run_system_once(level_menu);
+        assert!(!world.resource::<LevelMenu>().open);
+        let events = world.resource::<Events<LoadLevel>>();
+        let loaded: Vec<_> = (events.get_reader().read(events))
+            .map(|LoadLevel(level)| (level.width, level.height()))
+            .collect();
+        assert_eq!(loaded, [(1, 4)]);
+    }
+}
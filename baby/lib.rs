@@ -0,0 +1,3 @@
+// the parts of the game that don't need an app running, so bench_collide can use them too
+pub mod map;
+pub mod physics;
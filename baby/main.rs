@@ -3,37 +3,200 @@
 
 use bevy::prelude::*;
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
+use grossbaby::{map, physics};
 
 const WINDOW_WIDTH: f32 = 800.;
 const WINDOW_HEIGHT: f32 = 600.;
-// surely this should be wide enough
-const PILLARBOX_WIDTH: f32 = 2000.;
 
+mod fade;
+mod ghost;
 mod intro;
+mod letterbox;
 mod level;
+mod levelselect;
+mod minimap;
+mod pace;
+#[cfg(test)]
+mod tests;
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash)]
 enum AppState {
+    // waiting on the intro's assets
+    Loading,
     Intro,
     Game,
 }
 
 fn main() {
-    App::new()
-        .add_plugins(EmbeddedAssetPlugin {
-            mode: PluginMode::ReplaceDefault,
-        })
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Baby".into(),
-                resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
-                ..default()
-            }),
+    let mut app = App::new();
+    app.add_plugins(EmbeddedAssetPlugin {
+        mode: PluginMode::ReplaceDefault,
+    });
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Baby".into(),
+            resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
+            present_mode: bevy::window::PresentMode::AutoVsync,
             ..default()
-        }))
+        }),
+        ..default()
+    }));
+    add_game(&mut app, intro::SkipIntro::from_env());
+    app.run();
+}
+
+// everything but the engine plugins, so the game can be wired into an app without a window
+// (see tests.rs for running it headless)
+fn add_game(app: &mut App, skip_intro: intro::SkipIntro) {
+    app
         // Shared
-        .insert_state(AppState::Intro)
-        .add_systems(Update, intro::animate_texture)
+        .insert_state(if skip_intro.0 {
+            AppState::Game
+        } else {
+            AppState::Loading
+        })
+        .insert_resource(ClearColor(Color::BLACK))
+        .add_systems(Update, letterbox::fit_viewport)
+        .add_plugins(pace::PacePlugin)
+        .insert_resource(intro::TimeScale(1.))
+        .insert_resource(intro::GameTime::from_env())
+        .add_systems(
+            PreUpdate,
+            (intro::pause_on_focus, intro::tick_game_time).chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                intro::swap_sheets,
+                intro::animate_texture,
+                intro::follow_paths,
+            )
+                .chain()
+                .after(intro::sequence_cues),
+        )
+        .add_event::<fade::FadedOut>()
+        .add_systems(Update, fade::update_fade);
+    if !skip_intro.0 {
+        add_intro(app);
+    }
+    app
+        // Game
+        .add_plugins(level::DebugGamePlugin)
+        .add_plugins(levelselect::LevelSelectPlugin)
+        .add_plugins(ghost::GhostPlugin)
+        .add_plugins(minimap::MinimapPlugin)
+        .add_plugins(level::PhysicsLogPlugin)
+        .init_resource::<level::PhysicsTick>()
+        .init_resource::<level::PhysicsScale>()
+        .insert_resource(level::PlayerSize::from_env())
+        .insert_resource(first_level(&skip_intro, &intro::StartLevel::from_env()))
+        .insert_resource(level::CameraFollow::default())
+        .insert_resource(level::PlayerCollide(false))
+        .init_resource::<level::MapVersion>()
+        .init_resource::<level::TileCache>()
+        .init_resource::<level::InteractTarget>()
+        .init_resource::<level::CameraCue>()
+        .init_resource::<level::EditMode>()
+        .init_resource::<level::SaveDir>()
+        .insert_resource(level::Rng::from_env())
+        .insert_resource(physics::GameFeel::from_env())
+        .insert_resource(level::Socd::from_env())
+        .insert_resource(level::PuzzleMode::from_env())
+        .init_resource::<physics::MaxVelocity>()
+        .add_event::<level::CollisionEvent>()
+        .add_event::<level::Crushed>()
+        .add_event::<level::PlatePressed>()
+        .add_event::<level::PlateReleased>()
+        .add_systems(OnEnter(AppState::Game), level::setup)
+        .add_systems(
+            Update,
+            level::quit_on_escape.run_if(in_state(AppState::Game)),
+        )
+        .configure_sets(
+            Update,
+            (
+                level::GameSet::Input,
+                level::GameSet::Physics,
+                level::GameSet::Move,
+                level::GameSet::Camera,
+            )
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        )
+        .configure_sets(
+            Update,
+            (level::GameSet::Input, level::GameSet::Physics).run_if(level::EditMode::off),
+        )
+        .add_systems(
+            Update,
+            (
+                // input and physics, or the grid moves instead in puzzle mode
+                (level::check_kbd, level::crouch, level::dash)
+                    .chain()
+                    .run_if(level::PuzzleMode::off)
+                    .in_set(level::GameSet::Input),
+                (
+                    level::move_platforms,
+                    level::watch_tiles,
+                    level::cache_tiles,
+                    level::check_collide,
+                    level::push_players,
+                    level::play_land_sound,
+                    level::play_footsteps,
+                    level::take_damage,
+                    level::update_health_bar,
+                )
+                    .chain()
+                    .run_if(level::PuzzleMode::off)
+                    .in_set(level::GameSet::Physics),
+                (
+                    // physics didn't run, so there's nothing to move by
+                    (level::update_movement.run_if(level::PuzzleMode::off))
+                        .run_if(level::EditMode::off),
+                    (level::puzzle_move.run_if(level::PuzzleMode::on)).run_if(level::EditMode::off),
+                    // everything that follows from where the player ended up
+                    (
+                        level::pick_sheet,
+                        level::find_interactable,
+                        level::press_plates,
+                        level::show_plates,
+                        level::stomp_tiles,
+                        level::animate_breaking,
+                        level::fade_foreground,
+                        level::fade_afterimages,
+                    )
+                        .chain(),
+                )
+                    .chain()
+                    .in_set(level::GameSet::Move),
+                (
+                    level::camera_triggers,
+                    level::pan_camera,
+                    level::fill_background,
+                    level::show_win,
+                )
+                    .chain()
+                    .in_set(level::GameSet::Camera),
+            ),
+        );
+}
+
+// the map the game opens on. BABY_START is normally loaded as the intro ends, so without the
+// intro it's loaded here instead
+fn first_level(skip_intro: &intro::SkipIntro, start: &intro::StartLevel) -> map::Level {
+    (skip_intro.0.then(|| start.load()).flatten()).unwrap_or_else(map::Level::from_env)
+}
+
+// the loading screen and the intro, left out entirely with BABY_SKIP_INTRO
+fn add_intro(app: &mut App) {
+    app
+        // Loading
+        .add_systems(OnEnter(AppState::Loading), intro::start_loading)
+        .add_systems(
+            Update,
+            intro::wait_for_assets.run_if(in_state(AppState::Loading)),
+        )
+        .add_systems(OnExit(AppState::Loading), intro::finish_loading)
         // Intro
         .add_systems(
             OnEnter(AppState::Intro),
@@ -44,7 +207,9 @@ fn main() {
             (
                 intro::sequence_cues,
                 intro::sequence_camera,
-                intro::check_kbd,
+                // before the sequence ends, so a skip on the last frame is the only fade
+                intro::check_kbd.before(intro::sequence_cues),
+                intro::replay_intro,
             )
                 .run_if(in_state(AppState::Intro)),
         )
@@ -53,20 +218,7 @@ fn main() {
             intro::draw_debug.run_if(in_state(AppState::Intro)),
         )
         .add_systems(OnExit(AppState::Intro), intro::cleanup)
-        // Game
-        .add_plugins(level::DebugGamePlugin)
-        .insert_resource(level::PhysicsTick(0.))
-        .add_systems(OnEnter(AppState::Game), level::setup)
-        .add_systems(
-            Update,
-            (
-                level::check_kbd,
-                level::check_collide,
-                level::update_movement,
-                level::pan_camera,
-            )
-                .run_if(in_state(AppState::Game))
-                .chain(),
-        )
-        .run();
+        .insert_resource(intro::AttractMode::from_env())
+        .insert_resource(intro::StartLevel::from_env())
+        .add_plugins(intro::CapturePlugin);
 }
@@ -0,0 +1,904 @@
+use bevy::{
+    ecs::system::Resource,
+    math::{IVec2, Rect, Vec2},
+    render::color::Color,
+};
+use std::path::Path;
+
+use crate::physics::Tile;
+
+// (bottom left tile position, width in tiles, tile ids row by row from the top)
+pub const MAP: (Vec2, usize, [u8; 27 * 112]) = (
+    Vec2::new(-200.0, -400.0),
+    27,
+    [
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, // 0
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 1
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 2
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, // 3
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 1, // 4
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, // 5
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 1, // 6
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 7
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 1, // 8
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, // 9
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 10
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 11
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 12
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 13
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 14
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 15
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 16
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 17
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 18
+        1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 19
+        1, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 20
+        1, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 21
+        1, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 22
+        1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 23
+        1, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 24
+        1, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 25
+        1, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 26
+        1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 27
+        1, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 28
+        1, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 29
+        1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 30
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 31
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 32
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 33
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 34
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 35
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 36
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 37
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 38
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 39
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 40
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 41
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 4, 1, 5, 0, 0, 0, 0, 1, // 42
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 43
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 44
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 45
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, // 46
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 47
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 48
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 49
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 50
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 51
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, // 52
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 53
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 54
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 55
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 56
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 57
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 58
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 59
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, // 60
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 61
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 62
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 63
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 64
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 65
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 66
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 67
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 68
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 69
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 70
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 71
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 1, // 72
+        1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 1, // 73
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 74
+        1, 0, 0, 0, 0, 0, 0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 75
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, // 76
+        1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 77
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 1, 5, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, // 78
+        1, 0, 0, 0, 0, 0, 0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, // 79
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, // 80
+        1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, // 81
+        1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 82
+        1, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 83
+        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 84
+        1, 0, 0, 0, 0, 0, 1, 1, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, // 85
+        1, 0, 0, 0, 1, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 86
+        1, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 87
+        3, 0, 0, 0, 0, 0, 4, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 88
+        1, 5, 0, 0, 0, 0, 1, 5, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 89
+        3, 0, 0, 0, 0, 4, 1, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 90
+        1, 5, 0, 0, 0, 0, 1, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 91
+        3, 0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 92
+        1, 5, 0, 0, 0, 0, 1, 5, 0, 1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 93
+        1, 0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 94
+        1, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 95
+        1, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 96
+        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 97
+        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 98
+        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // 99
+        1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0,
+        1, // 100
+        1, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0,
+        1, // 101
+        1, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0,
+        1, // 102
+        1, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0,
+        1, // 103
+        1, 0, 1, 0, 0, 0, 0, 1, 1, 1, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
+        1, // 104
+        1, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
+        1, // 105
+        1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
+        1, // 106
+        1, 0, 1, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
+        1, // 107
+        1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 3, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
+        1, // 108
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        1, // 109
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        1, // 110
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, // 111
+    ],
+);
+// the array's length is checked against its type but nothing checks the width against either, and
+// a wrong width shears every row. from_grid would only catch it once the game runs, so check here
+const _: () = assert!(
+    MAP.1 > 0 && MAP.2.len().is_multiple_of(MAP.1),
+    "MAP's tiles don't fill whole rows of its width"
+);
+// the debug color of each tile id, a map can change them with `// tile: <id> <kind> #rrggbbaa`
+pub const PALETTE: [Color; Tile::NUM] = [
+    Color::NONE,
+    Color::WHITE,
+    Color::RED,
+    Color::BLUE,
+    Color::ORANGE,
+    Color::GREEN,
+    Color::PURPLE,
+    Color::YELLOW,
+    Color::rgb(0.6, 0.4, 0.2),
+];
+// the rgb each tile id is saved as in map.bmp, which doesn't follow the map's palette so the
+// picture reads the same for every map
+pub const BMP_PALETTE: [[u8; 3]; Tile::NUM] = [
+    [0x00, 0x00, 0x00], // black  - empty
+    [0xff, 0xff, 0xff], // white  - Square
+    [0xff, 0x00, 0x00], // red    - StepR
+    [0x00, 0x00, 0xff], // blue   - StepL
+    [0xff, 0x88, 0x00], // orange - SlopeR
+    [0x00, 0xff, 0x00], // green  - SlopeL
+    [0x80, 0x00, 0x80], // purple - Hazard
+    [0xff, 0xff, 0x00], // yellow - Plate
+    [0x99, 0x66, 0x33], // brown  - Break
+];
+// the rgb tile id is saved as, magenta for an id without one so the stray tile stands out
+pub fn bmp_color(id: u8) -> [u8; 3] {
+    match BMP_PALETTE.get(id as usize) {
+        Some(&rgb) => rgb,
+        None => {
+            bevy::log::warn!("tile id {id} has no map.bmp color, saving it as magenta");
+            [0xff, 0x00, 0xff]
+        }
+    }
+}
+
+// how each tile id is written in the text format, by id
+pub const TEXT_CHARS: [char; Tile::NUM] = ['.', '#', '/', '\\', 'r', 'l', '^', '_', '%'];
+
+// the map the game is played on, MAP unless BABY_MAP points somewhere else
+#[derive(Resource, Clone)]
+pub struct Level {
+    pub origin: Vec2,
+    pub width: usize,
+    pub data: Vec<u8>,
+    // from a `// name: ...` comment outside the tile list
+    pub name: Option<String>,
+    // (row from the top, text) from comments after a row's tiles, eg. `1, 0, 1, // 12 boss room`
+    pub labels: Vec<(usize, String)>,
+    // tile colors by id, PALETTE unless the map has `// tile: ...` lines
+    pub palette: [Color; Tile::NUM],
+    // asset path of the texture tiles are cut from, from a `// texture: ...` comment
+    pub texture: Option<String>,
+    // flat color behind the map, from a `// background: #rrggbb` comment. black if it's not set
+    pub background: Option<Color>,
+    // (area, push per physics tick) from `// wind: <x> <y> <w> <h> <fx> <fy>` comments, the area
+    // from its bottom left corner in world units
+    pub winds: Vec<(Rect, Vec2)>,
+    // (area, where to look, camera scale) from `// camera: <x> <y> <w> <h> <tx> <ty> <scale>`
+    // comments, the camera looks there instead of following the player while they're inside
+    pub cameras: Vec<(Rect, Vec2, f32)>,
+    // (area, how far it goes, seconds there and back) from
+    // `// platform: <x> <y> <w> <h> <dx> <dy> <period>` comments, a solid block that eases from
+    // the area out to area + (dx, dy) and back
+    pub platforms: Vec<(Rect, Vec2, f32)>,
+    // centers of the tiles that are only decoration, from `// decor: <x> <y>` comments
+    pub decor: Vec<Vec2>,
+    // centers of the tiles that collide as a square with a slope on top instead of their own
+    // shape, from `// composite: <x> <y>` comments
+    pub composite: Vec<Vec2>,
+}
+
+impl Level {
+    pub fn builtin() -> Level {
+        Self::from_grid(MAP.0, MAP.1, MAP.2.to_vec()).unwrap()
+    }
+
+    // a map from tile ids laid out like MAP's (rows of width from the top, origin is the bottom
+    // left tile), for levels made by code rather than by hand
+    pub fn from_grid(origin: Vec2, width: usize, data: Vec<u8>) -> Result<Level, String> {
+        if width == 0 || !data.len().is_multiple_of(width) {
+            return Err(format!("{} tiles don't fill rows of {width}", data.len()));
+        }
+        Ok(Level {
+            origin,
+            width,
+            data,
+            name: None,
+            labels: vec![],
+            palette: PALETTE,
+            texture: None,
+            background: None,
+            winds: vec![],
+            cameras: vec![],
+            platforms: vec![],
+            decor: vec![],
+            composite: vec![],
+        })
+    }
+
+    // BABY_MAP=<file> loads the map from a file in the format save_map prints, so a map saved
+    // from the editor can be played again without recompiling. BABY_MAP=- reads it from stdin.
+    // files ending in .map are in the text format from to_text
+    pub fn from_env() -> Level {
+        let Ok(path) = std::env::var("BABY_MAP") else {
+            return Self::builtin();
+        };
+        let level = if path == "-" {
+            std::io::read_to_string(std::io::stdin())
+                .map_err(|e| e.to_string())
+                .and_then(|src| Self::parse(&src))
+        } else {
+            Self::load(Path::new(&path))
+        };
+        match level {
+            Ok(level) => level,
+            Err(e) => {
+                eprintln!("can't load BABY_MAP {path:?}: {e}, using the built in map");
+                Self::builtin()
+            }
+        }
+    }
+
+    // a map file, in the text format if it ends in .map and the format save_map prints otherwise
+    pub fn load(path: &Path) -> Result<Level, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        if path.extension().is_some_and(|ext| ext == "map") {
+            Self::parse_text(&src)
+        } else {
+            Self::parse(&src)
+        }
+    }
+
+    // parse the `const MAP: ... = (Vec2::new(x, y), width, [ids...]);` text save_map prints
+    // (map.rs itself parses too). comments are ignored other than the name, texture, palette and
+    // row labels, and the row numbers save_map puts in front of the labels
+    pub fn parse(text: &str) -> Result<Level, String> {
+        let src: String = (text.lines())
+            .map(|l| l.split("//").next().unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let rest = (src.split_once("Vec2::new(").map(|(_, r)| r)).ok_or("missing origin")?;
+        let (origin, rest) = rest.split_once(')').ok_or("unclosed origin")?;
+        let (x, y) = origin.split_once(',').ok_or("origin needs x and y")?;
+        let origin = Vec2::new(num(x)?, num(y)?);
+
+        let rest = rest.trim_start().trim_start_matches(',');
+        let (width, rest) = rest.split_once(',').ok_or("missing width")?;
+        let width: usize = num(width)?;
+
+        let (_, rest) = rest.split_once('[').ok_or("missing tile list")?;
+        let (ids, _) = rest.split_once(']').ok_or("unclosed tile list")?;
+        // comments were cut off line by line, so lines still match up with the original text
+        let first_line = src[..src.len() - rest.len()].matches('\n').count();
+        let last_line = first_line + ids.matches('\n').count();
+        let data = (ids.split(','))
+            .filter(|id| !id.trim().is_empty())
+            .map(num)
+            .collect::<Result<Vec<u8>, _>>()?;
+        let grid = Self::from_grid(origin, width, data)?;
+
+        let mut name = None;
+        let mut labels = vec![];
+        let mut palette = PALETTE;
+        let mut texture = None;
+        let mut background = None;
+        let mut winds = vec![];
+        let mut cameras = vec![];
+        let mut platforms = vec![];
+        let mut decor = vec![];
+        let mut composite = vec![];
+        let mut count = 0;
+        let notes = (text.lines().enumerate()).filter_map(|(i, l)| {
+            let (code, note) = l.split_once("//")?;
+            Some((i, code.trim().is_empty(), note))
+        });
+        let mut segs = ids.split('\n');
+        let mut line = first_line;
+        for (i, whole_line, note) in notes {
+            if (first_line..=last_line).contains(&i) {
+                // tiles up to the end of this line say which row the comment is on
+                for seg in segs.by_ref().take(i + 1 - line) {
+                    count += seg.split(',').filter(|id| !id.trim().is_empty()).count();
+                }
+                line = i + 1;
+                let label = row_label(note);
+                if !label.is_empty() && count > 0 {
+                    labels.push(((count - 1) / width, label.to_string()));
+                }
+            } else if let Some((key, val)) = note.split_once(':').filter(|_| whole_line) {
+                // metadata is a comment on a line of its own (so code printing it isn't read as
+                // metadata), anything else that looks like metadata is skipped
+                match key.trim() {
+                    "name" => name = Some(val.trim().to_string()),
+                    "texture" => texture = Some(val.trim().to_string()),
+                    "background" => background = Some(parse_color(val)?),
+                    "tile" => {
+                        let (tile, color) = parse_tile(val)?;
+                        palette[tile.0 as usize] = color;
+                    }
+                    "wind" => winds.push(parse_wind(val)?),
+                    "camera" => cameras.push(parse_camera(val)?),
+                    "platform" => platforms.push(parse_platform(val)?),
+                    "decor" => decor.push(parse_point(val)?),
+                    "composite" => composite.push(parse_point(val)?),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Level {
+            name,
+            labels,
+            palette,
+            texture,
+            background,
+            winds,
+            cameras,
+            platforms,
+            decor,
+            composite,
+            ..grid
+        })
+    }
+
+    // one char per tile (TEXT_CHARS), one line per row from the top, so an edit only changes the
+    // lines of the rows it touched. the metadata comes first, then a `map <x> <y> <width>` line
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        if let Some(name) = &self.name {
+            out += &format!("name: {name}\n");
+        }
+        if let Some(texture) = &self.texture {
+            out += &format!("texture: {texture}\n");
+        }
+        if let Some(line) = self.background_line() {
+            out += line.trim_start_matches("// ");
+            out += "\n";
+        }
+        for line in (self.palette_lines())
+            .chain(self.zone_lines())
+            .chain(self.tile_lines())
+        {
+            out += line.trim_start_matches("// ");
+            out += "\n";
+        }
+        out += &format!(
+            "map {:?} {:?} {}\n",
+            self.origin.x, self.origin.y, self.width
+        );
+        for (row, ids) in self.data.chunks(self.width).enumerate() {
+            out.extend(
+                ids.iter()
+                    .map(|&id| *TEXT_CHARS.get(id as usize).unwrap_or(&'?')),
+            );
+            if let Some((_, label)) = self.labels.iter().find(|(r, _)| *r == row) {
+                out += &format!(" {label}");
+            }
+            out += "\n";
+        }
+        out
+    }
+
+    // read back what to_text writes
+    pub fn parse_text(text: &str) -> Result<Level, String> {
+        let mut lines = text.lines();
+        let mut name = None;
+        let mut palette = PALETTE;
+        let mut texture = None;
+        let mut background = None;
+        let mut winds = vec![];
+        let mut cameras = vec![];
+        let mut platforms = vec![];
+        let mut decor = vec![];
+        let mut composite = vec![];
+        let (origin, width) = loop {
+            let line = lines.next().ok_or("missing `map <x> <y> <width>` line")?;
+            if let Some(head) = line.strip_prefix("map ") {
+                let mut parts = head.split_whitespace();
+                let (Some(x), Some(y), Some(width), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(format!("{line:?} should be `map <x> <y> <width>`"));
+                };
+                break (Vec2::new(num(x)?, num(y)?), num::<usize>(width)?);
+            }
+            match line.split_once(':') {
+                Some(("name", val)) => name = Some(val.trim().to_string()),
+                Some(("texture", val)) => texture = Some(val.trim().to_string()),
+                Some(("background", val)) => background = Some(parse_color(val)?),
+                Some(("tile", val)) => {
+                    let (tile, color) = parse_tile(val)?;
+                    palette[tile.0 as usize] = color;
+                }
+                Some(("wind", val)) => winds.push(parse_wind(val)?),
+                Some(("camera", val)) => cameras.push(parse_camera(val)?),
+                Some(("platform", val)) => platforms.push(parse_platform(val)?),
+                Some(("decor", val)) => decor.push(parse_point(val)?),
+                Some(("composite", val)) => composite.push(parse_point(val)?),
+                _ => return Err(format!("unknown line {line:?}")),
+            }
+        };
+        let mut data = vec![];
+        let mut labels = vec![];
+        for (row, line) in lines.enumerate() {
+            let tiles: Vec<char> = line.chars().take(width).collect();
+            if tiles.len() < width {
+                return Err(format!("row {row} is shorter than {width}"));
+            }
+            for c in tiles {
+                let id = (TEXT_CHARS.iter().position(|t| *t == c))
+                    .ok_or_else(|| format!("no tile for {c:?} in row {row}"))?;
+                data.push(id as u8);
+            }
+            let label: String = line.chars().skip(width).collect();
+            if !label.trim().is_empty() {
+                labels.push((row, label.trim().to_string()));
+            }
+        }
+
+        Ok(Level {
+            name,
+            labels,
+            palette,
+            texture,
+            background,
+            winds,
+            cameras,
+            platforms,
+            decor,
+            composite,
+            ..Self::from_grid(origin, width, data)?
+        })
+    }
+
+    // the `// tile: ...` lines describing the palette, in the form parse reads back
+    pub fn palette_lines(&self) -> impl Iterator<Item = String> + '_ {
+        (1..Tile::NUM).map(|id| {
+            let [r, g, b, a] = self.palette[id].as_rgba_u8();
+            let kind = Tile(id as u8).kind();
+            format!("// tile: {id} {kind} #{r:02x}{g:02x}{b:02x}{a:02x}")
+        })
+    }
+
+    // the `// background: ...` line, if the map has its own
+    pub fn background_line(&self) -> Option<String> {
+        let [r, g, b, _] = self.background?.as_rgba_u8();
+        Some(format!("// background: #{r:02x}{g:02x}{b:02x}"))
+    }
+
+    // the `// wind: ...`, `// camera: ...` and `// platform: ...` lines, in the form parse reads
+    // back
+    pub fn zone_lines(&self) -> impl Iterator<Item = String> + '_ {
+        let winds = (self.winds.iter()).map(|(area, push)| {
+            let (min, size) = (area.min, area.size());
+            format!(
+                "// wind: {} {} {} {} {} {}",
+                min.x, min.y, size.x, size.y, push.x, push.y
+            )
+        });
+        let cameras = (self.cameras.iter()).map(|(area, target, scale)| {
+            let (min, size) = (area.min, area.size());
+            format!(
+                "// camera: {} {} {} {} {} {} {scale}",
+                min.x, min.y, size.x, size.y, target.x, target.y
+            )
+        });
+        let platforms = (self.platforms.iter()).map(|(area, travel, period)| {
+            let (min, size) = (area.min, area.size());
+            format!(
+                "// platform: {} {} {} {} {} {} {period}",
+                min.x, min.y, size.x, size.y, travel.x, travel.y
+            )
+        });
+        winds.chain(cameras).chain(platforms)
+    }
+
+    // the `// decor: ...` and `// composite: ...` lines for tiles that aren't plain solid tiles,
+    // in the form parse reads back
+    pub fn tile_lines(&self) -> impl Iterator<Item = String> + '_ {
+        let decor = (self.decor.iter()).map(|pos| format!("// decor: {} {}", pos.x, pos.y));
+        let composite =
+            (self.composite.iter()).map(|pos| format!("// composite: {} {}", pos.x, pos.y));
+        decor.chain(composite)
+    }
+
+    // whether the tile centered at pos is only decoration
+    pub fn is_decor(&self, pos: Vec2) -> bool {
+        self.has_cell(&self.decor, pos)
+    }
+
+    // whether the tile centered at pos is a composite one
+    pub fn is_composite(&self, pos: Vec2) -> bool {
+        self.has_cell(&self.composite, pos)
+    }
+
+    fn has_cell(&self, cells: &[Vec2], pos: Vec2) -> bool {
+        let cell = world_to_tile(self.origin, Tile::SZ, pos);
+        (cells.iter()).any(|p| world_to_tile(self.origin, Tile::SZ, *p) == cell)
+    }
+
+    // label of the row at world height y, if it has one
+    pub fn label_at(&self, y: f32) -> Option<&str> {
+        let top = self.origin.y + (self.height() as f32 - 1.) * Tile::SZ;
+        let row = ((top - y) / Tile::SZ).round();
+        (self.labels.iter())
+            .find(|(r, _)| *r as f32 == row)
+            .map(|(_, label)| label.as_str())
+    }
+
+    pub fn height(&self) -> usize {
+        self.data.len() / self.width
+    }
+
+    // center of the grid cell pos is in, the grid lines up with the map's tiles rather than the
+    // world origin, since a map saved from the editor can have any origin
+    pub fn snap(&self, pos: Vec2) -> Vec2 {
+        tile_to_world(
+            self.origin,
+            Tile::SZ,
+            world_to_tile(self.origin, Tile::SZ, pos),
+        )
+    }
+
+    pub fn background_color(&self) -> Color {
+        self.background.unwrap_or(Color::BLACK)
+    }
+
+    // the palette color a tile is drawn in, magenta like bmp_color for an id the palette doesn't
+    // have so the stray tile stands out
+    pub fn tile_color(&self, tile: Tile) -> Color {
+        (self.palette.get(tile.0 as usize).copied()).unwrap_or(Color::rgb(1., 0., 1.))
+    }
+
+    // y of the top edge of the map
+    pub fn top(&self) -> f32 {
+        (self.height() as f32) * Tile::SZ + self.origin.y
+    }
+
+    // every non-empty tile, and its position in the world
+    pub fn tiles(&self) -> impl Iterator<Item = (Tile, Vec2)> + '_ {
+        let width = self.width;
+        (self.data.iter().rev().enumerate())
+            .filter(|(_, &t)| t != 0)
+            .map(move |(i, &t)| {
+                let (x, y) = (width - (i % width) - 1, i / width);
+                let cell = IVec2::new(x as i32, y as i32);
+                (Tile(t), tile_to_world(self.origin, Tile::SZ, cell))
+            })
+    }
+}
+
+// the grid cell a world position is in, for a grid of size wide cells with cell (0, 0) centered on
+// origin. x goes right and y up, like the world
+pub fn world_to_tile(origin: Vec2, size: f32, pos: Vec2) -> IVec2 {
+    ((pos - origin) / size).round().as_ivec2()
+}
+
+// the center of a grid cell, the other way from world_to_tile
+pub fn tile_to_world(origin: Vec2, size: f32, cell: IVec2) -> Vec2 {
+    origin + cell.as_vec2() * size
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    let s = s.trim();
+    s.parse().map_err(|e| format!("bad number {s:?}: {e}"))
+}
+
+// a row's comment without the row number save_map puts in front of it, so `// 12 2nd floor` is
+// labelled `2nd floor`
+fn row_label(note: &str) -> &str {
+    let note = note.trim();
+    let (first, rest) = note.split_once(' ').unwrap_or((note, ""));
+    if first.parse::<usize>().is_ok() {
+        rest.trim()
+    } else {
+        note
+    }
+}
+
+// `<x> <y>`
+fn parse_point(val: &str) -> Result<Vec2, String> {
+    let nums = (val.split_whitespace())
+        .map(num::<f32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let &[x, y] = nums.as_slice() else {
+        return Err(format!("{val:?} should be `<x> <y>`"));
+    };
+    Ok(Vec2::new(x, y))
+}
+
+// `<x> <y> <w> <h> <fx> <fy>`
+fn parse_wind(val: &str) -> Result<(Rect, Vec2), String> {
+    let nums = (val.split_whitespace())
+        .map(num::<f32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let &[x, y, w, h, fx, fy] = nums.as_slice() else {
+        return Err(format!(
+            "wind {val:?} should be `<x> <y> <w> <h> <fx> <fy>`"
+        ));
+    };
+    let min = Vec2::new(x, y);
+    Ok((
+        Rect::from_corners(min, min + Vec2::new(w, h)),
+        Vec2::new(fx, fy),
+    ))
+}
+
+// `<x> <y> <w> <h> <tx> <ty> <scale>`
+fn parse_camera(val: &str) -> Result<(Rect, Vec2, f32), String> {
+    let nums = (val.split_whitespace())
+        .map(num::<f32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let &[x, y, w, h, tx, ty, scale] = nums.as_slice() else {
+        return Err(format!(
+            "camera {val:?} should be `<x> <y> <w> <h> <tx> <ty> <scale>`"
+        ));
+    };
+    if scale.is_nan() || scale <= 0. {
+        return Err(format!("camera scale {scale} should be more than 0"));
+    }
+    let min = Vec2::new(x, y);
+    Ok((
+        Rect::from_corners(min, min + Vec2::new(w, h)),
+        Vec2::new(tx, ty),
+        scale,
+    ))
+}
+
+// `<x> <y> <w> <h> <dx> <dy> <period>`
+fn parse_platform(val: &str) -> Result<(Rect, Vec2, f32), String> {
+    let nums = (val.split_whitespace())
+        .map(num::<f32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let &[x, y, w, h, dx, dy, period] = nums.as_slice() else {
+        return Err(format!(
+            "platform {val:?} should be `<x> <y> <w> <h> <dx> <dy> <period>`"
+        ));
+    };
+    if period.is_nan() || period <= 0. {
+        return Err(format!("platform period {period} should be more than 0"));
+    }
+    let min = Vec2::new(x, y);
+    Ok((
+        Rect::from_corners(min, min + Vec2::new(w, h)),
+        Vec2::new(dx, dy),
+        period,
+    ))
+}
+
+// `<id> <kind> #rrggbbaa`, the kind is only there to read the file by, colliders come from the id
+// so it has to be the id's kind
+fn parse_tile(val: &str) -> Result<(Tile, Color), String> {
+    let mut parts = val.split_whitespace();
+    let (Some(id), Some(kind), Some(hex), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("tile {val:?} should be `<id> <kind> #rrggbbaa`"));
+    };
+    let tile = match id.parse::<u8>() {
+        Ok(id) if (1..Tile::NUM).contains(&(id as usize)) => Tile(id),
+        _ => return Err(format!("no tile with id {id:?}")),
+    };
+    if tile.kind() != kind {
+        return Err(format!("tile {id} is {}, not {kind}", tile.kind()));
+    }
+    Ok((tile, parse_color(hex)?))
+}
+
+fn parse_color(hex: &str) -> Result<Color, String> {
+    let hex = hex.trim();
+    Color::hex(hex).map_err(|e| format!("bad color {hex:?}: {e:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bmp_color_falls_back_to_magenta() {
+        assert_eq!(bmp_color(Tile::SQUARE.0), [0xff, 0xff, 0xff]);
+        assert_eq!(bmp_color(Tile::NUM as u8 - 1), BMP_PALETTE[Tile::NUM - 1]);
+        assert_eq!(bmp_color(Tile::NUM as u8), [0xff, 0x00, 0xff]);
+        assert_eq!(bmp_color(u8::MAX), [0xff, 0x00, 0xff]);
+    }
+
+    #[test]
+    fn world_and_tile_coordinates_round_trip() {
+        for origin in [Vec2::ZERO, Vec2::new(-250., -500.), Vec2::new(13.5, -7.25)] {
+            for x in -20..=20 {
+                for y in -20..=20 {
+                    let cell = IVec2::new(x, y);
+                    let center = tile_to_world(origin, Tile::SZ, cell);
+                    assert_eq!(world_to_tile(origin, Tile::SZ, center), cell);
+                    // anywhere inside the cell is that cell
+                    let near = Vec2::splat(Tile::SZ / 2. - 0.01);
+                    assert_eq!(world_to_tile(origin, Tile::SZ, center - near), cell);
+                    assert_eq!(world_to_tile(origin, Tile::SZ, center + near), cell);
+                }
+            }
+        }
+        assert_eq!(
+            world_to_tile(Vec2::ZERO, 10., Vec2::new(4.9, -5.1)),
+            IVec2::new(0, -1)
+        );
+    }
+
+    #[test]
+    fn row_labels_keep_their_own_numbers() {
+        let src = "const MAP: (Vec2, usize, [u8; 8]) = (
+  Vec2::new(0.0, 0.0),
+  2,
+  [
+    1, 1, // 0 2nd floor
+    1, 1, // 1
+    1, 1, // 2 3 rooms
+    1, 1, // 10
+  ],
+);";
+        let level = Level::parse(src).unwrap();
+        let labels = [(0, "2nd floor".to_string()), (2, "3 rooms".to_string())];
+        assert_eq!(level.labels, labels);
+        assert_eq!(Level::parse_text(&level.to_text()).unwrap().labels, labels);
+        assert_eq!(row_label(" 12 2nd floor"), "2nd floor");
+        assert_eq!(row_label(" 2nd floor"), "2nd floor");
+        assert_eq!(row_label(" 12"), "");
+    }
+
+    #[test]
+    fn decor_and_composite_survive_both_formats() {
+        let mut level = Level::from_grid(Vec2::new(-50., 0.), 3, vec![1, 1, 1]).unwrap();
+        level.decor = vec![Vec2::new(0., 0.)];
+        level.composite = vec![Vec2::new(50., 0.)];
+        let text = level.to_text();
+        assert!(text.contains("decor: 0 0\ncomposite: 50 0\n"), "{text}");
+        let reread = Level::parse_text(&text).unwrap();
+        assert_eq!(reread.decor, level.decor);
+        assert_eq!(reread.composite, level.composite);
+        assert!(reread.is_decor(Vec2::new(0., 0.)) && !reread.is_decor(Vec2::new(-50., 0.)));
+        assert!(reread.is_composite(Vec2::new(50., 0.)) && !reread.is_composite(Vec2::ZERO));
+        let src = format!(
+            "{}\nconst MAP: (Vec2, usize, [u8; 3]) = (Vec2::new(-50.0, 0.0), 3, [1, 1, 1]);",
+            level.tile_lines().collect::<Vec<_>>().join("\n")
+        );
+        let parsed = Level::parse(&src).unwrap();
+        assert_eq!(parsed.decor, level.decor);
+        assert_eq!(parsed.composite, level.composite);
+    }
+
+    #[test]
+    fn text_format_is_stable() {
+        // every kind of tile once, with a label and some metadata
+        let data = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 1, 1, 1];
+        let mut level = Level::from_grid(Vec2::new(-75., 25.), 6, data).unwrap();
+        level.name = Some("kinds".into());
+        level.labels = vec![(1, "floor".into())];
+        level.decor = vec![Vec2::new(-75., 25.)];
+        let text = level.to_text();
+        assert!(text.starts_with("name: kinds\n"), "{text}");
+        assert!(
+            text.ends_with("map -75.0 25.0 6\n.#/\\rl\n^_%### floor\n"),
+            "{text}"
+        );
+        let reread = Level::parse_text(&text).unwrap();
+        assert_eq!((reread.origin, reread.width), (level.origin, level.width));
+        assert_eq!(reread.data, level.data);
+        assert_eq!(reread.to_text(), text);
+        // and the built in map, wrapped lines and all, comes out a line per row
+        let builtin = Level::builtin();
+        let text = builtin.to_text();
+        let rows: Vec<_> = text
+            .lines()
+            .skip_while(|l| !l.starts_with("map "))
+            .skip(1)
+            .collect();
+        assert_eq!(rows.len(), builtin.height());
+        assert!(rows
+            .iter()
+            .all(|r| r.chars().take_while(|c| *c != ' ').count() == 27));
+        assert_eq!(Level::parse_text(&text).unwrap().to_text(), text);
+    }
+
+    #[test]
+    fn painting_lands_on_the_loaded_lattice() {
+        // an origin 20 off a multiple of the tile size each way
+        let level = Level::from_grid(Vec2::new(-230., -470.), 3, vec![1, 0, 1, 1, 1, 1]).unwrap();
+        let loaded: Vec<_> = level.tiles().map(|(_, pos)| pos).collect();
+        // anywhere in the empty cell, even right by its edges, paints its center
+        let empty = Vec2::new(-180., -420.);
+        assert!(!loaded.contains(&empty));
+        for off in [
+            Vec2::ZERO,
+            Vec2::splat(24.),
+            Vec2::splat(-24.),
+            Vec2::new(24., -24.),
+        ] {
+            assert_eq!(level.snap(empty + off), empty, "{off}");
+        }
+        // and clicking on a loaded tile finds exactly where it is, not the world grid's cell
+        for &pos in &loaded {
+            assert_eq!(level.snap(pos + Vec2::new(13., -7.)), pos);
+            assert_ne!((pos / Tile::SZ).round() * Tile::SZ, pos);
+        }
+        // cells outside the map are on the same lattice too
+        let far = level.snap(Vec2::new(1000., 1000.));
+        let cells = (far - level.origin) / Tile::SZ;
+        assert_eq!(cells, cells.round());
+    }
+
+    #[test]
+    fn palette_and_texture_travel_with_the_map() {
+        let mut level = Level::from_grid(Vec2::ZERO, 2, vec![1, 6, 8, 0]).unwrap();
+        level.palette[1] = Color::rgba_u8(0x12, 0x34, 0x56, 0x78);
+        level.palette[6] = Color::rgb_u8(0xff, 0x00, 0x80);
+        level.texture = Some("tiles/cave.png".into());
+        let rgba = |l: &Level| l.palette.map(|c| c.as_rgba_u8());
+        let text = Level::parse_text(&level.to_text()).unwrap();
+        let src = format!(
+            "// texture: tiles/cave.png\n{}\nconst MAP: (Vec2, usize, [u8; 4]) = (Vec2::new(0.0, 0.0), 2, [1, 6, 8, 0]);",
+            level.palette_lines().collect::<Vec<_>>().join("\n")
+        );
+        let src = Level::parse(&src).unwrap();
+        for reread in [text, src] {
+            assert_eq!(rgba(&reread), rgba(&level));
+            assert_eq!(reread.texture, level.texture);
+            assert_eq!(reread.data, level.data);
+        }
+        // a map that doesn't say keeps the built in colors
+        let plain = Level::parse_text("map 0 0 2\n#^\n%.\n").unwrap();
+        assert_eq!(
+            plain.palette.map(|c| c.as_rgba_u8()),
+            PALETTE.map(|c| c.as_rgba_u8())
+        );
+        assert_eq!(plain.texture, None);
+    }
+
+    #[test]
+    fn tiles_that_dont_fill_their_rows_are_rejected() {
+        let ragged = Level::from_grid(Vec2::ZERO, 3, vec![1; 7]);
+        assert_eq!(ragged.err().unwrap(), "7 tiles don't fill rows of 3");
+        assert!(Level::from_grid(Vec2::ZERO, 0, vec![]).is_err());
+        assert_eq!(
+            Level::from_grid(Vec2::ZERO, 3, vec![1; 6])
+                .unwrap()
+                .height(),
+            2
+        );
+        assert_eq!(
+            Level::from_grid(MAP.0, MAP.1, MAP.2.to_vec())
+                .unwrap()
+                .height(),
+            112
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+
+use crate::level::{Control, GameSet, MapBounds};
+use crate::map::{world_to_tile, Level};
+use crate::physics::Tile;
+use crate::AppState;
+
+// M shows a small picture of the whole map in the bottom right corner, one pixel per tile in the
+// level's palette, with a dot where the player is
+pub struct MinimapPlugin;
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Game), setup_minimap)
+            .add_systems(
+                Update,
+                (toggle_minimap, draw_minimap, move_marker)
+                    .chain()
+                    .after(GameSet::Move)
+                    .run_if(in_state(AppState::Game)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Minimap;
+
+#[derive(Component)]
+pub struct MinimapMarker;
+
+impl Minimap {
+    // the longest the map is drawn on screen, in pixels
+    const MAX_SIZE: Vec2 = Vec2::new(120., 160.);
+    const MARKER: f32 = 4.;
+
+    // where pos is across the map, from (0, 0) at the top left corner to (1, 1) at the bottom right
+    // like ui percentages. off the map is clamped to its edge
+    pub fn fraction(bounds: Rect, pos: Vec2) -> Vec2 {
+        let f = ((pos - bounds.min) / bounds.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        let f = Vec2::new(f.x, 1. - f.y);
+        if f.is_finite() {
+            f
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    // one pixel per tile cell of the bounds, transparent where there's no tile
+    fn image(bounds: Rect, level: &Level) -> Image {
+        let size = (bounds.size() / Tile::SZ).round().max(Vec2::ONE).as_uvec2();
+        let mut data = vec![0; (size.x * size.y * 4) as usize];
+        for (tile, pos) in level.tiles() {
+            let cell = world_to_tile(bounds.min + Tile::SZ / 2., Tile::SZ, pos);
+            if cell.x < 0 || cell.y < 0 || cell.x >= size.x as i32 || cell.y >= size.y as i32 {
+                continue;
+            }
+            let (x, y) = (cell.x as u32, size.y - 1 - cell.y as u32);
+            let i = ((y * size.x + x) * 4) as usize;
+            let color = level.tile_color(tile).as_rgba_u8();
+            data[i..i + 4].copy_from_slice(&color);
+        }
+        let mut image = Image::new(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        image.sampler = ImageSampler::nearest();
+        image
+    }
+
+    // screen size for a map of this many tiles, as big as fits in MAX_SIZE without stretching
+    fn screen_size(tiles: Vec2) -> Vec2 {
+        let px = (Self::MAX_SIZE / tiles.max(Vec2::ONE))
+            .min_element()
+            .min(4.);
+        tiles * px
+    }
+}
+
+pub fn setup_minimap(mut commands: Commands) {
+    commands
+        .spawn((
+            Minimap,
+            ImageBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.),
+                    right: Val::Px(10.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.5).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|map| {
+            map.spawn((
+                MinimapMarker,
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(Minimap::MARKER),
+                        height: Val::Px(Minimap::MARKER),
+                        margin: UiRect::all(Val::Px(-Minimap::MARKER / 2.)),
+                        ..default()
+                    },
+                    background_color: Color::RED.into(),
+                    ..default()
+                },
+            ));
+        });
+}
+
+pub fn toggle_minimap(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut map: Query<&mut Visibility, With<Minimap>>,
+) {
+    if !kbd.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    for mut vis in &mut map {
+        *vis = match *vis {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+// redrawn whenever the bounds change, which is whenever a level is loaded
+pub fn draw_minimap(
+    bounds: Res<MapBounds>,
+    level: Res<Level>,
+    mut images: ResMut<Assets<Image>>,
+    mut map: Query<(&mut UiImage, &mut Style), With<Minimap>>,
+) {
+    if !bounds.is_changed() && !level.is_changed() {
+        return;
+    }
+    for (mut img, mut style) in &mut map {
+        let size = Minimap::screen_size(bounds.0.size() / Tile::SZ);
+        style.width = Val::Px(size.x);
+        style.height = Val::Px(size.y);
+        img.texture = images.add(Minimap::image(bounds.0, &level));
+    }
+}
+
+pub fn move_marker(
+    bounds: Res<MapBounds>,
+    ctl: Query<&Transform, With<Control>>,
+    mut marker: Query<&mut Style, With<MinimapMarker>>,
+) {
+    let Ok(t) = ctl.get_single() else {
+        return;
+    };
+    let f = Minimap::fraction(bounds.0, t.translation.xy());
+    for mut style in &mut marker {
+        style.left = Val::Percent(100. * f.x);
+        style.top = Val::Percent(100. * f.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn the_marker_lands_on_the_players_pixel() {
+        // open_level with a tile in its top left corner, 11 by 20 tiles is 44 by 80 pixels
+        let mut level = crate::tests::open_level();
+        level.data[0] = Tile::SQUARE.0;
+        let mut app = crate::tests::game(level);
+        let mut map = app.world.query_filtered::<&Style, With<Minimap>>();
+        let style = map.single(&app.world);
+        assert_eq!((style.width, style.height), (Val::Px(44.), Val::Px(80.)));
+        let mut pixel = |world: Vec2| {
+            let mut ctl = app.world.query_filtered::<&mut Transform, With<Control>>();
+            ctl.single_mut(&mut app.world).translation = world.extend(0.);
+            app.world.run_system_once(move_marker);
+            let mut marker = app.world.query_filtered::<&Style, With<MinimapMarker>>();
+            let style = marker.single(&app.world);
+            let (Val::Percent(x), Val::Percent(y)) = (style.left, style.top) else {
+                panic!("marker at {:?} {:?}", style.left, style.top);
+            };
+            Vec2::new(x * 44., y * 80.) / 100.
+        };
+        // the map runs from (-275, -525) to (275, 475), with y down on screen
+        assert_eq!(pixel(Vec2::new(-275., 475.)), Vec2::ZERO);
+        assert_eq!(pixel(Vec2::new(-137.5, 225.)), Vec2::new(11., 20.));
+        assert_eq!(pixel(Vec2::new(0., -25.)), Vec2::new(22., 40.));
+        // past the edge stays on the edge
+        assert_eq!(pixel(Vec2::new(1000., -2000.)), Vec2::new(44., 80.));
+    }
+
+    #[test]
+    fn unknown_tile_ids_are_magenta() {
+        // a square in the bottom left corner and an id past the palette next to it
+        let mut level = Level::from_grid(Vec2::ZERO, 2, vec![Tile::SQUARE.0, 200]).unwrap();
+        let bounds = Rect::new(-25., -25., 75., 25.);
+        let image = Minimap::image(bounds, &level);
+        assert_eq!(image.data[..4], level.tile_color(Tile::SQUARE).as_rgba_u8());
+        assert_eq!(image.data[4..], [0xff, 0x00, 0xff, 0xff]);
+        // not the color the id would wrap around to
+        level.data[1] = 200 % Tile::NUM as u8;
+        assert_ne!(
+            Minimap::image(bounds, &level).data[4..],
+            [0xff, 0x00, 0xff, 0xff]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::{prelude::*, window::PresentMode, window::PrimaryWindow};
+use std::time::{Duration, Instant};
+
+// vsync is on by default, V toggles it. BABY_FPS=<n> also caps the frame rate at n by sleeping
+// off whatever is left of each frame, for when vsync is off or the driver ignores it
+pub struct PacePlugin;
+impl Plugin for PacePlugin {
+    fn build(&self, app: &mut App) {
+        let cap = match std::env::var("BABY_FPS").map(|fps| fps.parse::<f32>()) {
+            Ok(Ok(fps)) if fps > 0. => Some(Duration::from_secs_f32(1. / fps)),
+            Ok(_) => {
+                eprintln!("BABY_FPS should be a number of frames a second, not capping");
+                None
+            }
+            Err(_) => None,
+        };
+        app.insert_resource(FrameCap(cap))
+            .add_systems(Update, toggle_vsync)
+            .add_systems(Last, limit_frame);
+    }
+}
+
+#[derive(Resource)]
+pub struct FrameCap(pub Option<Duration>);
+
+pub fn toggle_vsync(
+    kbd: Res<ButtonInput<KeyCode>>,
+    mut win: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !kbd.just_pressed(KeyCode::KeyV) {
+        return;
+    }
+    for mut win in &mut win {
+        win.present_mode = match win.present_mode {
+            PresentMode::AutoNoVsync | PresentMode::Immediate | PresentMode::Mailbox => {
+                PresentMode::AutoVsync
+            }
+            _ => PresentMode::AutoNoVsync,
+        };
+        info!("present mode {:?}", win.present_mode);
+    }
+}
+
+pub fn limit_frame(cap: Res<FrameCap>, mut last: Local<Option<Instant>>) {
+    let Some(cap) = cap.0 else {
+        return;
+    };
+    if let Some(left) = last.and_then(|last| cap.checked_sub(last.elapsed())) {
+        std::thread::sleep(left);
+    }
+    *last = Some(Instant::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn v_toggles_vsync() {
+        let mut world = World::new();
+        // as main opens it
+        let window = Window {
+            present_mode: PresentMode::AutoVsync,
+            ..default()
+        };
+        let win = (world.spawn((window, PrimaryWindow))).id();
+        let press_v = |world: &mut World| {
+            let mut kbd = ButtonInput::<KeyCode>::default();
+            kbd.press(KeyCode::KeyV);
+            world.insert_resource(kbd);
+            world.run_system_once(toggle_vsync);
+            world.get::<Window>(win).unwrap().present_mode
+        };
+        assert_eq!(press_v(&mut world), PresentMode::AutoNoVsync);
+        assert_eq!(press_v(&mut world), PresentMode::AutoVsync);
+        // nothing pressed leaves it be
+        world.insert_resource(ButtonInput::<KeyCode>::default());
+        world.run_system_once(toggle_vsync);
+        assert_eq!(
+            world.get::<Window>(win).unwrap().present_mode,
+            PresentMode::AutoVsync
+        );
+    }
+
+    #[test]
+    fn cap_sleeps_off_the_rest_of_the_frame() {
+        let mut world = World::new();
+        world.insert_resource(FrameCap(Some(Duration::from_millis(50))));
+        let mut limit = IntoSystem::into_system(limit_frame);
+        limit.initialize(&mut world);
+        limit.run((), &mut world);
+        let start = Instant::now();
+        limit.run((), &mut world);
+        assert!(
+            start.elapsed() >= Duration::from_millis(45),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
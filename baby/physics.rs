@@ -0,0 +1,1385 @@
+// the player physics, kept free of ecs queries so it can be run outside of the game
+// (see bench_collide.rs)
+use bevy::{
+    math::bounding::{Aabb2d, BoundingVolume, IntersectsVolume},
+    prelude::*,
+};
+
+#[derive(Component, Default, Debug)]
+pub struct Movement {
+    pub ctl: Vec2,
+    pub force: Vec2,
+    pub out: Vec2,
+    pub climb: bool,
+    pub grounded: bool,
+    // which side the wall the player is pressed against is on, -1. left, 1. right, 0. no wall
+    pub wall_dir: f32,
+    // ground distance covered since the last footstep
+    pub step_dist: f32,
+    // ducking, with a shorter box and slower run
+    pub crouch: bool,
+    // sideways speed a slope added to the last tick's move, slopes push out diagonally so
+    // sliding down one moves the player sideways without it being in ctl or force
+    pub slide: f32,
+    // seconds since last on the ground, and seconds left on a jump pressed too early
+    pub air_time: f32,
+    pub jump_buffer: f32,
+    // push from wind zones this tick, it moves the player like ctl does but isn't kept
+    pub wind: Vec2,
+    // which side the ledge the player is hanging from is on, like wall_dir, 0. when not hanging
+    pub hang: f32,
+    // the moving platform the player is standing on, which carries them along wherever it goes
+    // (down included, so they don't drop off and land again) until they jump or walk off
+    pub platform: Option<Entity>,
+    // jumps left before landing again, and seconds until dash works again
+    pub air_jumps: u32,
+    pub dash_cooldown: f32,
+}
+// all the movement tunables in one place
+// speeds are in pixels per physics tick, accelerations in pixels per tick per tick
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct GameFeel {
+    pub gravity: f32,
+    // upward speed while jump is held
+    pub jump_impulse: f32,
+    // fraction of upward speed kept when jump is released mid-rise. NaN doesn't cut at all,
+    // leaving whatever push the player already had
+    pub jump_cut: f32,
+    // seconds after leaving the ground a jump still works, and seconds before landing a jump
+    // press is remembered for. classic lets jump go at any time, so both are infinite there
+    pub coyote: f32,
+    pub jump_buffer: f32,
+    pub max_fall: f32,
+    // max fall speed while pressed against a wall in the air
+    pub wall_slide: f32,
+    pub run_speed: f32,
+    pub run_accel: f32,
+    pub run_decel: f32,
+    pub air_accel: f32,
+    // fraction of the slide kept as sideways speed when sliding off the end of a slope, and the
+    // slide speed it takes, so a slow walk off the end still just drops
+    pub slope_launch: f32,
+    pub slope_launch_min: f32,
+    // fraction of gravity while in the air and moving slower than apex_speed up or down, so
+    // the top of a jump hangs for a moment
+    pub apex_gravity: f32,
+    pub apex_speed: f32,
+    // how far below the top of the player the lip of a wall it's pushing into while falling can
+    // be for it to grab on and hang there. 0 never grabs
+    pub ledge_reach: f32,
+    // extra jumps in the air, given back on landing
+    pub air_jumps: u32,
+    // seconds from the start of a dash before the next one can go
+    pub dash_cooldown: f32,
+}
+#[derive(Component, Deref, DerefMut, Clone, Copy, Debug, PartialEq)]
+pub struct Tile(pub u8);
+
+impl Tile {
+    pub const SZ: f32 = 50.;
+    pub const NUM: usize = 9;
+    pub const SQUARE: Tile = Tile(1);
+    pub const STEPR: Tile = Tile(2);
+    pub const STEPL: Tile = Tile(3);
+    pub const SLOPER: Tile = Tile(4);
+    pub const SLOPEL: Tile = Tile(5);
+    // solid like a square, but hurts
+    pub const HAZARD: Tile = Tile(6);
+    // solid like a square, standing on it sends PlatePressed
+    pub const PLATE: Tile = Tile(7);
+    // solid like a square, until it's landed on hard
+    pub const BREAK: Tile = Tile(8);
+
+    // what each id collides as, by name, for map files
+    pub const KINDS: [&'static str; Tile::NUM] = [
+        "empty", "square", "stepr", "stepl", "sloper", "slopel", "hazard", "plate", "break",
+    ];
+
+    pub fn kind(self) -> &'static str {
+        Tile::KINDS
+            .get(self.0 as usize)
+            .copied()
+            .unwrap_or("unknown")
+    }
+
+    // health lost on touching this tile
+    pub fn damage(self) -> f32 {
+        match self {
+            Tile::HAZARD => 1.,
+            _ => 0.,
+        }
+    }
+}
+
+impl GameFeel {
+    // the original tuning: instant run speed, jump stops rising as soon as it's released
+    pub const CLASSIC: GameFeel = GameFeel {
+        gravity: 9.8 / 60.,
+        jump_impulse: 5.,
+        jump_cut: f32::NAN,
+        coyote: f32::INFINITY,
+        jump_buffer: f32::INFINITY,
+        max_fall: f32::INFINITY,
+        wall_slide: f32::INFINITY,
+        run_speed: 5.,
+        run_accel: f32::INFINITY,
+        run_decel: f32::INFINITY,
+        air_accel: f32::INFINITY,
+        slope_launch: 0.,
+        slope_launch_min: f32::INFINITY,
+        apex_gravity: 1.,
+        apex_speed: 0.,
+        ledge_reach: 0.,
+        air_jumps: 0,
+        dash_cooldown: 0.,
+    };
+    pub const FLOATY: GameFeel = GameFeel {
+        gravity: 6. / 60.,
+        jump_impulse: 4.,
+        jump_cut: 0.6,
+        coyote: 0.12,
+        jump_buffer: 0.12,
+        max_fall: 8.,
+        wall_slide: 2.,
+        run_speed: 4.,
+        run_accel: 0.4,
+        run_decel: 0.2,
+        air_accel: 0.15,
+        slope_launch: 0.8,
+        slope_launch_min: 2.,
+        apex_gravity: 0.5,
+        apex_speed: 1.,
+        ledge_reach: 10.,
+        air_jumps: 1,
+        dash_cooldown: 0.6,
+    };
+    pub const SNAPPY: GameFeel = GameFeel {
+        gravity: 14. / 60.,
+        jump_impulse: 6.,
+        jump_cut: 0.,
+        coyote: 0.08,
+        jump_buffer: 0.1,
+        max_fall: 12.,
+        wall_slide: 3.,
+        run_speed: 5.,
+        run_accel: 1.5,
+        run_decel: 2.5,
+        air_accel: 1.,
+        slope_launch: 1.,
+        slope_launch_min: 2.,
+        apex_gravity: 0.5,
+        apex_speed: 1.5,
+        ledge_reach: 8.,
+        air_jumps: 1,
+        dash_cooldown: 0.4,
+    };
+
+    pub fn preset(name: &str) -> Option<GameFeel> {
+        match name {
+            "classic" => Some(Self::CLASSIC),
+            "floaty" => Some(Self::FLOATY),
+            "snappy" => Some(Self::SNAPPY),
+            _ => None,
+        }
+    }
+
+    // pick a preset with BABY_FEEL=<name>, falling back to classic
+    pub fn from_env() -> GameFeel {
+        let Ok(name) = std::env::var("BABY_FEEL") else {
+            return Self::CLASSIC;
+        };
+        Self::preset(&name).unwrap_or_else(|| {
+            eprintln!("unknown BABY_FEEL preset {name:?}, using classic");
+            Self::CLASSIC
+        })
+    }
+}
+
+// result of collide_push
+// - delta is how far to move the aabb so it no longer overlaps the tile
+// - normal is the unit direction out of the surface that was hit: up for a floor, sideways for a
+//   wall, diagonal for a ramp. it's set even when delta is too small to tell
+// - damp_h means the push was sideways, so horizontal force into the tile should be cancelled
+// - damp_v means the tile can be stood on (or bumped from below), so vertical force should be cancelled
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Push {
+    pub delta: Vec2,
+    pub normal: Vec2,
+    pub damp_h: bool,
+    pub damp_v: bool,
+}
+
+impl Push {
+    pub const NONE: Push = Push::new(Vec2::ZERO, Vec2::ZERO, false, false);
+
+    pub const fn new(delta: Vec2, normal: Vec2, damp_h: bool, damp_v: bool) -> Push {
+        Push {
+            delta,
+            normal,
+            damp_h,
+            damp_v,
+        }
+    }
+
+    // straight up or down by dy
+    fn vert(dy: f32, damp_v: bool) -> Push {
+        Push::new(Vec2::new(0., dy), Vec2::new(0., dy.signum()), false, damp_v)
+    }
+
+    // straight left or right by dx
+    fn horz(dx: f32) -> Push {
+        Push::new(Vec2::new(dx, 0.), Vec2::new(dx.signum(), 0.), true, false)
+    }
+
+    // dist along a ramp's normal
+    fn diag(normal: Vec2, dist: f32, damp_v: bool) -> Push {
+        Push::new(normal * dist, normal, false, damp_v)
+    }
+}
+
+// unit normal pointing out of the solid side of a tile's diagonal, which runs corner to corner
+// so it is only 45 degrees when the tile is square (a composite's slope is half height)
+fn diag_normal(col_aabb: &Aabb2d, left: bool) -> Vec2 {
+    let h = col_aabb.half_size();
+    if left {
+        Vec2::new(h.y, h.x).normalize()
+    } else {
+        Vec2::new(-h.y, h.x).normalize()
+    }
+}
+
+// distance from p to the diagonal through the tile's center, and whether p lies above it, on the
+// triangle's empty side. left is a |\ triangle, otherwise /|
+fn pt_line_dist(col_aabb: &Aabb2d, left: bool, p: Vec2) -> (f32, bool) {
+    let d = (p - col_aabb.center()).dot(diag_normal(col_aabb, left));
+    (d.abs(), d > 0.)
+}
+
+// whether p is inside the solid part of a tile, so the empty half of a triangle doesn't count
+pub fn tile_contains(col: &Tile, col_aabb: &Aabb2d, p: Vec2) -> bool {
+    if !col_aabb.contains(&Aabb2d::new(p, Vec2::ZERO)) {
+        return false;
+    }
+    match *col {
+        Tile::STEPL | Tile::SLOPEL => !pt_line_dist(col_aabb, true, p).1,
+        Tile::STEPR | Tile::SLOPER => !pt_line_dist(col_aabb, false, p).1,
+        _ => true,
+    }
+}
+
+// calculate how much we have to push aabb to no longer collide with col
+// for instance, if aabb is not intersection col_aabb, then we don't need to push it away at all
+// if aabb is intersecting col_aabb, col is square, and it would
+//
+// col determines the shape and characteristics:
+// - Square is a square block. standing on this dampens gravity's pull
+// - StepL/R are left or right steps
+//   the collider is the shape of left or right triangles,
+//   and they allow you to stand on them by dampening gravity
+// - SlopeL/R are left or right slopes,
+//   the collider is the shape of left or right triangles,
+//   but standing on them does not dampen gravity
+//
+pub fn collide_push(aabb: &Aabb2d, col: &Tile, col_aabb: &Aabb2d) -> Push {
+    let lt = col_aabb.min.x - aabb.max.x;
+    let rt = col_aabb.max.x - aabb.min.x;
+    let up = col_aabb.max.y - aabb.min.y;
+    let dn = col_aabb.min.y - aabb.max.y;
+    let horz = if lt.abs() < rt.abs() { lt } else { rt };
+    let vert = if dn.abs() < up.abs() { dn } else { up };
+
+    match *col {
+        Tile::SQUARE | Tile::HAZARD | Tile::PLATE | Tile::BREAK => {
+            if horz.abs() > vert.abs() {
+                Push::vert(vert, true)
+            } else {
+                Push::horz(horz)
+            }
+        }
+        Tile::STEPL | Tile::SLOPEL => {
+            // collide like a left triangle |\
+            // probe with the bottom left corner of the overlap between the two boxes, not of the
+            // player: when the player hangs over the tile's edge its own corner is outside the
+            // triangle and the line test gives a push the tile never asked for (or misses the tip)
+            let (dist, sign) = pt_line_dist(col_aabb, true, aabb.min.max(col_aabb.min));
+            if sign {
+                return Push::NONE;
+            }
+            let dampv = (*col == Tile::STEPL) || vert <= 0.;
+            let (vert_dist, vert_v) = (vert.abs(), Push::vert(vert, dampv));
+            let (horz_dist, horz_v) = (horz.abs(), Push::horz(horz));
+            let diag_v = Push::diag(diag_normal(col_aabb, true), dist, *col == Tile::STEPL);
+            if dampv && vert_dist < horz_dist && vert_dist < dist {
+                vert_v
+            } else if horz_dist < dist {
+                horz_v
+            } else {
+                diag_v
+            }
+        }
+        Tile::STEPR | Tile::SLOPER => {
+            // collide like a right triangle /|
+            // same as above, with the overlap's bottom right corner
+            let p = Vec2::new(
+                aabb.max.x.min(col_aabb.max.x),
+                aabb.min.y.max(col_aabb.min.y),
+            );
+            let (dist, sign) = pt_line_dist(col_aabb, false, p);
+            if sign {
+                return Push::NONE;
+            }
+            let dampv = (*col == Tile::STEPR) || vert <= 0.;
+            let (vert_dist, vert_v) = (vert.abs(), Push::vert(vert, dampv));
+            let (horz_dist, horz_v) = (horz.abs(), Push::horz(horz));
+            let diag_v = Push::diag(diag_normal(col_aabb, false), dist, *col == Tile::STEPR);
+            if dampv && vert_dist < horz_dist && vert_dist < dist {
+                vert_v
+            } else if horz_dist < dist {
+                horz_v
+            } else {
+                diag_v
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+// cap on the combined movement per physics tick along each axis, so stacked speed sources
+// (dash, pushes, falling) can't add up to something silly. INFINITY leaves an axis alone
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MaxVelocity(pub Vec2);
+impl Default for MaxVelocity {
+    fn default() -> Self {
+        MaxVelocity(Vec2::INFINITY)
+    }
+}
+impl MaxVelocity {
+    // ctl is capped on its own, then force gets whatever room is left. an axis under its cap is
+    // left exactly as it was, since (ctl + force) - ctl isn't always force again in floats
+    pub fn clamp(&self, v: &mut Movement) {
+        v.ctl = v.ctl.clamp(-self.0, self.0);
+        let total = v.ctl + v.force;
+        let over = total.abs().cmpgt(self.0);
+        let capped = total.clamp(-self.0, self.0) - v.ctl;
+        v.force = Vec2::select(over, capped, v.force);
+    }
+}
+
+// zero out non-finite vectors and components too small to matter
+pub fn sanitize(v: Vec2) -> Vec2 {
+    const EPSILON: f32 = 1e-4;
+    if !v.is_finite() {
+        return Vec2::ZERO;
+    }
+    Vec2::select(v.abs().cmplt(Vec2::splat(EPSILON)), Vec2::ZERO, v)
+}
+
+// true if any tile would still need to push aabb out by more than a pixel
+// touching edges and float error from earlier pushes don't count
+fn is_embedded(aabb: &Aabb2d, tiles: &[(Tile, Aabb2d)]) -> bool {
+    tiles.iter().any(|(c, col_aabb)| {
+        aabb.intersects(col_aabb) && collide_push(aabb, c, col_aabb).delta.length() > 1.
+    })
+}
+
+// when the push loop gives up (eg. a tile was placed on top of the player, or it is boxed in on
+// several sides) search outward in rings for the closest spot that doesn't overlap anything
+// returns None if aabb isn't stuck, or if nothing free was found within a few tiles
+fn unstick(aabb: &Aabb2d, tiles: &[(Tile, Aabb2d)]) -> Option<Aabb2d> {
+    if !is_embedded(aabb, tiles) {
+        return None;
+    }
+    use std::f32::consts::FRAC_1_SQRT_2 as D;
+    // prefer popping out upward, then sideways, then down
+    const DIRS: [Vec2; 8] = [
+        Vec2::new(0., 1.),
+        Vec2::new(-D, D),
+        Vec2::new(D, D),
+        Vec2::new(-1., 0.),
+        Vec2::new(1., 0.),
+        Vec2::new(-D, -D),
+        Vec2::new(D, -D),
+        Vec2::new(0., -1.),
+    ];
+    const STEP: f32 = Tile::SZ / 4.;
+    for ring in 1..=16 {
+        for dir in DIRS {
+            let center = aabb.center() + dir * STEP * ring as f32;
+            let cand = Aabb2d::new(center, aabb.half_size());
+            if !is_embedded(&cand, tiles) {
+                return Some(cand);
+            }
+        }
+    }
+    None
+}
+
+// a push that happened during a step, and the player's velocity going into it
+#[derive(Debug)]
+pub struct Contact {
+    pub pass: usize,
+    pub tile: Tile,
+    pub push: Push,
+    pub vel: Vec2,
+}
+
+pub struct Step {
+    pub aabb: Aabb2d,
+    // every tile overlapping the moved aabb (after the last sub-move), closest first
+    pub collisions: Vec<(Tile, Aabb2d)>,
+    pub contacts: Vec<Contact>,
+}
+
+// longest move that's collision tested in one go, anything faster is split into equal sub-moves
+// so it can't skip over a tile (a tile is SZ across, the player a bit less)
+pub const SUBSTEP_DIST: f32 = Tile::SZ / 2.;
+// gap left between the player and whatever it's resting against. collisions are resolved with
+// the box grown by this much, so a resting player sinks into the margin every tick and gets
+// pushed back out, rather than flickering between touching and not touching the edge itself
+pub const SKIN: f32 = 0.5;
+
+// advance the player one physics tick: apply gravity, move aabb by the player's velocity and push it
+// back out of any tiles it ended up in
+pub fn step(aabb: Aabb2d, v: &mut Movement, feel: &GameFeel, tiles: &[(Tile, Aabb2d)]) -> Step {
+    // hanging stays put until a jump, or pushing away from the wall lets go
+    if v.hang != 0. {
+        if v.ctl.y <= 0. && v.ctl.x * v.hang >= 0. {
+            v.force = Vec2::ZERO;
+            v.grounded = false;
+            v.wall_dir = v.hang;
+            return Step {
+                aabb,
+                collisions: vec![],
+                contacts: vec![],
+            };
+        }
+        v.hang = 0.;
+    }
+    let top = aabb.max.y;
+    let mut aabb = Aabb2d::new(aabb.center(), aabb.half_size() + SKIN);
+    let mut collisions = vec![];
+    let mut contacts = vec![];
+    let gravity = if !v.grounded && v.force.y.abs() < feel.apex_speed {
+        feel.gravity * feel.apex_gravity
+    } else {
+        feel.gravity
+    };
+    v.grounded = false;
+    v.wall_dir = 0.;
+    v.force += Vec2::new(0., -gravity);
+    v.force.y = v.force.y.max(-feel.max_fall);
+    // the upper bound keeps a huge velocity from stalling the game (and NaN casts to no sub-moves)
+    let substeps = ((v.ctl + v.force + v.wind).length() / SUBSTEP_DIST)
+        .ceil()
+        .clamp(1., 64.) as usize;
+    let (start, want) = (aabb.center().x, v.ctl.x + v.force.x + v.wind.x);
+    for _ in 0..substeps {
+        // pushes can damp force, so later sub-moves use what's left of the velocity.
+        // horizontal goes first so a walk off an edge isn't caught by the ground it just left
+        let dx = (v.ctl.x + v.force.x + v.wind.x) / substeps as f32;
+        aabb = Aabb2d::new(aabb.center() + Vec2::X * dx, aabb.half_size());
+        collisions = push_out(&mut aabb, v, tiles, &mut contacts);
+        let dy = (v.ctl.y + v.force.y + v.wind.y) / substeps as f32;
+        aabb = Aabb2d::new(aabb.center() + Vec2::Y * dy, aabb.half_size());
+        for c in push_out(&mut aabb, v, tiles, &mut contacts) {
+            if !collisions.iter().any(|h| h.2.min == c.2.min) {
+                collisions.push(c);
+            }
+        }
+    }
+    // holding toward a wall while falling past it slows the fall
+    if !v.grounded && v.wall_dir != 0. && v.ctl.x * v.wall_dir > 0. {
+        v.force.y = v.force.y.max(-feel.wall_slide);
+    }
+    let on_slope = contacts.iter().any(|c| {
+        matches!(c.tile, Tile::SLOPEL | Tile::SLOPER)
+            && c.push.normal.x != 0.
+            && c.push.normal.y != 0.
+    });
+    if on_slope {
+        // going off the end only catches a corner, which slides a lot less than the last
+        // full tick did, so hold on to the fastest slide until the player leaves
+        let slide = aabb.center().x - start - want;
+        if slide * v.slide <= 0. || slide.abs() > v.slide.abs() {
+            v.slide = slide;
+        }
+    } else {
+        // leaving a slope fast keeps going the way it was sliding instead of dropping off
+        // but only downhill, walking up and off the tip shouldn't throw the player back
+        let downhill = v.slide * (v.ctl.x + v.force.x) >= 0.;
+        if !v.grounded && downhill && v.slide.abs() >= feel.slope_launch_min {
+            v.force.x += v.slide * feel.slope_launch;
+        }
+        v.slide = 0.;
+    }
+    // nothing else pushes sideways for long, so a launch is spent once it lands
+    if v.grounded {
+        v.force.x = 0.;
+    }
+    let falling = v.ctl.y + v.force.y <= 0.;
+    if feel.ledge_reach > 0. && !v.grounded && falling && v.ctl.x * v.wall_dir > 0. {
+        let now = aabb.max.y - SKIN;
+        if let Some(lip) = ledge(
+            &aabb,
+            v.wall_dir,
+            (now - feel.ledge_reach, top.max(now)),
+            tiles,
+        ) {
+            // line the player's top up with the lip
+            let dy = Vec2::Y * (lip - now);
+            aabb = Aabb2d::new(aabb.center() + dy, aabb.half_size());
+            v.force = Vec2::ZERO;
+            v.hang = v.wall_dir;
+        }
+    }
+    if let Some(free) = unstick(&aabb, tiles) {
+        aabb = free;
+        v.force = Vec2::ZERO;
+    }
+
+    Step {
+        aabb: Aabb2d::new(aabb.center(), aabb.half_size() - SKIN),
+        collisions: (collisions.into_iter())
+            .map(|(_, c, aabb)| (c, aabb))
+            .collect(),
+        contacts,
+    }
+}
+
+// whether p is in the solid part of any of the tiles
+pub fn solid_at(tiles: &[(Tile, Aabb2d)], p: Vec2) -> bool {
+    (tiles.iter()).any(|(c, col_aabb)| tile_contains(c, col_aabb, p))
+}
+
+// join runs of squares side by side in a row into one long box each, so a long floor is a single
+// collider instead of a seam every tile for the player to catch on. other tiles are left alone,
+// they either aren't boxes or need to be hit one at a time
+pub fn merge_squares(tiles: &mut Vec<(Tile, Aabb2d)>) {
+    const EPS: f32 = 0.01;
+    let mut squares: Vec<_> = (tiles.iter())
+        .filter(|(c, _)| *c == Tile::SQUARE)
+        .map(|(_, aabb)| *aabb)
+        .collect();
+    tiles.retain(|(c, _)| *c != Tile::SQUARE);
+    squares.sort_by(|a, b| {
+        (a.min.y.total_cmp(&b.min.y))
+            .then(a.max.y.total_cmp(&b.max.y))
+            .then(a.min.x.total_cmp(&b.min.x))
+    });
+    let mut run: Option<Aabb2d> = None;
+    for aabb in squares {
+        match &mut run {
+            Some(r)
+                if (r.min.y - aabb.min.y).abs() < EPS
+                    && (r.max.y - aabb.max.y).abs() < EPS
+                    && aabb.min.x <= r.max.x + EPS =>
+            {
+                r.max.x = r.max.x.max(aabb.max.x);
+            }
+            _ => {
+                tiles.extend(run.map(|r| (Tile::SQUARE, r)));
+                run = Some(aabb);
+            }
+        }
+    }
+    tiles.extend(run.map(|r| (Tile::SQUARE, r)));
+}
+
+// join diagonal runs of the same slope, each tile's corner touching the next one's, into one big
+// triangle along the same line, so walking up a long ramp is one smooth push instead of a bump at
+// every tile. the big triangle is solid under the whole run, so a run only grows while everything
+// under it already is, and a staircase of slopes over a gap keeps its gap
+pub fn merge_slopes(tiles: &mut Vec<(Tile, Aabb2d)>) {
+    const EPS: f32 = 0.01;
+    let close = |a: Vec2, b: Vec2| (a - b).abs().max_element() < EPS;
+    for slope in [Tile::SLOPER, Tile::SLOPEL] {
+        let mut left: Vec<_> = (tiles.iter())
+            .filter(|(c, _)| *c == slope)
+            .map(|(_, aabb)| *aabb)
+            .collect();
+        if left.len() < 2 {
+            continue;
+        }
+        tiles.retain(|(c, _)| *c != slope);
+        // runs are followed from their leftmost tile
+        left.sort_by(|a, b| b.min.x.total_cmp(&a.min.x));
+        // the corner of a tile the next one up the line starts at: top right going up (/|),
+        // bottom right going down (|\)
+        type Corner = fn(&Aabb2d) -> Vec2;
+        let (from, to): (Corner, Corner) = if slope == Tile::SLOPER {
+            (|a: &Aabb2d| a.min, |a: &Aabb2d| a.max)
+        } else {
+            (
+                |a: &Aabb2d| Vec2::new(a.min.x, a.max.y),
+                |a: &Aabb2d| Vec2::new(a.max.x, a.min.y),
+            )
+        };
+        let big = |first: &Aabb2d, last: &Aabb2d| {
+            let (a, b) = (from(first), to(last));
+            Aabb2d::new((a + b) / 2., (b - a).abs() / 2.)
+        };
+        while let Some(first) = left.pop() {
+            let mut run = vec![first];
+            // only squares of the same size line up with the big triangle's diagonal
+            while let Some(i) = left.iter().position(|a| {
+                close(from(a), to(run.last().unwrap()))
+                    && close(a.half_size(), first.half_size())
+                    && filled_under(slope, &big(&first, a), &run, a, tiles)
+            }) {
+                run.push(left.remove(i));
+            }
+            tiles.push((slope, big(&first, run.last().unwrap())));
+        }
+    }
+}
+
+// whether every cell of the big triangle that isn't on its diagonal (one of run's tiles or next)
+// is filled in by tiles that are solid all the way through
+fn filled_under(
+    slope: Tile,
+    big: &Aabb2d,
+    run: &[Aabb2d],
+    next: &Aabb2d,
+    tiles: &[(Tile, Aabb2d)],
+) -> bool {
+    let cell = next.half_size() * 2.;
+    let cells = (big.half_size() * 2. / cell).round().as_ivec2();
+    let on_diagonal =
+        |c: Vec2| (run.iter().chain([next])).any(|a| (a.center() - c).abs().max_element() < 0.01);
+    // a few points around the middle of the cell, none of them on a diagonal
+    let probes =
+        [(-0.3, 0.1), (0.3, -0.1), (0.1, 0.3), (-0.1, -0.3)].map(|(x, y)| cell * Vec2::new(x, y));
+    (0..cells.x).all(|x| {
+        (0..cells.y).all(|y| {
+            let c = big.min + cell * (Vec2::new(x as f32, y as f32) + 0.5);
+            on_diagonal(c)
+                || !tile_contains(&slope, big, c)
+                || probes.iter().all(|p| solid_at(tiles, c + *p))
+        })
+    })
+}
+
+// the highest top edge of a wall beside aabb on the dir side with its top in the (low, high)
+// range, that has nothing on top of it to stop the player climbing over
+fn ledge(
+    aabb: &Aabb2d,
+    dir: f32,
+    (low, high): (f32, f32),
+    tiles: &[(Tile, Aabb2d)],
+) -> Option<f32> {
+    let x = if dir > 0. { aabb.max.x } else { aabb.min.x } + dir * SKIN;
+    (tiles.iter())
+        .map(|(_, col_aabb)| col_aabb.max.y)
+        .filter(|&lip| (low..=high).contains(&lip))
+        .filter(|&lip| solid_at(tiles, Vec2::new(x, lip - SKIN)))
+        .filter(|&lip| !solid_at(tiles, Vec2::new(x, lip + SKIN)))
+        .max_by(f32::total_cmp)
+}
+
+// boxes can only intersect if their bounding circles do, which is a cheap way to skip most
+// of the map
+fn might_touch(a: &Aabb2d, b: &Aabb2d) -> bool {
+    let r = a.half_size().length() + b.half_size().length();
+    a.center().distance_squared(b.center()) <= r * r
+}
+
+// push aabb out of the tiles it overlaps after a move, damping v as it goes
+// returns the overlapping tiles with their squared distance to aabb, closest first
+fn push_out(
+    aabb: &mut Aabb2d,
+    v: &mut Movement,
+    tiles: &[(Tile, Aabb2d)],
+    contacts: &mut Vec<Contact>,
+) -> Vec<(f32, Tile, Aabb2d)> {
+    let mut collisions = vec![];
+    // aabb has already been moved so there's no need to pad for speed
+    for &(c, col_aabb) in tiles {
+        if !might_touch(aabb, &col_aabb) {
+            continue;
+        }
+        if aabb.intersects(&col_aabb) {
+            collisions.push((
+                (col_aabb.center() - aabb.center()).length_squared(),
+                c,
+                col_aabb,
+            ));
+        }
+    }
+
+    // sort by distance to aabb
+    collisions.sort_by(|c1, c2| c1.0.total_cmp(&c2.0));
+
+    // three tries outta be enough
+    for i in 0..3 {
+        let mut pushed = false;
+        for (_, col, col_aabb) in &collisions {
+            if !aabb.intersects(col_aabb) {
+                continue;
+            }
+            let push = collide_push(aabb, col, col_aabb);
+            if push.delta == Vec2::ZERO {
+                continue;
+            }
+            contacts.push(Contact {
+                pass: i,
+                tile: *col,
+                push,
+                vel: v.ctl + v.force,
+            });
+
+            if push.damp_v {
+                if v.ctl.y > 0. && push.normal.y < 0. {
+                    v.climb = true;
+                }
+                if push.normal.y > 0. {
+                    v.grounded = true;
+                }
+                v.force.y = 0.;
+            }
+            if push.damp_h {
+                // the push points away from the wall
+                v.wall_dir = -push.normal.x;
+                if push.normal.x != v.force.x.signum() {
+                    v.force.x = 0.;
+                }
+            }
+            aabb.min += push.delta;
+            aabb.max += push.delta;
+            pushed = true;
+        }
+        if !pushed {
+            break;
+        }
+    }
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a tile's collider with its center at (x, y)
+    fn tile(c: Tile, x: f32, y: f32) -> (Tile, Aabb2d) {
+        (c, Aabb2d::new(Vec2::new(x, y), Vec2::splat(Tile::SZ / 2.)))
+    }
+
+    // a row of squares with their tops at y = 0, from x = -500 to 500
+    fn floor() -> Vec<(Tile, Aabb2d)> {
+        (-10..=10)
+            .map(|i| tile(Tile::SQUARE, i as f32 * Tile::SZ, -Tile::SZ / 2.))
+            .collect()
+    }
+
+    fn player(x: f32, bottom: f32) -> Aabb2d {
+        Aabb2d::new(Vec2::new(x, bottom + 22.5), Vec2::splat(22.5))
+    }
+
+    // ticks physics steps with v's input held throughout, as check_collide runs them at 60 a
+    // second, returning where the player ends up. v is left with the final state
+    fn simulate(
+        aabb: Aabb2d,
+        v: &mut Movement,
+        feel: &GameFeel,
+        tiles: &[(Tile, Aabb2d)],
+        ticks: u32,
+    ) -> Aabb2d {
+        (0..ticks).fold(aabb, |aabb, _| step(aabb, v, feel, tiles).aabb)
+    }
+
+    // collide_push for a 20x20 player centered at p against a tile centered on the origin
+    fn push(c: Tile, p: Vec2) -> Push {
+        let col_aabb = Aabb2d::new(Vec2::ZERO, Vec2::splat(Tile::SZ / 2.));
+        collide_push(&Aabb2d::new(p, Vec2::splat(10.)), &c, &col_aabb)
+    }
+
+    fn assert_push(got: Push, want: Push) {
+        let close = |a: Vec2, b: Vec2| (a - b).abs().max_element() < 1e-4;
+        assert!(
+            close(got.delta, want.delta)
+                && close(got.normal, want.normal)
+                && got.damp_h == want.damp_h
+                && got.damp_v == want.damp_v,
+            "got {got:?}, want {want:?}"
+        );
+    }
+
+    #[test]
+    fn push_out_of_squares() {
+        // everything that isn't a triangle collides like a square
+        for c in [Tile::SQUARE, Tile::HAZARD, Tile::PLATE, Tile::BREAK] {
+            let above = push(c, Vec2::new(0., 33.));
+            assert_push(above, Push::new(Vec2::new(0., 2.), Vec2::Y, false, true));
+            let below = push(c, Vec2::new(0., -33.));
+            assert_push(below, Push::new(Vec2::new(0., -2.), -Vec2::Y, false, true));
+            let left = push(c, Vec2::new(-33., 0.));
+            assert_push(left, Push::new(Vec2::new(-2., 0.), -Vec2::X, true, false));
+            let right = push(c, Vec2::new(33., 0.));
+            assert_push(right, Push::new(Vec2::new(2., 0.), Vec2::X, true, false));
+        }
+    }
+
+    #[test]
+    fn push_out_of_triangles() {
+        use std::f32::consts::FRAC_1_SQRT_2;
+        // the right triangles /| are the left ones |\ mirrored, so each case is checked both ways
+        let flip = |v: Vec2| Vec2::new(-v.x, v.y);
+        for (left, right, step) in [
+            (Tile::STEPL, Tile::STEPR, true),
+            (Tile::SLOPEL, Tile::SLOPER, false),
+        ] {
+            let diag = Vec2::splat(FRAC_1_SQRT_2);
+            let cases = [
+                // into the diagonal, out along its normal. steps can be stood on, slopes slide
+                (
+                    Vec2::new(5., 5.),
+                    Push::new(Vec2::splat(5.), diag, false, step),
+                ),
+                // into the upright side from outside
+                (
+                    Vec2::new(-33., -10.),
+                    Push::new(Vec2::new(-2., 0.), -Vec2::X, true, false),
+                ),
+                // up into the bottom
+                (
+                    Vec2::new(-10., -33.),
+                    Push::new(Vec2::new(0., -2.), -Vec2::Y, false, true),
+                ),
+                // only overlapping the empty half, from above the diagonal and from the open side
+                (Vec2::new(12., 12.), Push::NONE),
+                (Vec2::new(33., -10.), Push::NONE),
+            ];
+            for (p, want) in cases {
+                assert_push(push(left, p), want);
+                let mirrored = Push::new(
+                    flip(want.delta),
+                    flip(want.normal),
+                    want.damp_h,
+                    want.damp_v,
+                );
+                assert_push(push(right, flip(p)), mirrored);
+            }
+        }
+    }
+
+    #[test]
+    fn pushes_go_along_their_normal() {
+        // from every side and corner, each push is out along a unit normal the tile's shape has
+        let diag = |x: f32| Vec2::new(x, 1.).normalize();
+        for c in (1..Tile::NUM as u8).map(Tile) {
+            let mut normals = vec![Vec2::X, -Vec2::X, Vec2::Y, -Vec2::Y];
+            match c {
+                Tile::STEPL | Tile::SLOPEL => normals.push(diag(1.)),
+                Tile::STEPR | Tile::SLOPER => normals.push(diag(-1.)),
+                _ => {}
+            }
+            for x in (-30..=30).step_by(5) {
+                for y in (-30..=30).step_by(5) {
+                    let p = Vec2::new(x as f32, y as f32);
+                    let got = push(c, p);
+                    if got.delta == Vec2::ZERO {
+                        continue;
+                    }
+                    assert!(
+                        normals.iter().any(|n| (got.normal - *n).length() < 1e-4),
+                        "{c:?} at {p}: {got:?}"
+                    );
+                    let along = got.normal * got.delta.length();
+                    assert!((got.delta - along).length() < 1e-4, "{c:?} at {p}: {got:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn push_normal_is_set_for_tiny_overlaps() {
+        let touching = push(Tile::SQUARE, Vec2::new(0., 35. - 1e-3));
+        assert_eq!(touching.normal, Vec2::Y);
+        assert!(touching.delta.y > 0. && touching.delta.y < 1e-2);
+        let ramp = push(Tile::SLOPER, Vec2::new(-10., 10. - 1e-3));
+        assert!((ramp.normal - Vec2::new(-1., 1.).normalize()).length() < 1e-4);
+        assert!(ramp.delta.length() < 1e-2);
+    }
+
+    #[test]
+    fn crosses_each_ramp_both_ways() {
+        // a ramp up onto a ledge one tile high, on the right for the /| tiles and the left for |\
+        for (c, side) in [
+            (Tile::STEPR, 1.),
+            (Tile::STEPL, -1.),
+            (Tile::SLOPER, 1.),
+            (Tile::SLOPEL, -1.),
+        ] {
+            let mut tiles = floor();
+            tiles.push(tile(c, side * 100., Tile::SZ / 2.));
+            tiles.extend(
+                (3..=10).map(|i| tile(Tile::SQUARE, side * i as f32 * Tile::SZ, Tile::SZ / 2.)),
+            );
+            let walk = |from: Aabb2d, dir: f32| {
+                let mut v = Movement {
+                    ctl: Vec2::new(dir * 5., 0.),
+                    ..default()
+                };
+                let end = (0..90).fold(from, |aabb, i| {
+                    let aabb = step(aabb, &mut v, &GameFeel::CLASSIC, &tiles).aabb;
+                    assert!(
+                        !is_embedded(&aabb, &tiles),
+                        "{c:?} tick {i}: {}",
+                        aabb.center()
+                    );
+                    aabb
+                });
+                (end, v.grounded)
+            };
+            // down off the ledge and onto the floor past the ramp's foot
+            let (down, grounded) = walk(player(side * 200., Tile::SZ), -side);
+            assert!(grounded, "{c:?}");
+            assert!(
+                down.center().x * side < 50.,
+                "{c:?} stuck at {}",
+                down.center()
+            );
+            assert!(down.min.y.abs() <= SKIN, "{c:?} bottom at {}", down.min.y);
+            // steps can be walked back up, slopes only go as far as their tip
+            let (up, grounded) = walk(down, side);
+            if c == Tile::STEPR || c == Tile::STEPL {
+                assert!(grounded, "{c:?}");
+                assert!(
+                    (up.min.y - Tile::SZ).abs() <= SKIN,
+                    "{c:?} bottom at {}",
+                    up.min.y
+                );
+            } else {
+                assert!(
+                    up.center().x * side < 125.,
+                    "{c:?} climbed to {}",
+                    up.center()
+                );
+            }
+        }
+    }
+
+    // a 3 tile SLOPER ramp going up to the right from (0, 0), over squares if filled
+    fn ramp(filled: bool) -> Vec<(Tile, Aabb2d)> {
+        let mut tiles: Vec<_> = (0..3)
+            .map(|i| tile(Tile::SLOPER, i as f32 * Tile::SZ, i as f32 * Tile::SZ))
+            .collect();
+        if filled {
+            for (x, y) in [(1, 0), (2, 0), (2, 1)] {
+                tiles.push(tile(Tile::SQUARE, x as f32 * Tile::SZ, y as f32 * Tile::SZ));
+            }
+        }
+        tiles
+    }
+
+    #[test]
+    fn merges_filled_ramp() {
+        let mut tiles = ramp(true);
+        merge_slopes(&mut tiles);
+        let slopes: Vec<_> = tiles.iter().filter(|(c, _)| *c == Tile::SLOPER).collect();
+        assert_eq!(slopes.len(), 1);
+        assert_eq!(slopes[0].1.min, Vec2::splat(-Tile::SZ / 2.));
+        assert_eq!(slopes[0].1.max, Vec2::splat(2.5 * Tile::SZ));
+    }
+
+    #[test]
+    fn keeps_gap_under_ramp() {
+        // with nothing under them the slopes can't become one triangle, it'd fill the gap in
+        let mut tiles = ramp(false);
+        merge_slopes(&mut tiles);
+        assert_eq!(tiles.len(), 3);
+        assert!(!solid_at(&tiles, Vec2::new(Tile::SZ, 0.)));
+    }
+
+    #[test]
+    fn merging_keeps_builtin_map_shape() {
+        let tiles: Vec<_> = (crate::map::Level::builtin().tiles())
+            .map(|(c, pos)| (c, Aabb2d::new(pos, Vec2::splat(Tile::SZ / 2.))))
+            .collect();
+        let mut merged = tiles.clone();
+        merge_slopes(&mut merged);
+        // points off every tile's diagonal, in every cell of the map
+        for (_, aabb) in &tiles {
+            for dy in -2..=2 {
+                for dx in -2..=2 {
+                    let cell = aabb.center() + Vec2::new(dx as f32, dy as f32) * Tile::SZ;
+                    for off in [(-0.3, 0.1), (0.3, -0.1), (0.1, 0.3), (-0.1, -0.3)] {
+                        let p = cell + Vec2::from(off) * Tile::SZ;
+                        assert_eq!(solid_at(&tiles, p), solid_at(&merged, p), "at {p}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn max_velocity_only_touches_capped_axes() {
+        // 0.1 + 0.2 - 0.1 isn't 0.2 in floats, so rewriting force every tick would drift it
+        let mut v = Movement {
+            ctl: Vec2::new(0.1, 0.1),
+            force: Vec2::new(0.2, 0.2),
+            ..default()
+        };
+        MaxVelocity::default().clamp(&mut v);
+        assert_eq!(v.force, Vec2::new(0.2, 0.2));
+        MaxVelocity(Vec2::new(f32::INFINITY, 10.)).clamp(&mut v);
+        assert_eq!(v.force, Vec2::new(0.2, 0.2));
+
+        let mut v = Movement {
+            ctl: Vec2::new(5., -3.),
+            force: Vec2::new(20., -20.),
+            ..default()
+        };
+        MaxVelocity(Vec2::new(10., f32::INFINITY)).clamp(&mut v);
+        assert_eq!(v.force, Vec2::new(5., -20.));
+        assert_eq!(v.ctl, Vec2::new(5., -3.));
+    }
+
+    #[test]
+    fn lands_on_floor() {
+        let mut v = Movement::default();
+        let end = simulate(player(0., 100.), &mut v, &GameFeel::CLASSIC, &floor(), 120);
+        assert!(v.grounded);
+        assert_eq!(v.force.y, 0.);
+        assert!(end.min.y.abs() <= SKIN, "resting at {}", end.min.y);
+        assert_eq!(end.center().x, 0.);
+    }
+
+    #[test]
+    fn rests_a_skin_away_without_flickering() {
+        let mut tiles = floor();
+        tiles.push(tile(Tile::SQUARE, 100., Tile::SZ / 2.));
+        let mut v = Movement {
+            ctl: Vec2::new(2., 0.),
+            ..default()
+        };
+        let mut aabb = simulate(player(0., 100.), &mut v, &GameFeel::CLASSIC, &tiles, 120);
+        // pushing into the wall and standing on the floor, each a skin's width off
+        for tick in 0..60 {
+            aabb = step(aabb, &mut v, &GameFeel::CLASSIC, &tiles).aabb;
+            assert!(
+                (aabb.min.y - SKIN).abs() < 1e-3,
+                "tick {tick} at {}",
+                aabb.min
+            );
+            assert!(
+                (75. - aabb.max.x - SKIN).abs() < 1e-3,
+                "tick {tick} at {}",
+                aabb.max
+            );
+            assert!(v.grounded && v.wall_dir == 1., "tick {tick}");
+        }
+    }
+
+    #[test]
+    fn stops_at_wall() {
+        let mut tiles = floor();
+        tiles.push(tile(Tile::SQUARE, 200., Tile::SZ / 2.));
+        let wall = tiles.last().unwrap().1.min.x;
+        let mut v = Movement {
+            ctl: Vec2::new(5., 0.),
+            ..default()
+        };
+        let end = simulate(player(0., 0.), &mut v, &GameFeel::CLASSIC, &tiles, 120);
+        assert!(end.max.x <= wall, "went through the wall to {}", end.max.x);
+        assert!(wall - end.max.x <= SKIN, "stopped short at {}", end.max.x);
+        assert_eq!(v.wall_dir, 1.);
+        assert!(v.grounded);
+    }
+
+    #[test]
+    fn fast_moves_dont_tunnel() {
+        // walls a tile thick, further than a tile away, at speeds that would take a single move
+        // into or right past them
+        let mut tiles = floor();
+        tiles.push(tile(Tile::SQUARE, 150., Tile::SZ / 2.));
+        let wall = tiles.last().unwrap().1.min.x;
+        for speed in [120., 400., 1000.] {
+            let mut v = Movement {
+                ctl: Vec2::new(speed, 0.),
+                grounded: true,
+                ..default()
+            };
+            let end = step(player(0., 0.), &mut v, &GameFeel::CLASSIC, &tiles).aabb;
+            assert!(
+                end.max.x <= wall + SKIN,
+                "{speed}: through to {}",
+                end.max.x
+            );
+            assert!(wall - end.max.x <= SKIN, "{speed}: short at {}", end.max.x);
+        }
+        let ceiling = vec![tile(Tile::SQUARE, 0., 200.)];
+        let mut v = Movement {
+            force: Vec2::new(0., 300.),
+            ..default()
+        };
+        let end = step(player(0., 0.), &mut v, &GameFeel::CLASSIC, &ceiling).aabb;
+        assert!(
+            end.max.y <= 175. + SKIN,
+            "through the ceiling to {}",
+            end.max.y
+        );
+    }
+
+    #[test]
+    fn holding_into_a_wall_slows_the_fall() {
+        // a tall wall to the right, with nothing below
+        let wall: Vec<_> = (-20..20)
+            .map(|i| tile(Tile::SQUARE, 50., i as f32 * Tile::SZ))
+            .collect();
+        let feel = GameFeel::SNAPPY;
+        let fall = |ctl: f32, ticks: u32| {
+            let mut v = Movement {
+                ctl: Vec2::new(ctl, 0.),
+                ..default()
+            };
+            let end = simulate(player(2., 500.), &mut v, &feel, &wall, ticks);
+            (end, v)
+        };
+        let (end, held) = fall(3., 60);
+        assert_eq!(held.wall_dir, 1.);
+        assert_eq!(held.force.y, -feel.wall_slide);
+        assert!(end.max.x <= 25. + SKIN, "into the wall at {}", end.max.x);
+        // letting go falls as fast as ever
+        let (_, free) = fall(0., 60);
+        assert!(free.force.y < -2. * feel.wall_slide, "{}", free.force.y);
+        let (_, mut v) = fall(3., 30);
+        v.ctl = Vec2::ZERO;
+        let before = v.force.y;
+        step(player(2., 0.), &mut v, &feel, &wall);
+        assert!(v.force.y < before, "{} after {before}", v.force.y);
+    }
+
+    #[test]
+    fn walks_off_a_ledge_cleanly() {
+        // the floor ends at x = 25
+        let tiles: Vec<_> = floor().into_iter().filter(|t| t.1.max.x <= 25.).collect();
+        let mut v = Movement {
+            ctl: Vec2::new(5., 0.),
+            ..default()
+        };
+        let mut aabb = player(-100., SKIN);
+        for tick in 0..60 {
+            let next = step(aabb, &mut v, &GameFeel::CLASSIC, &tiles).aabb;
+            // the corner never holds the player back, or up
+            let dx = next.min.x - aabb.min.x;
+            assert!(
+                (dx - 5.).abs() < 1e-3,
+                "tick {tick} moved {dx} from {}",
+                aabb.min
+            );
+            assert!(
+                next.min.y <= aabb.min.y + 1e-3,
+                "tick {tick} rose to {}",
+                next.min
+            );
+            assert_eq!(v.wall_dir, 0., "tick {tick}");
+            if next.min.x > 25. + 1. {
+                assert!(!v.grounded, "tick {tick} still standing at {}", next.min);
+            }
+            aabb = next;
+        }
+        // all the way below the top of the ledge
+        assert!(aabb.max.y < 0., "only fell to {}", aabb.min);
+    }
+
+    #[test]
+    fn grabs_a_ledge_jumped_up_to() {
+        // a wall to the right with its lip at y = 100 and nothing above
+        let wall: Vec<_> = (-10..=0)
+            .map(|i| tile(Tile::SQUARE, 50., 75. + i as f32 * Tile::SZ))
+            .collect();
+        // jumping from below the lip while holding toward the wall, the top goes past it and back
+        let jump = |feel: &GameFeel| {
+            let mut v = Movement {
+                ctl: Vec2::new(3., 0.),
+                force: Vec2::new(0., feel.jump_impulse),
+                ..default()
+            };
+            let mut aabb = player(2., 0.);
+            for _ in 0..60 {
+                aabb = step(aabb, &mut v, feel, &wall).aabb;
+                if v.hang != 0. {
+                    break;
+                }
+            }
+            (aabb, v)
+        };
+        let (hanging, mut v) = jump(&GameFeel::SNAPPY);
+        assert_eq!(v.hang, 1.);
+        assert_eq!(hanging.max.y, 100.);
+        // and stays right there
+        let held = simulate(hanging, &mut v, &GameFeel::SNAPPY, &wall, 30);
+        assert_eq!((held.min, v.hang), (hanging.min, 1.));
+        // until it jumps
+        v.ctl.y = 1.;
+        let up = step(held, &mut v, &GameFeel::SNAPPY, &wall).aabb;
+        assert_eq!(v.hang, 0.);
+        assert!(up.min.y > held.min.y, "{} from {}", up.min, held.min);
+        // classic never grabs on
+        let (_, v) = jump(&GameFeel::CLASSIC);
+        assert_eq!(v.hang, 0.);
+    }
+
+    // the most sideways push the player gets after running right at speed from x on a slope of
+    // n tiles that ends in midair
+    fn launch(speed: f32, n: i32, x: f32) -> f32 {
+        // a ledge with its top at 100 up to x = 25, then the slope down from there
+        let mut tiles: Vec<_> = (-10..=0)
+            .map(|i| tile(Tile::SQUARE, i as f32 * Tile::SZ, 75.))
+            .collect();
+        tiles.extend((1..=n).map(|i| {
+            tile(
+                Tile::SLOPEL,
+                i as f32 * Tile::SZ,
+                125. - i as f32 * Tile::SZ,
+            )
+        }));
+        let mut v = Movement {
+            ctl: Vec2::new(speed, 0.),
+            ..default()
+        };
+        // standing on the slope, which is as high as it is far from x = 125
+        let mut aabb = player(x + 22.5, 125. - x + SKIN);
+        let mut most = 0f32;
+        for _ in 0..120 {
+            aabb = step(aabb, &mut v, &GameFeel::SNAPPY, &tiles).aabb;
+            most = most.max(v.force.x);
+        }
+        assert!(
+            aabb.max.y < 125. - (n + 1) as f32 * Tile::SZ,
+            "stuck at {}",
+            aabb.min
+        );
+        most
+    }
+
+    #[test]
+    fn sliding_off_a_slope_launches() {
+        // four tiles of slope is plenty of slide to carry on with
+        let long = launch(4., 4, 30.);
+        assert!(
+            long >= GameFeel::SNAPPY.slope_launch_min,
+            "launched at {long}"
+        );
+        // walking off from halfway down one isn't
+        assert_eq!(launch(1., 1, 50.), 0.);
+    }
+
+    // ticks a jump with feel spends within 5px of its highest point
+    fn ticks_at_apex(feel: &GameFeel) -> usize {
+        let mut v = Movement {
+            force: Vec2::new(0., 6.),
+            ..default()
+        };
+        let heights: Vec<f32> = (0..200)
+            .scan(player(0., SKIN), |aabb, _| {
+                *aabb = step(*aabb, &mut v, feel, &floor()).aabb;
+                Some(aabb.min.y)
+            })
+            .collect();
+        let top = heights.iter().copied().fold(0., f32::max);
+        heights.iter().filter(|y| **y > top - 5.).count()
+    }
+
+    #[test]
+    fn jumps_hang_at_the_top() {
+        let ramp = GameFeel::SNAPPY;
+        let flat = GameFeel {
+            apex_gravity: 1.,
+            ..ramp
+        };
+        let (hang, no_hang) = (ticks_at_apex(&ramp), ticks_at_apex(&flat));
+        assert!(
+            hang > no_hang + 3,
+            "{hang} ticks at the top, {no_hang} without"
+        );
+    }
+
+    #[test]
+    fn walks_up_slope() {
+        // a ramp up onto a ledge one tile high. steps are the ramps that can be walked up, the
+        // player slides back down slopes
+        let mut tiles = floor();
+        tiles.push(tile(Tile::STEPR, 100., Tile::SZ / 2.));
+        tiles.extend((3..=10).map(|i| tile(Tile::SQUARE, i as f32 * Tile::SZ, Tile::SZ / 2.)));
+        let mut v = Movement {
+            ctl: Vec2::new(5., 0.),
+            ..default()
+        };
+        let end = simulate(player(0., 0.), &mut v, &GameFeel::CLASSIC, &tiles, 90);
+        assert!(end.min.x > 150., "stuck at the ramp, x {}", end.min.x);
+        assert!(
+            (end.min.y - Tile::SZ).abs() <= SKIN,
+            "not on the step, bottom at {}",
+            end.min.y
+        );
+        assert!(v.grounded);
+    }
+
+    #[test]
+    fn tiny_and_nan_velocities_stay_put() {
+        let mut v = Movement::default();
+        let rest = simulate(player(0., 100.), &mut v, &GameFeel::CLASSIC, &floor(), 120);
+        for bad in [
+            Vec2::new(1e-40, 0.),
+            Vec2::new(f32::NAN, 0.),
+            Vec2::splat(f32::INFINITY),
+        ] {
+            let mut v = Movement {
+                ctl: sanitize(bad),
+                force: sanitize(bad),
+                grounded: true,
+                ..default()
+            };
+            assert_eq!(v.ctl, Vec2::ZERO, "{bad}");
+            let out = step(rest, &mut v, &GameFeel::CLASSIC, &floor()).aabb;
+            assert_eq!(out.center().x, rest.center().x, "{bad}");
+            assert!(out.center().is_finite(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn ejects_from_inside_a_block() {
+        let block: Vec<_> = (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| tile(Tile::SQUARE, x as f32 * 50., y as f32 * 50.)))
+            .collect();
+        let inside = player(0., -22.5);
+        assert!(is_embedded(&inside, &block));
+        let mut v = Movement {
+            ctl: Vec2::new(1., 0.),
+            ..default()
+        };
+        let out = step(inside, &mut v, &GameFeel::CLASSIC, &block).aabb;
+        assert!(
+            !is_embedded(&out, &block),
+            "still stuck at {}",
+            out.center()
+        );
+        // out next to the block rather than flung off somewhere
+        let block_box = Aabb2d::new(Vec2::ZERO, Vec2::splat(75. - 1.));
+        assert!(!out.intersects(&block_box), "{}", out.center());
+        assert!(out.center().length() < 150., "{}", out.center());
+        assert_eq!(v.force, Vec2::ZERO);
+        // a player that isn't stuck is left alone
+        assert!(unstick(&player(0., 75.), &block).is_none());
+    }
+
+    #[test]
+    fn cull_keeps_every_overlapping_tile() {
+        let col = Aabb2d::new(Vec2::ZERO, Vec2::splat(Tile::SZ / 2.));
+        let sizes = [Vec2::splat(22.5), Vec2::new(5., 40.), Vec2::new(60., 3.)];
+        let mut culled = 0;
+        for half in sizes {
+            for x in -100..=100 {
+                for y in -100..=100 {
+                    let aabb = Aabb2d::new(Vec2::new(x as f32, y as f32), half);
+                    let touching = aabb.intersects(&col);
+                    assert!(!touching || might_touch(&aabb, &col), "{half} at {x} {y}");
+                    culled += !might_touch(&aabb, &col) as u32;
+                }
+            }
+        }
+        // and it does cull something
+        assert!(culled > 0);
+    }
+}
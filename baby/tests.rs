@@ -0,0 +1,1709 @@
+// the game's app wiring run without a window or renderer, stepping frames by hand
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{add_game, intro, level, map, physics, AppState};
+
+// MinimalPlugins and the engine plugins the game's systems read from, with every frame 1/60s.
+// there's no RenderPlugin, so nothing draws: WindowPlugin without a primary window stands in
+// for winit, the asset types the sprite, ui, text and render plugins would register (Image,
+// Mesh, ColorMaterial, TextureAtlasLayout, Font, Shader) are registered bare so handles to them
+// still load, and GizmoPlugin takes the debug overlay's gizmo calls and throws them away
+pub fn headless() -> App {
+    headless_with(intro::SkipIntro(false))
+}
+
+pub fn headless_with(skip_intro: intro::SkipIntro) -> App {
+    // the game reads its BABY_* settings from the environment as it's wired up, so drop them all
+    // and every test gets the defaults whatever shell it was run from
+    static CLEAR_ENV: std::sync::Once = std::sync::Once::new();
+    CLEAR_ENV.call_once(|| {
+        for (key, _) in std::env::vars_os() {
+            if key.to_string_lossy().starts_with("BABY_") {
+                std::env::remove_var(key);
+            }
+        }
+    });
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        bevy::window::WindowPlugin {
+            primary_window: None,
+            exit_condition: bevy::window::ExitCondition::DontExit,
+            ..default()
+        },
+        bevy::audio::AudioPlugin::default(),
+        bevy::animation::AnimationPlugin,
+        TransformPlugin,
+        HierarchyPlugin,
+        bevy::input::InputPlugin,
+    ))
+    // the gizmo plugin loads its shaders as it's added
+    .init_asset::<Shader>()
+    .add_plugins(bevy::gizmos::GizmoPlugin)
+    .init_asset::<Image>()
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<TextureAtlasLayout>()
+    .init_asset::<Font>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1. / 60.,
+    )));
+    add_game(&mut app, skip_intro);
+    app
+}
+
+#[test]
+fn the_harness_ignores_the_shells_settings() {
+    headless();
+    let set: Vec<_> = (std::env::vars_os())
+        .filter(|(key, _)| key.to_string_lossy().starts_with("BABY_"))
+        .collect();
+    assert!(set.is_empty(), "{set:?}");
+}
+
+#[test]
+fn intro_sequence_advances() {
+    let mut app = headless();
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Intro);
+    app.update();
+    app.update();
+    let start = app.world.resource::<intro::CueSequencer>().time;
+    for _ in 0..30 {
+        app.update();
+    }
+    let end = app.world.resource::<intro::CueSequencer>().time;
+    assert!(end > start, "sequence stuck at {start}");
+    assert!(
+        (end - start - 0.5).abs() < 0.05,
+        "30 frames moved it {}",
+        end - start
+    );
+}
+
+#[test]
+fn skipping_as_the_intro_ends_fades_out_once() {
+    let mut app = headless();
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Intro);
+    app.update();
+    app.update();
+    let mut sequence = app.world.resource_mut::<intro::CueSequencer>();
+    sequence.time = sequence.end;
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::Space);
+    app.update();
+    app.update();
+    let mut fades = app.world.query_filtered::<(), With<crate::fade::Fade>>();
+    assert_eq!(fades.iter(&app.world).count(), 1);
+}
+
+// straight into the game on level instead of the built in map
+pub fn game(level: map::Level) -> App {
+    let mut app = headless();
+    app.insert_resource(level);
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Game);
+    app.update();
+    app
+}
+
+// a map 11 tiles wide and 20 high around the player spawn, with only a floor along the bottom
+pub fn open_level() -> map::Level {
+    floored_level(physics::Tile::SQUARE)
+}
+
+// open_level with the floor made of floor tiles instead
+pub fn floored_level(floor: physics::Tile) -> map::Level {
+    let (width, height) = (11, 20);
+    let mut data = vec![0; width * (height - 1)];
+    data.extend(vec![floor.0; width]);
+    map::Level::from_grid(Vec2::new(-250., -500.), width, data).unwrap()
+}
+
+#[test]
+fn advance_uses_its_own_dt() {
+    let mut game = intro::GameTime::default();
+    game.step = Some(0.5);
+    game.advance(0.1, 2.);
+    assert_eq!(game.real_delta_seconds(), 0.1);
+    assert_eq!(game.delta_seconds(), 0.2);
+}
+
+#[test]
+fn game_time_drives_physics_and_animation() {
+    let mut app = game(open_level());
+    // frames take 0.1s, but the game's clock steps 1/60 each
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        0.1,
+    )));
+    app.world.resource_mut::<intro::GameTime>().step = Some(1. / 60.);
+    let anim = app
+        .world
+        .spawn((
+            TextureAtlas::default(),
+            intro::TextureAnimate {
+                frame_len: 0.15,
+                cycle: intro::Cycle::Loop,
+                idx_beg: 0,
+                idx_end: 9,
+                idx_left: None,
+                elapsed: 0.,
+            },
+        ))
+        .id();
+    let mut player = app
+        .world
+        .query_filtered::<(&Transform, &mut physics::Movement), With<level::Control>>();
+    let (t, mut v) = player.single_mut(&mut app.world);
+    // a player with no velocity at all isn't stepped, so start the fall with a nudge
+    v.force = Vec2::new(0., -0.01);
+    let start = t.translation.y;
+
+    let elapsed = app.world.resource::<intro::GameTime>().elapsed_seconds();
+    for _ in 0..60 {
+        app.update();
+    }
+    let game = app.world.resource::<intro::GameTime>();
+    assert!((game.elapsed_seconds() - elapsed - 1.).abs() < 1e-3);
+    // 1s at 0.15s a frame is 6 frames in
+    assert_eq!(app.world.get::<TextureAtlas>(anim).unwrap().index, 6);
+    // falling from rest for 60 ticks is g * (1 + 2 + .. + 60)
+    let fall = physics::GameFeel::CLASSIC.gravity * (60. * 61. / 2.);
+    let (t, _) = player.single(&app.world);
+    let dy = start - t.translation.y;
+    assert!((dy - fall).abs() < 10., "fell {dy}, expected about {fall}");
+}
+
+fn exited(app: &App) -> bool {
+    !app.world
+        .resource::<Events<bevy::app::AppExit>>()
+        .is_empty()
+}
+
+// escape saves the map into a scratch directory and quits, whatever mode the game is in
+fn assert_escape_saves_and_quits(mode: impl FnOnce(&mut World)) {
+    let mut app = game(open_level());
+    mode(&mut app.world);
+    app.update();
+    assert!(!exited(&app));
+    // one dir per mode, the tests run at once
+    static N: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = N.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("grossbaby-quit-{}-{n}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    app.world.resource_mut::<level::SaveDir>().0 = dir.clone();
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::Escape);
+    app.update();
+    assert!(exited(&app));
+    let saved = std::fs::read_to_string(dir.join("map.map")).unwrap();
+    assert_eq!(map::Level::parse_text(&saved).unwrap().tiles().count(), 11);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn escape_saves_and_quits_in_edit_mode() {
+    assert_escape_saves_and_quits(|world| world.resource_mut::<level::EditMode>().0 = true);
+}
+
+#[test]
+fn escape_saves_and_quits_in_puzzle_mode() {
+    assert_escape_saves_and_quits(|world| world.resource_mut::<level::PuzzleMode>().0 = true);
+}
+
+#[test]
+fn escape_quits_even_when_the_map_cant_be_saved() {
+    let mut app = game(open_level());
+    app.update();
+    let missing = std::env::temp_dir().join(format!("grossbaby-missing-{}", std::process::id()));
+    app.world.resource_mut::<level::SaveDir>().0 = missing.join("nowhere");
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::Escape);
+    app.update();
+    assert!(exited(&app));
+    assert!(!missing.exists());
+}
+
+#[test]
+fn background_leaves_letterbox_black() {
+    let mut level = open_level();
+    level.background = Some(Color::rgb(0.2, 0.4, 0.6));
+    let mut app = game(level);
+    app.update();
+    assert_eq!(app.world.resource::<ClearColor>().0, Color::BLACK);
+    let mut fill = app
+        .world
+        .query_filtered::<&Sprite, With<level::BackgroundFill>>();
+    let sprite = fill.single(&app.world);
+    assert_eq!(sprite.color, Color::rgb(0.2, 0.4, 0.6));
+}
+
+// the player's push up one frame after letting go of jump mid-rise, with feel
+fn force_after_release(feel: physics::GameFeel) -> f32 {
+    let mut app = game(open_level());
+    app.insert_resource(feel);
+    let mut player = app
+        .world
+        .query_filtered::<&mut physics::Movement, With<level::Control>>();
+    let mut v = player.single_mut(&mut app.world);
+    (v.ctl.y, v.force.y) = (feel.jump_impulse, 2.);
+    app.update();
+    player.single(&app.world).force.y
+}
+
+#[test]
+fn classic_jump_release_keeps_force() {
+    let feel = physics::GameFeel::CLASSIC;
+    let force = force_after_release(feel);
+    assert!((force - (2. - feel.gravity)).abs() < 1e-3, "force {force}");
+    let feel = physics::GameFeel::FLOATY;
+    let force = force_after_release(feel);
+    let cut = (feel.jump_impulse + 2.) * feel.jump_cut - feel.gravity;
+    assert!((force - cut).abs() < 1e-3, "force {force}, expected {cut}");
+}
+
+// the game on level with feel, once the player has dropped onto the floor and settled
+pub fn landed(level: map::Level, feel: physics::GameFeel) -> App {
+    let mut app = game(level);
+    app.insert_resource(feel);
+    // a player with no velocity at all isn't stepped, so start the fall with a nudge
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    for _ in 0..120 {
+        app.update();
+    }
+    assert!(player_mut(&mut app).grounded, "never landed");
+    app
+}
+
+pub fn player_pos(app: &mut App) -> Vec2 {
+    let mut player = (app.world).query_filtered::<&Transform, With<level::Control>>();
+    player.single(&app.world).translation.xy()
+}
+
+pub fn player_mut(app: &mut App) -> Mut<'_, physics::Movement> {
+    let mut player = (app.world).query_filtered::<&mut physics::Movement, With<level::Control>>();
+    player.single_mut(&mut app.world)
+}
+
+// how far the player runs right in frames at scale
+fn run_right(scale: f32, frames: usize) -> f32 {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    app.insert_resource(intro::TimeScale(scale));
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    let start = player_pos(&mut app).x;
+    for _ in 0..frames {
+        app.update();
+    }
+    player_pos(&mut app).x - start
+}
+
+#[test]
+fn half_time_scale_moves_half_as_far() {
+    let (full, half) = (run_right(1., 20), run_right(0.5, 20));
+    assert!(full > 50., "ran {full}");
+    assert!(
+        (half - full / 2.).abs() < 1.,
+        "ran {half} at half speed, {full} at full"
+    );
+    assert_eq!(run_right(0., 20), 0.);
+}
+
+// a key event through the input plugin, so just_pressed sees it like a real press
+pub fn send_key(app: &mut App, key: KeyCode, state: bevy::input::ButtonState) {
+    app.world.send_event(bevy::input::keyboard::KeyboardInput {
+        key_code: key,
+        logical_key: bevy::input::keyboard::Key::Unidentified(
+            bevy::input::keyboard::NativeKey::Unidentified,
+        ),
+        state,
+        window: Entity::PLACEHOLDER,
+    });
+}
+
+// press and release key over two frames
+pub fn tap(app: &mut App, key: KeyCode) {
+    send_key(app, key, bevy::input::ButtonState::Pressed);
+    app.update();
+    send_key(app, key, bevy::input::ButtonState::Released);
+    app.update();
+}
+
+#[test]
+fn tab_cycles_paint_and_wraps() {
+    let mut app = game(open_level());
+    let paint = |app: &App| app.world.resource::<level::Paint>().0;
+    assert_eq!(paint(&app), physics::Tile(1));
+    tap(&mut app, KeyCode::Tab);
+    assert_eq!(paint(&app), physics::Tile(2));
+    for _ in 2..physics::Tile::NUM {
+        tap(&mut app, KeyCode::Tab);
+    }
+    assert_eq!(paint(&app), physics::Tile(1), "wraps past the last type");
+    send_key(
+        &mut app,
+        KeyCode::ShiftLeft,
+        bevy::input::ButtonState::Pressed,
+    );
+    tap(&mut app, KeyCode::Tab);
+    assert_eq!(paint(&app), physics::Tile(physics::Tile::NUM as u8 - 1));
+}
+
+// every tile in the running game, as snapshot_level takes them
+fn snapshot(app: &mut App) -> map::Level {
+    let mut tiles = app.world.query::<(
+        &Transform,
+        &physics::Tile,
+        Has<level::Decor>,
+        Has<level::Composite>,
+    )>();
+    let level = app.world.resource::<map::Level>().clone();
+    level::snapshot_level(tiles.iter(&app.world), &level).unwrap()
+}
+
+#[test]
+fn decor_is_spawned_and_saved() {
+    let mut level = open_level();
+    let corner = level.origin;
+    level.decor.push(corner);
+    let mut app = game(level);
+    let mut decor = app
+        .world
+        .query_filtered::<&Transform, (With<physics::Tile>, With<level::Decor>)>();
+    let found: Vec<_> = decor.iter(&app.world).map(|t| t.translation.xy()).collect();
+    assert_eq!(found, [corner]);
+    let saved = map::Level::parse_text(&snapshot(&mut app).to_text()).unwrap();
+    assert_eq!(saved.decor, [corner]);
+}
+
+#[test]
+fn composite_is_spawned_and_saved() {
+    let mut level = open_level();
+    let corner = level.origin;
+    level.composite.push(corner);
+    let mut app = game(level);
+    let mut composite = app
+        .world
+        .query_filtered::<&Transform, (With<physics::Tile>, With<level::Composite>)>();
+    let found: Vec<_> = composite
+        .iter(&app.world)
+        .map(|t| t.translation.xy())
+        .collect();
+    assert_eq!(found, [corner]);
+    let saved = map::Level::parse_text(&snapshot(&mut app).to_text()).unwrap();
+    assert_eq!(saved.composite, [corner]);
+    assert!(saved.decor.is_empty());
+}
+
+#[test]
+fn start_level_is_used_without_the_intro() {
+    let path = std::env::temp_dir().join(format!("grossbaby-start-{}.map", std::process::id()));
+    let mut start = open_level();
+    start.name = Some("start".into());
+    std::fs::write(&path, start.to_text()).unwrap();
+    let start = intro::StartLevel(Some(path.clone()));
+    let skipped = crate::first_level(&intro::SkipIntro(true), &start);
+    assert_eq!(skipped.name.as_deref(), Some("start"));
+    // with the intro, the intro loads it on the way out
+    let played = crate::first_level(&intro::SkipIntro(false), &start);
+    assert_ne!(played.name.as_deref(), Some("start"));
+    // a start level that can't be loaded falls back to the usual map
+    let missing = intro::StartLevel(Some(path.with_extension("missing")));
+    let fallback = crate::first_level(&intro::SkipIntro(true), &missing);
+    assert_ne!(fallback.name.as_deref(), Some("start"));
+    std::fs::remove_file(path).unwrap();
+}
+
+// how high the player gets holding jump from the ground with feel
+fn jump_height(feel: physics::GameFeel) -> f32 {
+    let mut app = landed(open_level(), feel);
+    let ground = player_pos(&mut app).y;
+    send_key(&mut app, KeyCode::Space, bevy::input::ButtonState::Pressed);
+    (0..180)
+        .map(|_| {
+            app.update();
+            player_pos(&mut app).y - ground
+        })
+        .fold(0., f32::max)
+}
+
+#[test]
+fn presets_jump_to_different_heights() {
+    let heights = [
+        physics::GameFeel::CLASSIC,
+        physics::GameFeel::FLOATY,
+        physics::GameFeel::SNAPPY,
+    ]
+    .map(jump_height);
+    for (i, a) in heights.iter().enumerate() {
+        assert!(*a > 20., "{heights:?}");
+        for b in &heights[i + 1..] {
+            assert!((a - b).abs() > 5., "{heights:?}");
+        }
+    }
+}
+
+// one-shot sounds playing path
+pub fn sounds(app: &mut App, path: &str) -> usize {
+    let mut audio = app.world.query::<&Handle<AudioSource>>();
+    (audio.iter(&app.world))
+        .filter(|h| h.path().is_some_and(|p| p.path().to_str() == Some(path)))
+        .count()
+}
+
+#[test]
+fn only_hard_landings_thump() {
+    let mut app = game(open_level());
+    let land = |speed: f32| level::CollisionEvent {
+        entity: Entity::PLACEHOLDER,
+        tile: physics::Tile::SQUARE,
+        normal: Vec2::Y,
+        vel: Vec2::new(0., -speed),
+    };
+    app.world.send_event(land(1.));
+    app.update();
+    assert_eq!(sounds(&mut app, "sounds/thump.wav"), 0);
+    app.world.send_event(land(8.));
+    app.update();
+    assert_eq!(sounds(&mut app, "sounds/thump.wav"), 1);
+    // hitting a wall fast isn't a landing
+    app.world.send_event(level::CollisionEvent {
+        normal: Vec2::X,
+        vel: Vec2::new(-8., -8.),
+        ..land(0.)
+    });
+    app.update();
+    assert_eq!(sounds(&mut app, "sounds/thump.wav"), 1);
+}
+
+#[test]
+fn a_footstep_per_stride_walked() {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    let (before, start) = (sounds(&mut app, "sounds/thump.wav"), player_pos(&mut app).x);
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    for _ in 0..25 {
+        app.update();
+    }
+    let walked = player_pos(&mut app).x - start;
+    let steps = sounds(&mut app, "sounds/thump.wav") - before;
+    // 40px a stride
+    assert!(walked > 100., "walked {walked}");
+    assert_eq!(steps, (walked / 40.) as usize, "walked {walked}");
+    // standing still doesn't step, however long it takes
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).release(KeyCode::ArrowRight);
+    for _ in 0..60 {
+        app.update();
+    }
+    assert_eq!(sounds(&mut app, "sounds/thump.wav") - before, steps);
+}
+
+#[test]
+fn fade_reaches_black_before_switching_state() {
+    use bevy::ecs::system::RunSystemOnce;
+    let mut app = game(open_level());
+    app.world.run_system_once(|mut commands: Commands| {
+        crate::fade::fade_transition(&mut commands, 1., Some(AppState::Intro));
+    });
+    let mut overlay = (app.world).query_filtered::<&BackgroundColor, With<crate::fade::Fade>>();
+    let mut last = 0.;
+    for frame in 1..=60 {
+        app.update();
+        let alpha = overlay.single(&app.world).0.a();
+        let queued = app.world.resource::<NextState<AppState>>().0.clone();
+        if frame < 60 {
+            assert!(alpha > last && alpha < 1., "frame {frame} at {alpha}");
+            assert_eq!(queued, None, "switched at frame {frame}");
+        } else {
+            assert_eq!(alpha, 1.);
+            assert_eq!(queued, Some(AppState::Intro));
+        }
+        last = alpha;
+    }
+}
+
+#[test]
+fn a_hazard_hurts_once_per_touch() {
+    let mut app = game(floored_level(physics::Tile::HAZARD));
+    let mut health = (app.world).query_filtered::<&level::Health, With<level::Control>>();
+    let full = health.single(&app.world).0;
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    // landing hurts, then standing there for a couple of seconds doesn't hurt again
+    let mut hp = vec![];
+    for _ in 0..240 {
+        app.update();
+        hp.push(health.single(&app.world).0);
+    }
+    assert!(player_mut(&mut app).grounded);
+    let hits = hp.windows(2).filter(|w| w[1] < w[0]).count();
+    assert_eq!((hits, hp[239]), (1, full - 1.), "{hp:?}");
+    // hopping off and landing again is another touch
+    player_mut(&mut app).force = Vec2::new(0., 8.);
+    for _ in 0..120 {
+        app.update();
+    }
+    assert_eq!(health.single(&app.world).0, full - 2.);
+    // touching one every tick only hurts again once the invulnerability runs out
+    for _ in 0..60 {
+        app.update();
+    }
+    let mut health = (app.world).query_filtered::<&mut level::Health, With<level::Control>>();
+    health.single_mut(&mut app.world).0 = full;
+    let mut player = (app.world).query_filtered::<Entity, With<level::Control>>();
+    let entity = player.single(&app.world);
+    let mut hits = vec![];
+    for frame in 0..90 {
+        let before = health.single(&app.world).0;
+        app.world.send_event(level::CollisionEvent {
+            entity,
+            tile: physics::Tile::HAZARD,
+            normal: Vec2::Y,
+            vel: Vec2::ZERO,
+        });
+        app.update();
+        if health.single(&app.world).0 < before {
+            hits.push(frame);
+        }
+    }
+    // a second of invulnerability is 60 frames
+    assert_eq!(hits, [0, 61]);
+}
+
+#[test]
+fn physics_log_writes_a_record_per_tick() {
+    let path = std::env::temp_dir().join(format!("baby-physlog-{}.jsonl", std::process::id()));
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    app.insert_resource(level::PhysicsLog::create(&path).unwrap());
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    for _ in 0..10 {
+        app.update();
+    }
+    // dropping it flushes the file
+    app.world.remove_resource::<level::PhysicsLog>();
+    let text = std::fs::read_to_string(&path).unwrap();
+    _ = std::fs::remove_file(&path);
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 10, "{text}");
+    for (i, line) in lines.iter().enumerate() {
+        let prefix = format!(r#"{{"tick":{i},"pos":["#);
+        assert!(line.starts_with(&prefix) && line.ends_with("]}"), "{line}");
+        assert!(line.contains(r#""grounded":true"#), "{line}");
+        // balanced, and every number in it finite
+        let depth = line.chars().try_fold(0i32, |d, c| {
+            let d = d + match c {
+                '{' | '[' => 1,
+                '}' | ']' => -1,
+                _ => 0,
+            };
+            (d >= 0).then_some(d)
+        });
+        assert_eq!(depth, Some(0), "{line}");
+        assert!(!line.contains("NaN") && !line.contains("inf"), "{line}");
+    }
+}
+
+#[test]
+fn map_bounds_cover_the_loaded_tiles() {
+    // open_level's floor plus a lone tile near the top left, with empty rows at the top and bottom
+    // of the grid that shouldn't count
+    let mut level = open_level();
+    level.data[11 + 2] = physics::Tile::SQUARE.0;
+    level.data.extend([0; 11]);
+    let mut app = game(level);
+    let bounds = app.world.resource::<level::MapBounds>().0;
+    let mut tiles = (app.world).query::<(&Transform, &physics::Tile)>();
+    let centers: Vec<_> = (tiles.iter(&app.world))
+        .map(|(t, _)| t.translation.xy())
+        .collect();
+    let min = centers.iter().fold(Vec2::MAX, |m, p| m.min(*p));
+    let max = centers.iter().fold(Vec2::MIN, |m, p| m.max(*p));
+    let half = physics::Tile::SZ / 2.;
+    assert_eq!(bounds, Rect::from_corners(min - half, max + half));
+    // the floor's now on the row above the origin's, and the lone tile a row down from the top
+    assert_eq!(bounds.min, Vec2::new(-275., -475.));
+    assert_eq!(bounds.max, Vec2::new(275., 475.));
+}
+
+#[test]
+fn pausing_holds_the_player_animation() {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    let mut anim = (app.world)
+        .query_filtered::<(&TextureAtlas, &intro::TextureAnimate), With<level::Control>>();
+    let mut frame = |app: &App| {
+        let (atlas, anim) = anim.single(&app.world);
+        (atlas.index, anim.elapsed)
+    };
+    tap(&mut app, KeyCode::KeyP);
+    assert!(app.world.resource::<intro::GameTime>().paused);
+    let held = frame(&app);
+    for _ in 0..60 {
+        app.update();
+        assert_eq!(frame(&app), held);
+    }
+    // and carries on from there once unpaused
+    tap(&mut app, KeyCode::KeyP);
+    let mut indices = vec![];
+    for _ in 0..60 {
+        app.update();
+        indices.push(frame(&app).0);
+    }
+    assert!(frame(&app).1 > held.1);
+    assert!(indices.iter().any(|i| *i != held.0), "stuck on {indices:?}");
+}
+
+#[test]
+fn a_generated_checkerboard_spawns_where_it_says() {
+    let (width, height, origin) = (6, 4, Vec2::new(-125., -300.));
+    let data: Vec<u8> = (0..width * height)
+        .map(|i| ((i % width + i / width) % 2) as u8 * physics::Tile::HAZARD.0)
+        .collect();
+    let app = game(map::Level::from_grid(origin, width, data).unwrap());
+    let mut want = vec![];
+    for row in 0..height {
+        for col in 0..width {
+            if (row + col) % 2 == 1 {
+                // rows from the top down, the origin is the bottom left tile
+                let y = origin.y + (height - 1 - row) as f32 * physics::Tile::SZ;
+                want.push((origin.x + col as f32 * physics::Tile::SZ, y));
+            }
+        }
+    }
+    let mut got: Vec<_> = (app.world.iter_entities())
+        .filter_map(|e| Some((e.get::<physics::Tile>()?, e.get::<Transform>()?)))
+        .map(|(tile, t)| {
+            assert_eq!(*tile, physics::Tile::HAZARD);
+            (t.translation.x, t.translation.y)
+        })
+        .collect();
+    let order = |a: &(f32, f32), b: &(f32, f32)| a.partial_cmp(b).unwrap();
+    got.sort_by(order);
+    want.sort_by(order);
+    assert_eq!(got, want);
+}
+
+#[test]
+fn unknown_tile_ids_are_skipped() {
+    // open_level with a couple of its floor tiles from a newer palette
+    let mut level = open_level();
+    let floor = level.data.len() - 11;
+    level.data[floor + 3] = physics::Tile::NUM as u8;
+    level.data[floor + 7] = 200;
+    let mut app = game(level);
+    for _ in 0..10 {
+        app.update();
+    }
+    let mut tiles = (app.world).query::<&physics::Tile>();
+    let ids: Vec<_> = tiles.iter(&app.world).map(|t| t.0).collect();
+    assert_eq!(ids, [physics::Tile::SQUARE.0; 9]);
+}
+
+#[test]
+fn crouching_fits_under_a_low_ceiling() {
+    // open_level with a ceiling a tile above the floor from x = 50 to 150, and a player too tall
+    // to fit under it standing up
+    let mut level = open_level();
+    let row = level.data.len() - 3 * 11;
+    level.data[row + 6..row + 9].fill(physics::Tile::SQUARE.0);
+    let mut app = headless();
+    app.insert_resource(level::PlayerSize(Vec2::new(45., 70.)));
+    app.insert_resource(level);
+    (app.world.resource_mut::<NextState<AppState>>()).set(AppState::Game);
+    app.update();
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    let hold = |app: &mut App, keys: &[KeyCode], frames: usize| {
+        let mut kbd = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        kbd.release_all();
+        for key in keys {
+            kbd.press(*key);
+        }
+        for _ in 0..frames {
+            app.update();
+        }
+    };
+    hold(&mut app, &[], 120);
+    // standing, the ceiling's edge stops the player like a wall
+    hold(&mut app, &[KeyCode::ArrowRight], 60);
+    assert!(
+        player_pos(&mut app).x < 30.,
+        "walked in at {}",
+        player_pos(&mut app)
+    );
+    // crouched it fits, and can't stand back up under there
+    hold(&mut app, &[KeyCode::ArrowDown, KeyCode::ArrowRight], 45);
+    let under = player_pos(&mut app).x;
+    assert!((75.0..150.).contains(&under), "at {under}");
+    hold(&mut app, &[], 10);
+    assert!(player_mut(&mut app).crouch);
+    // out the other side it stands up again
+    hold(&mut app, &[KeyCode::ArrowRight], 60);
+    assert!(player_pos(&mut app).x > 200., "at {}", player_pos(&mut app));
+    hold(&mut app, &[], 10);
+    assert!(!player_mut(&mut app).crouch);
+    let mut t = (app.world).query_filtered::<&Transform, With<level::Control>>();
+    assert_eq!(t.single(&app.world).scale.y, 70.);
+}
+
+// the speeds of the one-shot sounds playing path
+fn sound_speeds(app: &mut App, path: &str) -> Vec<f32> {
+    let mut audio = app
+        .world
+        .query::<(&Handle<AudioSource>, &PlaybackSettings)>();
+    (audio.iter(&app.world))
+        .filter(|(h, _)| h.path().is_some_and(|p| p.path().to_str() == Some(path)))
+        .map(|(_, s)| s.speed)
+        .collect()
+}
+
+#[test]
+fn ground_and_wall_jumps_sound_different() {
+    // open_level walled off on the right
+    let mut level = open_level();
+    for row in level.data.chunks_mut(11) {
+        row[10] = physics::Tile::SQUARE.0;
+    }
+    let mut app = landed(level, physics::GameFeel::CLASSIC);
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    for _ in 0..60 {
+        app.update();
+    }
+    assert!(player_mut(&mut app).grounded);
+    assert!(sound_speeds(&mut app, "sounds/woosh.wav").is_empty());
+    // a jump off the floor next to the wall is still a ground jump
+    tap(&mut app, KeyCode::Space);
+    assert_eq!(sound_speeds(&mut app, "sounds/woosh.wav"), [1.]);
+    assert!(!player_mut(&mut app).grounded);
+    assert_eq!(player_mut(&mut app).wall_dir, 1.);
+    // and off the wall it's higher
+    tap(&mut app, KeyCode::Space);
+    let mut speeds = sound_speeds(&mut app, "sounds/woosh.wav");
+    speeds.sort_by(f32::total_cmp);
+    assert_eq!(speeds, [1., 1.3]);
+}
+
+// the lowest the player gets in frames of falling onto level's floor, after tapping keys
+fn fall_onto(level: map::Level, keys: &[KeyCode], frames: usize) -> f32 {
+    let mut app = game(level);
+    for key in keys {
+        tap(&mut app, *key);
+    }
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    let mut lowest = f32::INFINITY;
+    for _ in 0..frames {
+        app.update();
+        lowest = lowest.min(player_pos(&mut app).y);
+    }
+    lowest
+}
+
+// open_level with its floor made decoration, so nothing stops a fall
+fn bottomless_level() -> map::Level {
+    let mut level = open_level();
+    level.decor = (0..11)
+        .map(|x| level.origin + Vec2::new(x as f32 * 50., 0.))
+        .collect();
+    level
+}
+
+#[test]
+fn decor_tiles_dont_collide() {
+    let solid = fall_onto(open_level(), &[], 120);
+    // on top of the floor, whose top is at -475
+    assert!(solid > -475. && solid < -450., "got down to {solid}");
+    // the same floor as decoration is fallen through
+    let decor = fall_onto(bottomless_level(), &[], 120);
+    assert!(decor < -525., "got down to {decor}");
+}
+
+#[test]
+fn dashing_leaves_a_fading_trail() {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    let mut trail = (app.world).query_filtered::<(&Transform, &Sprite), With<level::Afterimage>>();
+    assert_eq!(trail.iter(&app.world).count(), 0);
+    tap(&mut app, KeyCode::KeyX);
+    let (mut most, mut positions) = (0, vec![]);
+    for _ in 0..60 {
+        app.update();
+        let images: Vec<_> = trail.iter(&app.world).collect();
+        most = most.max(images.len());
+        for (t, sprite) in images {
+            assert!(sprite.color.a() <= 0.5);
+            if !positions.contains(&t.translation.x) {
+                positions.push(t.translation.x);
+            }
+        }
+    }
+    // a copy every 0.03s of the 0.15s dash, left behind along the way
+    assert!((3..=8).contains(&most), "{most} at once");
+    assert!(positions.windows(2).all(|w| w[1] > w[0]), "{positions:?}");
+    assert_eq!(trail.iter(&app.world).count(), 0);
+}
+
+#[test]
+fn losing_focus_stops_the_game() {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    // where the player is and the game's clock, frames from now
+    let after = |app: &mut App, frames: usize| {
+        for _ in 0..frames {
+            app.update();
+        }
+        let time = app.world.resource::<intro::GameTime>().elapsed_seconds();
+        (player_pos(app).x, time)
+    };
+    let focus = |app: &mut App, focused| {
+        app.world.send_event(bevy::window::WindowFocused {
+            window: Entity::PLACEHOLDER,
+            focused,
+        });
+        after(app, 1)
+    };
+    let away = focus(&mut app, false);
+    assert_eq!(after(&mut app, 10), away);
+    let back = focus(&mut app, true);
+    let (x, time) = after(&mut app, 10);
+    assert!(x > back.0 + 20., "ran from {} to {x}", back.0);
+    assert!(time > back.1);
+}
+
+// how far the player gets each frame of running right with a push of 8 on top, under max
+fn pushed_run(max: physics::MaxVelocity) -> Vec<f32> {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    app.world.insert_resource(max);
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    let mut steps = vec![];
+    for _ in 0..20 {
+        let x = player_pos(&mut app).x;
+        player_mut(&mut app).force.x = 8.;
+        app.update();
+        steps.push(player_pos(&mut app).x - x);
+    }
+    steps
+}
+
+#[test]
+fn stacked_speeds_are_capped() {
+    let free = pushed_run(physics::MaxVelocity::default());
+    assert!(free[10..].iter().all(|dx| *dx > 10.), "{free:?}");
+    let capped = pushed_run(physics::MaxVelocity(Vec2::new(6., f32::INFINITY)));
+    assert!(capped.iter().all(|dx| *dx <= 6. + 1e-3), "{capped:?}");
+    assert!(capped[10..].iter().all(|dx| *dx > 5.9), "{capped:?}");
+}
+
+#[test]
+fn no_collide_passes_through_just_the_one_type() {
+    // N turns off collision for the paint, which starts as squares
+    let off = [KeyCode::KeyN];
+    let square = fall_onto(open_level(), &off, 120);
+    assert!(square < -525., "got down to {square}");
+    let slope = fall_onto(floored_level(physics::Tile::SLOPER), &off, 120);
+    assert!(slope > -500., "got down to {slope}");
+    // and N again turns it back on
+    let square = fall_onto(open_level(), &[KeyCode::KeyN, KeyCode::KeyN], 120);
+    assert!(square > -475., "got down to {square}");
+}
+
+// each frame of running right on open_level with physics at scale, (how far the player moved,
+// how far physics moved it)
+fn scaled_run(scale: f32) -> Vec<(Vec2, Vec2)> {
+    let mut app = game(open_level());
+    app.insert_resource(level::PhysicsScale(scale));
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    for _ in 0..120 {
+        app.update();
+    }
+    assert!(player_mut(&mut app).grounded, "never landed");
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    (0..10)
+        .map(|_| {
+            let start = player_pos(&mut app);
+            app.update();
+            (player_pos(&mut app) - start, player_mut(&mut app).out)
+        })
+        .collect()
+}
+
+#[test]
+fn physics_scale_multiplies_what_gets_drawn() {
+    let (one, two) = (scaled_run(1.), scaled_run(2.));
+    for ((moved, out), (moved2, out2)) in one.into_iter().zip(two) {
+        assert!(out.x > 0.);
+        assert_eq!(moved, out);
+        assert_eq!(moved2, out2 * 2.);
+        // running is the same speed in physics units, so twice as fast on screen
+        assert_eq!(out2, out);
+    }
+}
+
+#[test]
+fn a_plate_is_pressed_and_released_once() {
+    // a plate in open_level's floor, right under the spawn
+    let mut level = open_level();
+    let under = level.data.len() - 11 + 5;
+    level.data[under] = physics::Tile::PLATE.0;
+    let mut app = game(level);
+    let mut plates = app.world.query::<(Entity, &physics::Tile)>();
+    let plate = (plates.iter(&app.world))
+        .find(|(_, t)| **t == physics::Tile::PLATE)
+        .unwrap()
+        .0;
+    use bevy::ecs::event::ManualEventReader;
+    let mut pressed = ManualEventReader::<level::PlatePressed>::default();
+    let mut released = ManualEventReader::<level::PlateReleased>::default();
+    // (pressed, released) events for the plate over frames
+    let mut events = |app: &mut App, frames: usize| {
+        let mut n = (0, 0);
+        for _ in 0..frames {
+            app.update();
+            let ev = app.world.resource::<Events<level::PlatePressed>>();
+            n.0 += pressed.read(ev).filter(|ev| ev.entity == plate).count();
+            let ev = app.world.resource::<Events<level::PlateReleased>>();
+            n.1 += released.read(ev).filter(|ev| ev.entity == plate).count();
+        }
+        n
+    };
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    // landing on it and standing there
+    assert_eq!(events(&mut app, 180), (1, 0));
+    // and walking off onto the floor next to it
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    assert_eq!(events(&mut app, 30), (0, 1));
+    assert!(player_pos(&mut app).x > 50.);
+}
+
+// whether a jump pressed frames after running off the end of open_level's floor with feel goes
+fn late_jump(feel: physics::GameFeel, frames: usize) -> bool {
+    // the floor stops at x = 25
+    let mut level = open_level();
+    let len = level.data.len();
+    level.data[len - 5..].fill(0);
+    let mut app = landed(level, feel);
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    while player_mut(&mut app).grounded {
+        app.update();
+    }
+    for _ in 0..frames {
+        app.update();
+    }
+    send_key(&mut app, KeyCode::Space, bevy::input::ButtonState::Pressed);
+    let y = player_pos(&mut app).y;
+    for _ in 0..5 {
+        app.update();
+    }
+    player_pos(&mut app).y > y
+}
+
+#[test]
+fn a_longer_coyote_window_takes_a_later_jump() {
+    let feel = physics::GameFeel {
+        air_jumps: 0,
+        ..physics::GameFeel::SNAPPY
+    };
+    let long = physics::GameFeel {
+        coyote: 0.5,
+        ..feel
+    };
+    // a jump right after leaving the floor goes either way, a fifth of a second after only
+    // with the longer window
+    assert!(late_jump(feel, 1));
+    assert!(!late_jump(feel, 12));
+    assert!(late_jump(long, 12));
+}
+
+#[test]
+fn a_stomped_tile_breaks_and_goes() {
+    let mut app = game(floored_level(physics::Tile::BREAK));
+    let mut breaking = app.world.query::<(Entity, &level::Breaking)>();
+    let mut tiles = app.world.query::<&physics::Tile>();
+    assert_eq!(tiles.iter(&app.world).count(), 11);
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    let mut frames = 0;
+    while breaking.iter(&app.world).count() == 0 {
+        app.update();
+        frames += 1;
+        assert!(frames < 120, "never broke");
+    }
+    // just the one under the player, which stops colliding straight away
+    let (broken, _) = breaking.single(&app.world);
+    assert_eq!(tiles.iter(&app.world).count(), 10);
+    let x = app.world.get::<Transform>(broken).unwrap().translation.x;
+    assert_eq!(x, 0.);
+    // and is gone once it's done shrinking
+    for _ in 0..15 {
+        app.update();
+    }
+    assert!(app.world.get_entity(broken).is_some());
+    for _ in 0..5 {
+        app.update();
+    }
+    assert!(app.world.get_entity(broken).is_none());
+    assert_eq!(tiles.iter(&app.world).count(), 10);
+}
+
+#[test]
+fn falling_out_of_the_map_respawns_at_rest() {
+    let mut app = game(bottomless_level());
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    let mut last = player_pos(&mut app);
+    for _ in 0..300 {
+        app.update();
+        let pos = player_pos(&mut app);
+        if pos.y > last.y {
+            // back at the spawn, with none of the fall left over
+            assert!(last.y < -1000., "came back from {last}");
+            assert_eq!(pos, level::PlayerBundle::SPAWN);
+            let v = player_mut(&mut app);
+            assert_eq!(
+                (v.force, v.out, v.ctl),
+                (Vec2::ZERO, Vec2::ZERO, Vec2::ZERO)
+            );
+            return;
+        }
+        last = pos;
+    }
+    panic!("never respawned, at {last}");
+}
+
+// the player's x each frame, dropped into a level with these winds over the floor
+fn blown(winds: &[Vec2]) -> Vec<f32> {
+    let mut level = open_level();
+    // from left of the spawn to 60
+    let area = Rect::new(-100., -475., 60., -375.);
+    level.winds.extend(winds.iter().map(|&push| (area, push)));
+    let mut app = game(level);
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    (0..240)
+        .map(|_| {
+            app.update();
+            player_pos(&mut app).x
+        })
+        .collect()
+}
+
+#[test]
+fn a_wind_zone_pushes_only_while_inside() {
+    let xs = blown(&[Vec2::new(1., 0.)]);
+    // once it falls in it drifts right a step a tick until its left edge is out, then stops
+    let end = *xs.last().unwrap();
+    assert!(end - 22.5 > 60. && end - 22.5 <= 61., "stopped at {end}");
+    let start = xs.iter().position(|x| *x != 0.).unwrap() - 1;
+    for w in xs[start..].windows(2) {
+        let inside = w[0] - 22.5 < 60.;
+        assert_eq!(w[1] - w[0], if inside { 1. } else { 0. }, "from {}", w[0]);
+    }
+    // two zones on top of each other push twice as hard
+    let twice = blown(&[Vec2::new(1., 0.), Vec2::new(1., 0.)]);
+    let drift: Vec<_> = twice.windows(2).map(|w| w[1] - w[0]).collect();
+    assert!(drift.contains(&2.));
+    assert!(drift.iter().all(|d| *d == 0. || *d == 2.), "{drift:?}");
+}
+
+#[test]
+fn a_saved_slot_loads_back_over_changes() {
+    use crate::levelselect::MapSlot;
+    use bevy::input::ButtonState::{Pressed, Released};
+    let dir = std::env::temp_dir().join(format!("baby-slots-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut app = game(open_level());
+    app.world.resource_mut::<MapSlot>().dir = dir.clone();
+    let before = snapshot(&mut app).to_text();
+    send_key(&mut app, KeyCode::ControlLeft, Pressed);
+    tap(&mut app, KeyCode::Digit2);
+    send_key(&mut app, KeyCode::ControlLeft, Released);
+    app.update();
+    assert!(dir.join("map_2.map").exists());
+    // paint over the whole floor and knock a tile out of it
+    let mut tiles = app.world.query::<(Entity, &mut physics::Tile)>();
+    let gone = tiles.iter(&app.world).next().unwrap().0;
+    for (_, mut tile) in tiles.iter_mut(&mut app.world) {
+        *tile = physics::Tile::HAZARD;
+    }
+    app.world.despawn(gone);
+    assert_ne!(snapshot(&mut app).to_text(), before);
+    send_key(&mut app, KeyCode::ControlLeft, Pressed);
+    send_key(&mut app, KeyCode::ShiftLeft, Pressed);
+    tap(&mut app, KeyCode::Digit2);
+    app.update();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(snapshot(&mut app).to_text(), before);
+    assert_eq!(app.world.resource::<MapSlot>().last, Some(2));
+}
+
+#[test]
+fn a_bigger_player_is_drawn_and_collides_bigger() {
+    let mut app = headless();
+    app.insert_resource(open_level());
+    app.insert_resource(level::PlayerSize(Vec2::new(30., 90.)));
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Game);
+    app.update();
+    let mut player = (app.world).query_filtered::<(&Transform, &Sprite), With<level::Control>>();
+    let (t, sprite) = player.single(&app.world);
+    // the sprite keeps its proportions to the box, 1.2 by 1.4 of it
+    let drawn = sprite.custom_size.unwrap() * t.scale.xy();
+    assert!((drawn - Vec2::new(36., 126.)).length() < 1e-3, "{drawn}");
+    // and it lands with the bottom of the taller box on the floor, whose top is at -475
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    for _ in 0..120 {
+        app.update();
+    }
+    assert!(player_mut(&mut app).grounded);
+    let bottom = player_pos(&mut app).y - 45.;
+    assert!((bottom - -475.).abs() <= 1., "bottom at {bottom}");
+}
+
+// how far the player goes in 20 frames holding left, then right as well
+fn left_then_right(socd: level::Socd) -> f32 {
+    use bevy::input::ButtonState::Pressed;
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    app.insert_resource(socd);
+    send_key(&mut app, KeyCode::ArrowLeft, Pressed);
+    app.update();
+    send_key(&mut app, KeyCode::ArrowRight, Pressed);
+    app.update();
+    let from = player_pos(&mut app).x;
+    for _ in 0..20 {
+        app.update();
+    }
+    player_pos(&mut app).x - from
+}
+
+#[test]
+fn opposite_directions_resolve_by_socd_mode() {
+    assert!(left_then_right(level::Socd::LastWins) > 10.);
+    assert!(left_then_right(level::Socd::FirstWins) < -10.);
+    assert_eq!(left_then_right(level::Socd::Neutral), 0.);
+}
+
+// where the player ends up in puzzle mode after pressing right, with a wall there or not
+fn puzzle_right(wall: bool) -> Vec2 {
+    let mut level = open_level();
+    // the spawn at (0, 0) is cell (5, 10) from the bottom left, its right hand neighbour is in
+    // the 10th row down
+    if wall {
+        level.data[9 * 11 + 6] = physics::Tile::SQUARE.0;
+    }
+    let mut app = headless();
+    app.insert_resource(level);
+    app.insert_resource(level::PuzzleMode(true));
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Game);
+    app.update();
+    tap(&mut app, KeyCode::ArrowRight);
+    for _ in 0..30 {
+        app.update();
+    }
+    player_pos(&mut app)
+}
+
+#[test]
+fn puzzle_mode_moves_a_whole_tile_unless_blocked() {
+    assert_eq!(puzzle_right(false), Vec2::new(physics::Tile::SZ, 0.));
+    assert_eq!(puzzle_right(true), Vec2::ZERO);
+}
+
+#[test]
+fn a_camera_trigger_takes_over_until_the_player_leaves() {
+    use bevy::input::ButtonState::Pressed;
+    let target = Vec2::new(-200., 200.);
+    let mut level = open_level();
+    level
+        .cameras
+        .push((Rect::new(50., -475., 120., -375.), target, 2.));
+    let mut app = landed(level, physics::GameFeel::CLASSIC);
+    // walking right through it, in from x = 27.5 and out past 142.5
+    send_key(&mut app, KeyCode::ArrowRight, Pressed);
+    let mut last: Option<Transform> = None;
+    let mut followed = vec![];
+    for _ in 0..40 {
+        app.update();
+        let mut cam = app
+            .world
+            .query_filtered::<&Transform, With<intro::MainCamera>>();
+        let cam = *cam.single(&app.world);
+        let player = player_pos(&mut app);
+        let cued = app.world.resource::<level::CameraCue>().active.is_some();
+        assert_eq!(cued, player.x > 27.5 && player.x < 142.5, "at {player}");
+        if let Some(last) = last.filter(|_| cued) {
+            // heading for the cue and zooming out to it
+            let to = |t: &Transform| t.translation.xy().distance(target);
+            assert!(
+                to(&cam) < to(&last),
+                "{} after {}",
+                cam.translation,
+                last.translation
+            );
+            assert!(cam.scale.x > last.scale.x && cam.scale.x < 2.);
+        }
+        if !cued && player.x > 142.5 {
+            followed.push(player.x - cam.translation.x);
+            assert!(cam.scale.x < last.unwrap().scale.x, "zooming back in");
+        }
+        last = Some(cam);
+    }
+    // back to following the player along, however far behind it the camera sits
+    assert!(followed.len() > 5);
+    assert!(
+        followed.windows(2).all(|w| (w[0] - w[1]).abs() < 1e-3),
+        "{followed:?}"
+    );
+}
+
+#[test]
+fn each_kind_of_thing_spawns_on_its_own_layer() {
+    use level::Layer;
+    let mut level = open_level();
+    let area = Rect::new(-100., -400., 0., -300.);
+    level.winds.push((area, Vec2::X));
+    level.cameras.push((area, Vec2::ZERO, 2.));
+    level.platforms.push((area, Vec2::X, 1.));
+    let mut app = game(level);
+    app.update();
+    fn zs<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> Vec<f32> {
+        let mut q = app.world.query_filtered::<&Transform, F>();
+        q.iter(&app.world).map(|t| t.translation.z).collect()
+    }
+    let on = |zs: Vec<f32>, layer: Layer, n: usize| {
+        assert_eq!(zs, vec![layer.z(); n], "{layer:?}");
+    };
+    on(zs::<With<physics::Tile>>(&mut app), Layer::Tile, 11);
+    on(zs::<With<level::Platform>>(&mut app), Layer::Tile, 1);
+    on(zs::<With<level::Wind>>(&mut app), Layer::Zone, 1);
+    on(zs::<With<level::CameraTrigger>>(&mut app), Layer::Zone, 1);
+    on(zs::<With<level::Control>>(&mut app), Layer::Player, 1);
+    on(zs::<With<level::BackgroundFill>>(&mut app), Layer::Fill, 1);
+    // far enough apart that nudging something a tenth in front of or behind its layer can't
+    // cross into the next
+    let layers = [
+        Layer::Fill,
+        Layer::Backdrop,
+        Layer::Background,
+        Layer::Zone,
+        Layer::Tile,
+        Layer::Ghost,
+        Layer::Player,
+        Layer::Foreground,
+        Layer::Overlay,
+    ];
+    for w in layers.windows(2) {
+        assert!(w[1].z() - w[0].z() >= 0.5, "{:?} and {:?}", w[0], w[1]);
+    }
+}
+
+#[test]
+fn a_platform_heading_down_keeps_the_player_on_it() {
+    // a platform right under the spawn dropping 300 and back each second, at the start of the
+    // way down it pulls away far quicker than the player falls
+    let mut level = open_level();
+    let area = Rect::new(-50., -72.5, 50., -22.5);
+    level.platforms.push((area, Vec2::new(0., -300.), 1.));
+    let mut app = game(level);
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    let mut gap = || {
+        app.update();
+        let mut platform = app
+            .world
+            .query_filtered::<&Transform, With<level::Platform>>();
+        let top = platform.single(&app.world).translation.y + area.half_size().y;
+        let gap = player_pos(&mut app).y - 22.5 - top;
+        (gap, player_mut(&mut app).platform.is_some())
+    };
+    // it drops out from under the player to begin with, who catches up on its way back up
+    let gaps: Vec<_> = (0..60).map(|_| gap()).collect();
+    assert!(
+        gaps[..10].iter().all(|(gap, on)| *gap > 0.1 && !on),
+        "{gaps:?}"
+    );
+    assert!(gaps.last().unwrap().1, "{gaps:?}");
+    // and from there rides it all the way down and up again, a skin above it
+    for frame in 0..60 {
+        let (gap, on) = gap();
+        assert!(on && (gap - physics::SKIN).abs() < 1e-3, "{frame}: {gap}");
+    }
+}
+
+#[test]
+fn a_second_dash_waits_for_the_cooldown() {
+    let feel = physics::GameFeel {
+        dash_cooldown: 0.5,
+        ..physics::GameFeel::CLASSIC
+    };
+    let mut app = landed(open_level(), feel);
+    // how far a tap of X takes the player, a fifth of a second later
+    let dash = |app: &mut App| {
+        let x = player_pos(app).x;
+        tap(app, KeyCode::KeyX);
+        for _ in 0..10 {
+            app.update();
+        }
+        player_pos(app).x - x
+    };
+    assert!(dash(&mut app) > 100.);
+    assert!(dash(&mut app).abs() < 1e-3);
+    // the cooldown is over by now
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(dash(&mut app) > 100.);
+}
+
+#[test]
+fn the_debug_text_sits_on_a_dark_panel() {
+    let mut app = game(open_level());
+    app.update();
+    let mut text = (app.world).query_filtered::<&Parent, (With<level::DebugUi>, With<Text>)>();
+    let panel = text.single(&app.world).get();
+    // a child is drawn over its parent, so the panel is behind the text
+    let color = app.world.get::<BackgroundColor>(panel).unwrap().0;
+    assert!(color.a() > 0.5 && color.r() < 0.1, "{color:?}");
+}
+
+#[test]
+fn game_sets_run_in_their_order() {
+    #[derive(Resource, Default)]
+    struct Ran(Vec<level::GameSet>);
+    let mut app = game(open_level());
+    app.init_resource::<Ran>();
+    // added backwards and with nothing between them, it's only the sets that order them
+    for set in [
+        level::GameSet::Camera,
+        level::GameSet::Move,
+        level::GameSet::Physics,
+        level::GameSet::Input,
+    ] {
+        app.add_systems(
+            Update,
+            (move |mut ran: ResMut<Ran>| ran.0.push(set)).in_set(set),
+        );
+    }
+    app.update();
+    app.update();
+    use level::GameSet::*;
+    let frame = [Input, Physics, Move, Camera];
+    assert_eq!(app.world.resource::<Ran>().0, [frame, frame].concat());
+}
+
+#[test]
+fn skipping_the_intro_starts_in_the_game_without_it() {
+    let mut app = headless_with(intro::SkipIntro(true));
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(
+        app.world.resource::<State<AppState>>().get(),
+        &AppState::Game
+    );
+    assert_eq!(
+        app.world
+            .query::<&level::Control>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    // nothing of the intro was loaded or set up
+    assert!(!app.world.contains_resource::<intro::CueSequencer>());
+    let mut names = app.world.query::<&Name>();
+    let intro = ["baby", "city", "thump"];
+    assert!(!names
+        .iter(&app.world)
+        .any(|name| intro.contains(&name.as_str())));
+    let mut sounds = app.world.query::<&Handle<AudioSource>>();
+    assert_eq!(sounds.iter(&app.world).count(), 0);
+}
+
+#[test]
+fn edit_mode_holds_the_player_while_tiles_are_placed() {
+    let mut app = headless();
+    // the editor needs a window to put the cursor in, and a camera that knows where it's drawing
+    app.world
+        .spawn((Window::default(), bevy::window::PrimaryWindow));
+    app.init_resource::<bevy::render::camera::ManualTextureViews>()
+        .add_systems(
+            PostUpdate,
+            bevy::render::camera::camera_system::<OrthographicProjection>,
+        );
+    app.insert_resource(open_level());
+    (app.world.resource_mut::<NextState<AppState>>()).set(AppState::Game);
+    app.update();
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    for _ in 0..120 {
+        app.update();
+    }
+    let mut tiles = app.world.query::<&physics::Tile>();
+    let count = tiles.iter(&app.world).count();
+
+    tap(&mut app, KeyCode::KeyE);
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    let held = player_pos(&mut app);
+    // up in the empty sky above the floor
+    let mut win = app.world.query::<&mut Window>();
+    let cursor = Vec2::new(400., 100.);
+    win.single_mut(&mut app.world)
+        .set_cursor_position(Some(cursor));
+    app.world.send_event(bevy::input::mouse::MouseButtonInput {
+        button: MouseButton::Left,
+        state: bevy::input::ButtonState::Pressed,
+        window: Entity::PLACEHOLDER,
+    });
+    for _ in 0..20 {
+        app.update();
+    }
+    assert_eq!(player_pos(&mut app), held);
+    assert_eq!(tiles.iter(&app.world).count(), count + 1);
+
+    // and back to playing, from right where it was
+    tap(&mut app, KeyCode::KeyE);
+    for _ in 0..20 {
+        app.update();
+    }
+    assert!(player_pos(&mut app).x > held.x + 20.);
+}
+
+#[test]
+fn a_long_frame_runs_at_most_max_ticks() {
+    let path = std::env::temp_dir().join(format!("baby-longframe-{}.jsonl", std::process::id()));
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    app.insert_resource(level::PhysicsLog::create(&path).unwrap());
+    // running, since a player at rest isn't stepped
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    // a whole second stalled is 60 ticks owed
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(1)));
+    app.update();
+    app.world.remove_resource::<level::PhysicsLog>();
+    let text = std::fs::read_to_string(&path).unwrap();
+    _ = std::fs::remove_file(&path);
+    let tick = app.world.resource::<level::PhysicsTick>();
+    assert_eq!(text.lines().count(), tick.max_ticks as usize, "{text}");
+    // and the rest aren't owed to the next frame either
+    assert!(tick.rem < 1., "{}", tick.rem);
+}
+
+// the speeds of the footsteps taken walking right for half a second, with the pitch seeded
+fn footstep_speeds(seed: u64) -> Vec<f32> {
+    let mut app = landed(open_level(), physics::GameFeel::CLASSIC);
+    app.insert_resource(level::Rng::new(seed));
+    (app.world.resource_mut::<ButtonInput<KeyCode>>()).press(KeyCode::ArrowRight);
+    for _ in 0..30 {
+        app.update();
+    }
+    // the landing's thump is around 1, footsteps around 1.5
+    let mut speeds = sound_speeds(&mut app, "sounds/thump.wav");
+    speeds.retain(|&speed| speed > 1.2);
+    speeds
+}
+
+#[test]
+fn footsteps_vary_in_pitch_the_same_way_from_a_seed() {
+    let speeds = footstep_speeds(7);
+    assert!(speeds.len() >= 3, "{speeds:?}");
+    assert!(
+        speeds.iter().all(|s| (1.35..=1.65).contains(s)),
+        "{speeds:?}"
+    );
+    assert!(speeds.windows(2).any(|w| w[0] != w[1]), "{speeds:?}");
+    assert_eq!(footstep_speeds(7), speeds);
+    assert_ne!(footstep_speeds(8), speeds);
+}
+
+// each frame's move of the player kicked right into a ramp of 5 slopes going up from beside the
+// spawn, until it stops going right, with merge set as G leaves it
+fn ramp_climb(merge: bool) -> Vec<Vec2> {
+    let mut level = open_level();
+    // cell (x, y) from the bottom left, with the spawn at (5, 10)
+    let mut set = |x: usize, y: usize, tile: physics::Tile| level.data[(19 - y) * 11 + x] = tile.0;
+    for i in 1..=5 {
+        set(5 + i, i, physics::Tile::SLOPER);
+        for y in 1..i {
+            set(5 + i, y, physics::Tile::SQUARE);
+        }
+    }
+    let mut app = headless();
+    app.insert_resource(level);
+    app.world.resource_mut::<level::TileCache>().merge = merge;
+    (app.world.resource_mut::<NextState<AppState>>()).set(AppState::Game);
+    app.update();
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    for _ in 0..120 {
+        app.update();
+    }
+    player_mut(&mut app).force.x = 12.;
+    let mut moves = vec![];
+    let mut last = player_pos(&mut app);
+    for _ in 0..120 {
+        app.update();
+        let pos = player_pos(&mut app);
+        if pos.x <= last.x {
+            break;
+        }
+        moves.push(pos - last);
+        last = pos;
+    }
+    moves
+}
+
+#[test]
+fn a_merged_ramp_is_climbed_without_a_bump_at_each_tile() {
+    // past three of the corners between slopes. the first move is partly along the floor, and the
+    // last over the top
+    let moves = ramp_climb(true);
+    let climb = &moves[1..moves.len() - 1];
+    let climbed: f32 = climb.iter().map(|m| m.y).sum();
+    assert!(climbed > 150., "only got {climbed} up");
+    // straight up the diagonal, slowing by the same amount every frame past each tile's corner
+    assert!(climb.iter().all(|m| (m.x - m.y).abs() < 1e-3), "{climb:?}");
+    let slowing: Vec<_> = climb.windows(2).map(|w| w[0].y - w[1].y).collect();
+    assert!(
+        slowing.iter().all(|s| (s - slowing[0]).abs() < 1e-3),
+        "{slowing:?}"
+    );
+    // one slope at a time, the corner at the top of the first stops it dead
+    let tiles: f32 = ramp_climb(false).iter().map(|m| m.y).sum();
+    assert!(tiles < 60., "got {tiles} up");
+}
+
+#[test]
+fn the_legend_lists_every_tile_id_in_both_palettes() {
+    let mut level = open_level();
+    level.palette[3] = Color::rgb(0.1, 0.2, 0.3);
+    let mut app = game(level);
+    app.update();
+    let mut legend = app
+        .world
+        .query_filtered::<Entity, With<level::PaletteLegend>>();
+    let legend = legend.single(&app.world);
+    let rows = app.world.get::<Children>(legend).unwrap();
+    assert_eq!(rows.len(), physics::Tile::NUM);
+    let palette = app.world.resource::<map::Level>().palette;
+    for (id, row) in rows.iter().enumerate() {
+        let row = app.world.get::<Children>(*row).unwrap();
+        let swatch = |i: usize| app.world.get::<BackgroundColor>(row[i]).unwrap().0;
+        let [r, g, b] = map::BMP_PALETTE[id];
+        assert_eq!(swatch(0), palette[id], "{id}");
+        assert_eq!(swatch(1), Color::rgb_u8(r, g, b), "{id}");
+        let text = &app.world.get::<Text>(row[2]).unwrap().sections[0].value;
+        assert_eq!(*text, format!("{id} {}", physics::Tile(id as u8).kind()));
+    }
+    // hidden until L shows it
+    let shown = |app: &App| app.world.get::<Visibility>(legend) != Some(&Visibility::Hidden);
+    assert!(!shown(&app));
+    tap(&mut app, KeyCode::KeyL);
+    assert!(shown(&app));
+}
+
+// the frames a platform sweeping along open_level's floor crushes the player on, with or
+// without a wall right of the spawn for it to crush them against
+fn crushes(wall: bool) -> Vec<usize> {
+    let mut level = open_level();
+    if wall {
+        // cells (7, 1) and (7, 2) from the bottom left, the spawn is (5, 10)
+        level.data[18 * 11 + 7] = physics::Tile::SQUARE.0;
+        level.data[17 * 11 + 7] = physics::Tile::SQUARE.0;
+    }
+    // out to 100 over 3 seconds at the player's height, reaching them a while after they land
+    let area = Rect::new(-250., -470., -200., -435.);
+    level.platforms.push((area, Vec2::new(300., 0.), 6.));
+    let mut app = game(level);
+    player_mut(&mut app).force = Vec2::new(0., -0.01);
+    let mut crushed = bevy::ecs::event::ManualEventReader::<level::Crushed>::default();
+    let mut frames = vec![];
+    for frame in 0..180 {
+        app.update();
+        let events = app.world.resource::<Events<level::Crushed>>();
+        if crushed.read(events).count() > 0 {
+            frames.push(frame);
+            // and starts over from the spawn
+            assert_eq!(player_pos(&mut app), Vec2::ZERO);
+        }
+    }
+    frames
+}
+
+// two players dropped overlapping onto open_level's floor, which is a hazard from cell (7, 0)
+// on, where they both end up and their health
+fn drop_two_players(collide: bool) -> [(Vec2, f32); 2] {
+    let mut level = open_level();
+    for x in 7..11 {
+        level.data[19 * 11 + x] = physics::Tile::HAZARD.0;
+    }
+    let mut app = game(level);
+    app.insert_resource(level::PlayerCollide(collide));
+    let size = level::PlayerSize(Vec2::splat(45.));
+    let second = (app.world)
+        .spawn(level::PlayerBundle::new(
+            Vec2::new(30., 5.),
+            size,
+            Vec2::ONE,
+            Handle::default(),
+        ))
+        .id();
+    let mut players = (app.world).query_filtered::<Entity, With<level::Control>>();
+    let first = (players.iter(&app.world)).find(|e| *e != second).unwrap();
+    // both fall and land, then walk right a bit, onto the hazard if they're pushed apart
+    for e in [first, second] {
+        app.world.get_mut::<physics::Movement>(e).unwrap().force = Vec2::new(0., -0.01);
+    }
+    for frame in 0..120 {
+        let mut kbd = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        match frame {
+            60 => kbd.press(KeyCode::ArrowRight),
+            70 => kbd.release(KeyCode::ArrowRight),
+            _ => {}
+        }
+        app.update();
+    }
+    [first, second].map(|e| {
+        let t = app.world.get::<Transform>(e).unwrap();
+        let hp = app.world.get::<level::Health>(e).unwrap();
+        (t.translation.xy(), hp.0)
+    })
+}
+
+#[test]
+fn two_players_collide_and_take_their_own_damage() {
+    let [(first, first_hp), (second, second_hp)] = drop_two_players(true);
+    // side by side on the floor, a player width apart
+    assert!(second.x - first.x >= 45. - 1e-3, "{first} {second}");
+    assert!((first.y - -452.).abs() < 1. && (second.y - -452.).abs() < 1.);
+    // only the second is standing on the hazard, and only the second is hurt
+    assert!(
+        first.x + 22.5 < 75. && second.x + 22.5 > 75.,
+        "{first} {second}"
+    );
+    assert!(second_hp < first_hp, "{first_hp} {second_hp}");
+    // passing through each other they stay overlapped
+    let [(first, _), (second, _)] = drop_two_players(false);
+    assert!((second.x - first.x).abs() < 45., "{first} {second}");
+}
+
+#[test]
+fn a_platform_crushing_the_player_into_a_wall_kills_them() {
+    assert_eq!(crushes(true).len(), 1);
+    // with nowhere to be crushed against, it just pushes them along
+    assert!(crushes(false).is_empty());
+}
+
+#[test]
+fn holding_tab_cycles_paint_once_per_cooldown() {
+    let mut app = game(open_level());
+    let paint = |app: &App| app.world.resource::<level::Paint>().0;
+    send_key(&mut app, KeyCode::Tab, bevy::input::ButtonState::Pressed);
+    let mut last = paint(&app);
+    let mut changed = vec![];
+    for frame in 0..60 {
+        app.update();
+        if paint(&app) != last {
+            changed.push(frame);
+            last = paint(&app);
+        }
+    }
+    // right away on the press, then every 0.2s, 12 frames
+    assert_eq!(changed[0], 0);
+    let gaps: Vec<_> = changed.windows(2).map(|w| w[1] - w[0]).collect();
+    assert!(
+        gaps.len() >= 3 && gaps.iter().all(|&gap| gap == 12),
+        "{changed:?}"
+    );
+}
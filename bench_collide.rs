@@ -0,0 +1,122 @@
+// times physics::step against the real map, without starting up the rest of the game
+//
+//     cargo run --release --bin bench_collide [iterations]
+use std::hint::black_box;
+use std::time::Instant;
+
+use bevy::math::{bounding::Aabb2d, Vec2};
+
+use grossbaby::map;
+use grossbaby::physics::{self, GameFeel, Movement, Tile};
+
+// (name, start position, player input)
+// the player is put back at the start every second of game time so it keeps doing the same thing
+const TRAJECTORIES: &[(&str, Vec2, Vec2)] = &[
+    ("run along floor", Vec2::new(0., -352.5), Vec2::new(5., 0.)),
+    ("fall through open space", Vec2::new(0., 0.), Vec2::ZERO),
+    ("walk up stairs", Vec2::new(-50., 4030.), Vec2::new(5., 0.)),
+];
+
+const HALF_SIZE: Vec2 = Vec2::splat(45. / 2.);
+
+fn map_tiles() -> Vec<(Tile, Aabb2d)> {
+    (map::Level::builtin().tiles())
+        .map(|(t, pos)| (t, Aabb2d::new(pos, Vec2::splat(Tile::SZ / 2.))))
+        .collect()
+}
+
+// a second of steps from start with ctl held
+fn after_a_second(start: Vec2, ctl: Vec2, tiles: &[(Tile, Aabb2d)]) -> (Aabb2d, Movement) {
+    let mut v = Movement {
+        ctl,
+        ..Default::default()
+    };
+    let end = (0..60).fold(Aabb2d::new(start, HALF_SIZE), |aabb, _| {
+        physics::step(aabb, &mut v, &GameFeel::CLASSIC, tiles).aabb
+    });
+    (end, v)
+}
+
+fn main() {
+    let iters: u32 = match std::env::args().nth(1).map(|n| n.parse()) {
+        None => 100_000,
+        Some(Ok(n)) => n,
+        Some(Err(e)) => {
+            eprintln!("usage: bench_collide [iterations]: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let tiles = map_tiles();
+    let feel = GameFeel::CLASSIC;
+    println!("{} tiles, {iters} iterations", tiles.len());
+
+    // where each one ends up after a second, to check the physics still does what the name says
+    for &(name, start, ctl) in TRAJECTORIES {
+        let (end, v) = after_a_second(start, ctl, &tiles);
+        println!(
+            "{name:>24}: ends at {}, grounded {}",
+            (end.min + end.max) / 2.,
+            v.grounded
+        );
+    }
+
+    for &(name, start, ctl) in TRAJECTORIES {
+        let mut aabb = Aabb2d::new(start, HALF_SIZE);
+        let mut v = Movement {
+            ctl,
+            ..Default::default()
+        };
+        let mut contacts = 0;
+        let now = Instant::now();
+        for i in 0..iters {
+            if i % 60 == 0 {
+                aabb = Aabb2d::new(start, HALF_SIZE);
+                v.force = Vec2::ZERO;
+            }
+            let step = physics::step(aabb, &mut v, &feel, black_box(&tiles));
+            contacts += step.contacts.len();
+            aabb = black_box(step.aabb);
+        }
+        let elapsed = now.elapsed();
+        println!(
+            "{name:>24}: {:>10.3?} total, {:>8.3?} per step, {contacts} contacts",
+            elapsed,
+            elapsed / iters,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trajectories_do_what_they_say() {
+        let tiles = map_tiles();
+        let ends: Vec<_> = (TRAJECTORIES.iter())
+            .map(|&(_, start, ctl)| (start, after_a_second(start, ctl, &tiles)))
+            .collect();
+        let [run, fall, stairs] = &ends[..] else {
+            panic!("{} trajectories", ends.len());
+        };
+        let center = |a: &Aabb2d| (a.min + a.max) / 2.;
+
+        let (start, (end, v)) = run;
+        assert!(v.grounded);
+        assert!(center(end).x > start.x + 100., "ran to {}", center(end));
+        assert!(
+            (center(end).y - start.y).abs() < 5.,
+            "ran to {}",
+            center(end)
+        );
+
+        let (start, (end, _)) = fall;
+        assert!(center(end).y < start.y - 100., "fell to {}", center(end));
+        assert_eq!(center(end).x, start.x);
+
+        let (start, (end, _)) = stairs;
+        assert!(center(end).x > start.x, "climbed to {}", center(end));
+        assert!(center(end).y > start.y, "climbed to {}", center(end));
+    }
+}